@@ -0,0 +1,53 @@
+//! Compares the throughput of parsing records out of a slice against parsing
+//! the same records through a [`BufReader`].
+//!
+//! Records read through a `BufReader` cannot be borrowed from the reader's
+//! internal buffer so they must be copied. This benchmark measures how much
+//! that copy costs.
+//!
+//! Run with `cargo bench --bench bufreader`.
+
+use std::hint::black_box;
+use std::io::BufReader;
+use std::time::{Duration, Instant};
+
+use perf_event_data::endian::Little;
+use perf_event_data::parse::{ParseBuf, ParseConfig, Parser};
+use perf_event_data::Record;
+
+const RECORDS: usize = 100_000;
+const ITERATIONS: usize = 20;
+
+fn parse_all<'p, B: ParseBuf<'p>>(data: B) -> usize {
+    let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+    let mut count = 0;
+
+    while let Ok(record) = parser.parse::<Record>() {
+        black_box(record);
+        count += 1;
+    }
+
+    count
+}
+
+fn bench(name: &str, data: &[u8], mut func: impl FnMut() -> usize) {
+    let mut best = Duration::MAX;
+
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        let count = func();
+        best = best.min(start.elapsed());
+
+        assert_eq!(count, RECORDS);
+    }
+
+    let throughput = data.len() as f64 / best.as_secs_f64() / (1024.0 * 1024.0);
+    println!("{name:>12}: {best:>10.2?} ({throughput:.1} MiB/s)");
+}
+
+fn main() {
+    let data = perf_event_data::doctest::MMAP.repeat(RECORDS);
+
+    bench("slice", &data, || parse_all(&data[..]));
+    bench("bufreader", &data, || parse_all(BufReader::new(&data[..])));
+}
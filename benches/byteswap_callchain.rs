@@ -0,0 +1,70 @@
+//! Measures the cost of parsing a long callchain from non-native endian data.
+//!
+//! When the data does not have the native endianness a callchain can't be
+//! borrowed directly from the source buffer and has to be converted instead.
+//! This compares converting the whole array in bulk, which is what the record
+//! parsers do, against the fallback of parsing each element individually.
+//!
+//! Run with `cargo bench --bench byteswap_callchain`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use perf_event_data::endian::Big;
+use perf_event_data::parse::{ParseConfig, Parser};
+use perf_event_data::{Record, SampleFlags};
+
+const CALLCHAIN_LEN: usize = 1000;
+const ITERATIONS: usize = 1000;
+
+/// Build a big-endian SAMPLE record containing only a callchain.
+fn sample_record() -> Vec<u8> {
+    let size = 8 + 8 + 8 * CALLCHAIN_LEN;
+
+    let mut data = Vec::with_capacity(size);
+    data.extend_from_slice(&9u32.to_be_bytes()); // type (SAMPLE)
+    data.extend_from_slice(&0u16.to_be_bytes()); // misc
+    data.extend_from_slice(&(size as u16).to_be_bytes()); // size
+    data.extend_from_slice(&(CALLCHAIN_LEN as u64).to_be_bytes()); // nr
+    for ip in 0..CALLCHAIN_LEN as u64 {
+        data.extend_from_slice(&(0xFFFF_FFFF_8100_0000 + ip).to_be_bytes());
+    }
+
+    data
+}
+
+fn bench(name: &str, mut func: impl FnMut()) {
+    let mut best = Duration::MAX;
+
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        func();
+        best = best.min(start.elapsed());
+    }
+
+    let per_entry = best / CALLCHAIN_LEN as u32;
+    println!("{name:>16}: {best:>10.2?} ({per_entry:.2?}/entry)");
+}
+
+fn main() {
+    let data = sample_record();
+    let config = ParseConfig::<Big>::default().with_sample_type(SampleFlags::CALLCHAIN);
+
+    bench("record", || {
+        let mut parser = Parser::new(&data[..], config.clone());
+        black_box(parser.parse::<Record>().unwrap());
+    });
+
+    // Skip over the header and the callchain length.
+    let callchain = &data[16..];
+
+    bench("bulk", || {
+        let mut parser = Parser::new(callchain, config.clone());
+        black_box(parser.parse_u64_slice(CALLCHAIN_LEN).unwrap());
+    });
+
+    bench("per-element", || {
+        let mut parser = Parser::new(callchain, config.clone());
+        black_box(parser.parse_repeated::<u64>(CALLCHAIN_LEN).unwrap());
+    });
+}
@@ -0,0 +1,71 @@
+//! Measures the throughput of parsing a stream of small records.
+//!
+//! Records such as EXIT are only a few bytes long so any fixed per-record
+//! overhead within the parser (e.g. splitting off the `sample_id` trailer)
+//! makes up a significant fraction of the time spent parsing them. The
+//! records here are parsed both with and without `sample_id_all` set so the
+//! cost of the trailer can be compared against the fast path.
+//!
+//! Run with `cargo bench --bench small_records`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use perf_event_data::endian::Little;
+use perf_event_data::parse::{ParseConfig, Parser};
+use perf_event_data::Record;
+use perf_event_open_sys::bindings::perf_event_attr;
+
+const RECORDS: usize = 100_000;
+const ITERATIONS: usize = 20;
+
+#[rustfmt::skip]
+const EXIT: &[u8] = &[
+    0x04, 0x00, 0x00, 0x00, // type (EXIT)
+    0x00, 0x00, // misc
+    0x20, 0x00, // size
+    0x16, 0x4C, 0x01, 0x00, // pid
+    0x15, 0x4C, 0x01, 0x00, // ppid
+    0x16, 0x4C, 0x01, 0x00, // tid
+    0x15, 0x4C, 0x01, 0x00, // ptid
+    0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // time
+];
+
+fn parse_all(data: &[u8], config: &ParseConfig<Little>) -> usize {
+    let mut parser = Parser::new(data, config.clone());
+    let mut count = 0;
+
+    while let Ok(record) = parser.parse::<Record>() {
+        black_box(record);
+        count += 1;
+    }
+
+    count
+}
+
+fn bench(name: &str, data: &[u8], config: ParseConfig<Little>) {
+    let mut best = Duration::MAX;
+
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        let count = parse_all(data, &config);
+        best = best.min(start.elapsed());
+
+        assert_eq!(count, RECORDS);
+    }
+
+    let per_record = best / RECORDS as u32;
+    println!("{name:>16}: {best:>10.2?} ({per_record:.2?}/record)");
+}
+
+fn main() {
+    let data = EXIT.repeat(RECORDS);
+
+    bench("exit", &data, ParseConfig::default());
+
+    // With sample_id_all set but an empty sample_type the trailer is empty, so
+    // the records are identical but take the slower path through the parser.
+    let mut attr = perf_event_attr::default();
+    attr.set_sample_id_all(1);
+    bench("exit (id_all)", &data, ParseConfig::from(attr));
+}
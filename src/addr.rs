@@ -0,0 +1,147 @@
+use std::fmt;
+
+use crate::prelude::*;
+use crate::util::fmt::HexAddr;
+
+/// A virtual memory address.
+///
+/// Fields such as [`Sample::ip`], [`Sample::addr`], and [`Mmap::addr`] are
+/// all virtual addresses, while [`Sample::phys_addr`] is a physical address.
+/// Keeping them as separate types makes it harder to accidentally look up a
+/// physical address in something that is keyed by virtual address (or vice
+/// versa).
+///
+/// The `Debug` impl prints the address in hex.
+///
+/// [`Sample::ip`]: crate::Sample::ip
+/// [`Sample::addr`]: crate::Sample::addr
+/// [`Sample::phys_addr`]: crate::Sample::phys_addr
+/// [`Mmap::addr`]: crate::Mmap::addr
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct VirtAddr(u64);
+
+impl VirtAddr {
+    /// Create a `VirtAddr` from its raw value.
+    pub const fn new(addr: u64) -> Self {
+        Self(addr)
+    }
+
+    /// The raw value of this address.
+    pub const fn get(&self) -> u64 {
+        self.0
+    }
+
+    /// Whether this address is within the kernel half of the address space.
+    ///
+    /// This is a heuristic based on the top bit of the address, which is how
+    /// the kernel splits the address space on x86_64, aarch64, and most other
+    /// 64-bit architectures. It does not hold for 32-bit targets or for
+    /// architectures with separate user and kernel address spaces. Prefer
+    /// [`Sample::cpu_mode`](crate::Sample::cpu_mode) when it is available.
+    pub const fn is_kernel_space(&self) -> bool {
+        self.0 & (1 << 63) != 0
+    }
+
+    /// Whether this address is within the user half of the address space.
+    ///
+    /// This is the opposite of [`is_kernel_space`](Self::is_kernel_space) and
+    /// is subject to the same caveats.
+    pub const fn is_user_space(&self) -> bool {
+        !self.is_kernel_space()
+    }
+}
+
+/// A physical memory address.
+///
+/// This is the type of [`Sample::phys_addr`]. See [`VirtAddr`] for why the
+/// two are kept distinct.
+///
+/// The `Debug` impl prints the address in hex.
+///
+/// [`Sample::phys_addr`]: crate::Sample::phys_addr
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PhysAddr(u64);
+
+impl PhysAddr {
+    /// Create a `PhysAddr` from its raw value.
+    pub const fn new(addr: u64) -> Self {
+        Self(addr)
+    }
+
+    /// The raw value of this address.
+    pub const fn get(&self) -> u64 {
+        self.0
+    }
+}
+
+macro_rules! addr_impls {
+    ($ty:ident) => {
+        impl From<u64> for $ty {
+            fn from(addr: u64) -> Self {
+                Self::new(addr)
+            }
+        }
+
+        impl From<$ty> for u64 {
+            fn from(addr: $ty) -> Self {
+                addr.get()
+            }
+        }
+
+        impl<'p> Parse<'p> for $ty {
+            fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+            where
+                E: Endian,
+                B: ParseBuf<'p>,
+            {
+                Ok(Self::new(p.parse()?))
+            }
+        }
+
+        impl fmt::Debug for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_tuple(stringify!($ty))
+                    .field(&HexAddr(self.0))
+                    .finish()
+            }
+        }
+
+        impl fmt::LowerHex for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::LowerHex::fmt(&self.0, f)
+            }
+        }
+
+        impl fmt::UpperHex for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::UpperHex::fmt(&self.0, f)
+            }
+        }
+    };
+}
+
+addr_impls!(VirtAddr);
+addr_impls!(PhysAddr);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernel_space() {
+        assert!(VirtAddr::new(0xFFFF_FFFF_8100_0000).is_kernel_space());
+        assert!(VirtAddr::new(0x7F4F_9648_A000).is_user_space());
+        assert!(VirtAddr::default().is_user_space());
+    }
+
+    #[test]
+    fn formatting() {
+        let addr = VirtAddr::new(0x7F4F_9648_A000);
+        assert_eq!(format!("{addr:#x}"), "0x7f4f9648a000");
+        assert_eq!(format!("{addr:?}"), "VirtAddr(0x007F4F9648A000)");
+        assert_eq!(
+            format!("{:?}", PhysAddr::new(0x2000)),
+            "PhysAddr(0x00000000002000)"
+        );
+    }
+}
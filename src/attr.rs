@@ -0,0 +1,161 @@
+use perf_event_open_sys::bindings::perf_event_attr;
+
+use crate::prelude::*;
+
+/// Decoded flags from the bitfield within a [`perf_event_attr`].
+///
+/// The bitfield in `perf_event_attr` is only accessible through the
+/// generated accessor methods which return raw integers. This decodes the
+/// fields that are useful when interpreting the records emitted by the event.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ParsedAttrFlags {
+    /// How much skid is allowed in the `ip` of emitted samples.
+    pub precise_ip: PreciseIp,
+
+    /// The clock used for the `time` field of emitted records.
+    ///
+    /// This is only set if `use_clockid` was set. Otherwise, the kernel uses
+    /// the perf clock, which is not exposed to userspace directly.
+    pub clockid: Option<ClockId>,
+}
+
+impl ParsedAttrFlags {
+    /// Decode the flags within `attr`.
+    pub fn new(attr: &perf_event_attr) -> Self {
+        Self {
+            precise_ip: PreciseIp::new(attr.precise_ip()),
+            clockid: (attr.use_clockid() != 0).then(|| ClockId::new(attr.clockid as _)),
+        }
+    }
+}
+
+impl From<&perf_event_attr> for ParsedAttrFlags {
+    fn from(attr: &perf_event_attr) -> Self {
+        Self::new(attr)
+    }
+}
+
+/// The precision of the instruction pointer recorded in samples.
+///
+/// Hardware sampling usually records the IP some number of instructions after
+/// the one that actually triggered the sample. This is known as skid. Some
+/// PMUs (e.g. Intel PEBS) can reduce or eliminate it.
+///
+/// This corresponds to the `precise_ip` field of `perf_event_attr`. See the
+/// [manpage] for more documentation.
+///
+/// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum PreciseIp {
+    /// `SAMPLE_IP` can have arbitrary skid.
+    #[default]
+    Arbitrary,
+
+    /// `SAMPLE_IP` must have constant skid.
+    Constant,
+
+    /// `SAMPLE_IP` was requested to have 0 skid.
+    RequestedZero,
+
+    /// `SAMPLE_IP` must have 0 skid.
+    Zero,
+}
+
+impl PreciseIp {
+    /// Create a `PreciseIp` from the raw value of the `precise_ip` field.
+    ///
+    /// The field is only 2 bits wide so any higher bits are ignored.
+    pub const fn new(value: u64) -> Self {
+        match value & 0b11 {
+            0 => Self::Arbitrary,
+            1 => Self::Constant,
+            2 => Self::RequestedZero,
+            _ => Self::Zero,
+        }
+    }
+
+    /// Whether the recorded `ip` could be offset from the instruction that
+    /// actually triggered the sample.
+    pub const fn may_skid(&self) -> bool {
+        !matches!(self, Self::Zero)
+    }
+}
+
+c_enum! {
+    /// The clock used for timestamps within records.
+    ///
+    /// This corresponds to the `clockid` field of `perf_event_attr`, which
+    /// takes the same values as the clock ids accepted by
+    /// [`clock_gettime(2)`][0].
+    ///
+    /// [0]: https://man7.org/linux/man-pages/man2/clock_gettime.2.html
+    #[derive(Copy, Clone, Eq, PartialEq, Hash)]
+    pub enum ClockId : i32 {
+        /// `CLOCK_REALTIME`
+        REALTIME = 0,
+
+        /// `CLOCK_MONOTONIC`
+        MONOTONIC = 1,
+
+        /// `CLOCK_MONOTONIC_RAW`
+        MONOTONIC_RAW = 4,
+
+        /// `CLOCK_BOOTTIME`
+        BOOTTIME = 7,
+
+        /// `CLOCK_TAI`
+        TAI = 11,
+    }
+}
+
+impl ClockId {
+    /// Create a new `ClockId`.
+    pub const fn new(value: i32) -> Self {
+        Self(value)
+    }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        matches!(
+            *self,
+            Self::REALTIME | Self::MONOTONIC | Self::MONOTONIC_RAW | Self::BOOTTIME | Self::TAI
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_precise_ip() {
+        let mut attr = perf_event_attr::default();
+        assert_eq!(ParsedAttrFlags::new(&attr).precise_ip, PreciseIp::Arbitrary);
+
+        attr.set_precise_ip(2);
+        assert_eq!(
+            ParsedAttrFlags::new(&attr).precise_ip,
+            PreciseIp::RequestedZero
+        );
+
+        attr.set_precise_ip(3);
+        assert!(!ParsedAttrFlags::new(&attr).precise_ip.may_skid());
+    }
+
+    #[test]
+    fn decode_clockid() {
+        let mut attr = perf_event_attr::default();
+        attr.clockid = 4;
+        assert_eq!(ParsedAttrFlags::new(&attr).clockid, None);
+
+        attr.set_use_clockid(1);
+        assert_eq!(
+            ParsedAttrFlags::new(&attr).clockid,
+            Some(ClockId::MONOTONIC_RAW)
+        );
+
+        attr.clockid = 1000;
+        assert!(!ParsedAttrFlags::new(&attr).clockid.unwrap().is_known());
+    }
+}
@@ -6,6 +6,10 @@ use perf_event_open_sys::bindings::{self, perf_event_attr, PERF_SAMPLE_BRANCH_HW
 use crate::endian::Endian;
 use crate::{ReadFormat, SampleFlags};
 
+/// `PERF_SAMPLE_BRANCH_COUNTERS` is newer than the bindings in
+/// perf-event-open-sys2 so we define it here.
+pub(crate) const PERF_SAMPLE_BRANCH_COUNTERS: u64 = 1 << 19;
+
 bitflags! {
     /// The set of flags used by the kernel is a lot smaller than the full
     /// available 64 bits. We can shrink the size of the `ParseConfig` object
@@ -18,8 +22,11 @@ bitflags! {
     #[derive(Copy, Clone, Debug, Default)]
     struct ConfigFlags : u64 {
         const READ_FORMAT = ((1u64 << ConfigFlags::READ_FORMAT_WIDTH) - 1);
-        const SAMPLE_TYPE = (u64::MAX << ConfigFlags::READ_FORMAT_WIDTH) & (ConfigFlags::SAMPLE_ID_ALL.bits() - 1);
+        const SAMPLE_TYPE = (u64::MAX << ConfigFlags::READ_FORMAT_WIDTH) & (ConfigFlags::SIZE_CHECKS.bits() - 1);
 
+        const SIZE_CHECKS     = 1 << 43;
+        const MMAP_SAMPLE_ID  = 1 << 44;
+        const BRANCH_COUNTERS = 1 << 45;
         const SAMPLE_ID_ALL   = 1 << 46;
         const BRANCH_HW_INDEX = 1 << 47;
         const MISC = u64::MAX << ConfigFlags::MISC_OFFSET;
@@ -37,6 +44,9 @@ impl ConfigFlags {
 
     const READ_FORMAT_OFFSET: u32 = 0;
     const SAMPLE_TYPE_OFFSET: u32 = Self::READ_FORMAT_WIDTH;
+    const SIZE_CHECKS_OFFSET: u32 = Self::MMAP_SAMPLE_ID_OFFSET - 1;
+    const MMAP_SAMPLE_ID_OFFSET: u32 = Self::BRANCH_COUNTERS_OFFSET - 1;
+    const BRANCH_COUNTERS_OFFSET: u32 = Self::SAMPLE_ID_ALL_OFFSET - 1;
     const SAMPLE_ID_ALL_OFFSET: u32 = Self::BRANCH_HW_INDEX_OFFSET - 1;
     const BRANCH_HW_INDEX_OFFSET: u32 = Self::MISC_OFFSET - 1;
     const MISC_OFFSET: u32 = u64::BITS - Self::MISC_WIDTH;
@@ -48,11 +58,13 @@ impl ConfigFlags {
         sample_type: SampleFlags,
         sample_id_all: bool,
         branch_hw_index: bool,
+        branch_counters: bool,
         misc: u16,
     ) -> Self {
         let mut bits = 0u64;
         bits |= (sample_id_all as u64) << Self::SAMPLE_ID_ALL_OFFSET;
         bits |= (branch_hw_index as u64) << Self::BRANCH_HW_INDEX_OFFSET;
+        bits |= (branch_counters as u64) << Self::BRANCH_COUNTERS_OFFSET;
         bits |= (misc as u64) << Self::MISC_OFFSET;
 
         let mut flags = Self::from_bits_retain(bits);
@@ -81,6 +93,18 @@ impl ConfigFlags {
         self.contains(Self::BRANCH_HW_INDEX)
     }
 
+    fn branch_counters(&self) -> bool {
+        self.contains(Self::BRANCH_COUNTERS)
+    }
+
+    fn mmap_sample_id(&self) -> bool {
+        self.contains(Self::MMAP_SAMPLE_ID)
+    }
+
+    fn size_checks(&self) -> bool {
+        self.contains(Self::SIZE_CHECKS)
+    }
+
     fn misc(&self) -> u16 {
         ((*self & Self::MISC).bits() >> Self::MISC_OFFSET) as _
     }
@@ -107,8 +131,25 @@ impl ConfigFlags {
     }
 }
 
-#[derive(Copy, Clone, Debug, Default)]
+// The misc flags are set per-record while parsing so they are not considered
+// when comparing or hashing configs. This matches the serde impls below.
+impl PartialEq for ConfigFlags {
+    fn eq(&self, other: &Self) -> bool {
+        (*self & !Self::MISC).bits() == (*other & !Self::MISC).bits()
+    }
+}
+
+impl Eq for ConfigFlags {}
+
+impl std::hash::Hash for ConfigFlags {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (*self & !Self::MISC).bits().hash(state)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct RawParseConfig {
     config_flags: ConfigFlags,
     sample_regs_user: u64,
@@ -116,7 +157,14 @@ pub(crate) struct RawParseConfig {
 }
 
 /// All the configuration data needed to parse any perf record.
-#[derive(Clone, Default)]
+///
+/// With the `serde` feature enabled, `ParseConfig` can be serialized so that
+/// it can be persisted and used to parse the same records later on. The flags
+/// are stored in a packed form whose layout may change between versions of
+/// this crate so a serialized config should only be deserialized by the same
+/// version of this crate that produced it.
+#[derive(Clone, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParseConfig<E> {
     config: RawParseConfig,
     endian: E,
@@ -145,12 +193,82 @@ impl<E> ParseConfig<E> {
         self
     }
 
+    #[allow(dead_code)]
+    /// Used for testing, please open an issue if you need this.
+    pub(crate) fn with_branch_counters(mut self, branch_counters: bool) -> Self {
+        self.config
+            .config_flags
+            .set(ConfigFlags::BRANCH_COUNTERS, branch_counters);
+        self
+    }
+
+    /// Set whether MMAP records have a `sample_id` trailer.
+    ///
+    /// The `perf_event_open` [manpage] documents MMAP records as not having a
+    /// `sample_id` trailer but kernels do emit one when `sample_id_all` is
+    /// set. By default, MMAP records are parsed as if they do not have the
+    /// trailer. Enable this if parsing records from a kernel that emits it
+    /// so that the trailer is not treated as part of the filename.
+    ///
+    /// This has no effect unless `sample_id_all` is also set.
+    ///
+    /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
+    pub fn with_mmap_sample_id(mut self, enabled: bool) -> Self {
+        self.config
+            .config_flags
+            .set(ConfigFlags::MMAP_SAMPLE_ID, enabled);
+        self
+    }
+
+    /// Set whether parsing a record checks that its entire body was consumed.
+    ///
+    /// When enabled, [`Parser::parse_record`] returns an
+    /// [`InvalidRecord`](crate::parse::ErrorKind::InvalidRecord) error if any
+    /// bytes of the record body (excluding the `sample_id` trailer) were left
+    /// over after parsing it. This usually means that the config does not
+    /// match the one used to generate the record, or that this crate has a
+    /// bug in how it parses that record type.
+    ///
+    /// Records whose body is smaller than the minimum size for their type
+    /// (see [`RecordType::min_len`](crate::RecordType::min_len)) are also
+    /// rejected with an `InvalidRecord` error as soon as their header is
+    /// parsed, instead of failing with an `Eof` error partway through parsing
+    /// the record. This applies to [`Parser::parse_metadata`] as well.
+    ///
+    /// Size checks are disabled by default.
+    ///
+    /// [`Parser::parse_metadata`]: crate::parse::Parser::parse_metadata
+    ///
+    /// [`Parser::parse_record`]: crate::parse::Parser::parse_record
+    pub fn with_size_checks(mut self, enabled: bool) -> Self {
+        self.config
+            .config_flags
+            .set(ConfigFlags::SIZE_CHECKS, enabled);
+        self
+    }
+
     pub(crate) fn with_misc(mut self, misc: u16) -> Self {
         self.config.config_flags.set_misc(misc);
         self
     }
 }
 
+impl<E: Clone> ParseConfig<E> {
+    /// Build the config for the records of an event that was created using
+    /// `attr`.
+    ///
+    /// The endian and the options that are not part of the attr (size checks
+    /// and mmap sample ids) are carried over from this config.
+    pub(crate) fn for_attr(&self, attr: &perf_event_attr) -> Self {
+        Self {
+            endian: self.endian.clone(),
+            config: RawParseConfig::from(attr),
+        }
+        .with_size_checks(self.size_checks())
+        .with_mmap_sample_id(self.mmap_sample_id())
+    }
+}
+
 impl<E> ParseConfig<E> {
     /// Flags controlling what fields are returned by the kernel when reading
     /// from a counter.
@@ -180,6 +298,47 @@ impl<E> ParseConfig<E> {
         self.config.sample_regs_intr
     }
 
+    /// The minimum number of bytes that a [`Sample`](crate::Sample) parsed
+    /// using this config can occupy, not including the record header.
+    ///
+    /// This is the total size of all the fixed-size fields enabled by
+    /// [`sample_type`](Self::sample_type). Variable-length fields (e.g.
+    /// callchains or registers) only count the size of their length or ABI
+    /// prefix, so real samples may be larger than this. A record whose size
+    /// is smaller than this cannot have been generated with this config.
+    pub fn min_sample_len(&self) -> usize {
+        let sty = self.sample_type();
+        let read_format = self.read_format();
+
+        // Every known field other than READ occupies at least one u64. That is
+        // either the field itself or the length prefix of a variable-sized
+        // field.
+        let mut words = (sty & (SampleFlags::all() - SampleFlags::READ))
+            .bits()
+            .count_ones() as usize;
+
+        if sty.contains(SampleFlags::BRANCH_STACK) && self.branch_hw_index() {
+            words += 1;
+        }
+
+        if sty.contains(SampleFlags::READ) {
+            // Either the value or, for group reads, the number of entries.
+            words += 1;
+            words += (read_format
+                & (ReadFormat::TOTAL_TIME_ENABLED | ReadFormat::TOTAL_TIME_RUNNING))
+                .bits()
+                .count_ones() as usize;
+
+            if !read_format.contains(ReadFormat::GROUP) {
+                words += (read_format & (ReadFormat::ID | ReadFormat::LOST))
+                    .bits()
+                    .count_ones() as usize;
+            }
+        }
+
+        words * std::mem::size_of::<u64>()
+    }
+
     pub(crate) fn sample_id_all(&self) -> bool {
         self.config.config_flags.sample_id_all()
     }
@@ -188,24 +347,146 @@ impl<E> ParseConfig<E> {
         self.config.config_flags.branch_hw_index()
     }
 
+    pub(crate) fn branch_counters(&self) -> bool {
+        self.config.config_flags.branch_counters()
+    }
+
     pub(crate) fn misc(&self) -> u16 {
         self.config.config_flags.misc()
     }
 
+    /// Whether MMAP records are parsed as having a `sample_id` trailer.
+    ///
+    /// See [`with_mmap_sample_id`](Self::with_mmap_sample_id) for details.
+    pub fn mmap_sample_id(&self) -> bool {
+        self.config.config_flags.mmap_sample_id()
+    }
+
+    /// Whether parsing a record checks that its entire body was consumed.
+    ///
+    /// See [`with_size_checks`](Self::with_size_checks) for details.
+    pub fn size_checks(&self) -> bool {
+        self.config.config_flags.size_checks()
+    }
+
     /// The [`Endian`] for this `ParseConfig`.
     pub fn endian(&self) -> &E {
         &self.endian
     }
 }
 
-impl From<perf_event_attr> for RawParseConfig {
-    fn from(attrs: perf_event_attr) -> Self {
+impl<E: Endian + Default> ParseConfig<E> {
+    /// Make a best-effort guess at the config used to generate a stream of
+    /// records.
+    ///
+    /// This is meant for recovering data from captures where the
+    /// `perf_event_attr` has been lost. It looks at the first few SAMPLE
+    /// records within `data` and tries a set of candidate `sample_type`s,
+    /// returning the first candidate under which every one of those samples
+    /// parses and consumes its entire body.
+    ///
+    /// # This is a heuristic
+    /// The size of a sample is the only thing that can be checked, so the
+    /// returned config is only guaranteed to be _consistent_ with the data,
+    /// not to be the config that was actually used. Most sample fields are a
+    /// single `u64` so many different configs will fit the same data equally
+    /// well. Ties are broken in favour of the fields most commonly used by
+    /// the `perf` tool (`IP`, `TID`, `TIME`, and `PERIOD`). Streams with
+    /// variable-length fields (e.g. callchains) of differing lengths narrow
+    /// down the candidates considerably.
+    ///
+    /// Only `IDENTIFIER`, `IP`, `TID`, `TIME`, `ADDR`, `ID`, `STREAM_ID`,
+    /// `CPU`, `PERIOD`, `CALLCHAIN`, and `RAW` are considered. The returned
+    /// config never has `sample_id_all` set.
+    ///
+    /// Returns `None` if `data` contains no SAMPLE records or if none of the
+    /// candidates fit all of them.
+    pub fn infer_from_samples(data: &[u8]) -> Option<Self> {
+        use crate::parse::Parser;
+        use crate::{Record, RecordType};
+
+        /// The maximum number of samples to check each candidate against.
+        const MAX_SAMPLES: usize = 64;
+
+        // Each flag that is considered, along with how unlikely it is to be
+        // set. Candidates with a lower total cost are tried first.
+        const FLAGS: &[(SampleFlags, u32)] = &[
+            (SampleFlags::IDENTIFIER, 3),
+            (SampleFlags::IP, 1),
+            (SampleFlags::TID, 1),
+            (SampleFlags::TIME, 1),
+            (SampleFlags::ADDR, 4),
+            (SampleFlags::ID, 3),
+            (SampleFlags::STREAM_ID, 5),
+            (SampleFlags::CPU, 2),
+            (SampleFlags::PERIOD, 1),
+            (SampleFlags::CALLCHAIN, 2),
+            (SampleFlags::RAW, 4),
+        ];
+
+        let mut samples = Vec::new();
+        let mut rest = data;
+        while samples.len() < MAX_SAMPLES {
+            let mut parser = Parser::new(rest, Self::default());
+            let (ty, _, size) = match parser.read_header() {
+                Ok(header) => header,
+                Err(_) => break,
+            };
+
+            let size = size as usize;
+            if size < 8 || size > rest.len() {
+                break;
+            }
+
+            let (record, tail) = rest.split_at(size);
+            if ty == RecordType::SAMPLE {
+                samples.push(record);
+            }
+            rest = tail;
+        }
+
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut candidates: Vec<_> = (0u32..1 << FLAGS.len())
+            .map(|mask| {
+                FLAGS
+                    .iter()
+                    .enumerate()
+                    .filter(|&(index, _)| mask & (1 << index) != 0)
+                    .fold(
+                        (0, SampleFlags::empty()),
+                        |(cost, flags), (_, &(flag, c))| (cost + c, flags | flag),
+                    )
+            })
+            .collect();
+        candidates.sort_by_key(|&(cost, _)| cost);
+
+        candidates.into_iter().find_map(|(_, sample_type)| {
+            let config = Self::default()
+                .with_sample_type(sample_type)
+                .with_size_checks(true);
+            let fits = samples.iter().all(|&sample| {
+                Parser::new(sample, config.clone())
+                    .parse::<Record>()
+                    .is_ok()
+            });
+
+            fits.then(|| config.with_size_checks(false))
+        })
+    }
+}
+
+impl From<&perf_event_attr> for RawParseConfig {
+    fn from(attrs: &perf_event_attr) -> Self {
         Self {
             config_flags: ConfigFlags::new(
                 ReadFormat::from_bits_retain(attrs.read_format),
                 SampleFlags::from_bits_retain(attrs.sample_type),
                 attrs.sample_id_all() != 0,
                 (attrs.branch_sample_type & PERF_SAMPLE_BRANCH_HW_INDEX as u64) != 0,
+                (attrs.branch_sample_type & PERF_SAMPLE_BRANCH_COUNTERS) != 0,
                 0,
             ),
             sample_regs_user: attrs.sample_regs_user,
@@ -219,6 +500,15 @@ where
     E: Default,
 {
     fn from(value: perf_event_attr) -> Self {
+        Self::from(&value)
+    }
+}
+
+impl<E> From<&perf_event_attr> for ParseConfig<E>
+where
+    E: Default,
+{
+    fn from(value: &perf_event_attr) -> Self {
         Self {
             endian: E::default(),
             config: RawParseConfig::from(value),
@@ -233,6 +523,9 @@ impl<E: fmt::Debug> fmt::Debug for ParseConfig<E> {
             .field("sample_type", &self.sample_type())
             .field("sample_id_all", &self.sample_id_all())
             .field("branch_hw_index", &self.branch_hw_index())
+            .field("branch_counters", &self.branch_counters())
+            .field("mmap_sample_id", &self.mmap_sample_id())
+            .field("size_checks", &self.size_checks())
             .field("misc", &format_args!("0x{:X}", self.misc()))
             .field("regs_user", &format_args!("0x{:X}", self.regs_user()))
             .field("regs_intr", &format_args!("0x{:X}", self.regs_intr()))
@@ -262,7 +555,129 @@ mod fuzzing {
     }
 }
 
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::*;
+
+    // The misc flags are set per-record while parsing so they are not part of
+    // the config that gets persisted.
+    impl Serialize for ConfigFlags {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            (*self & !Self::MISC).bits().serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for ConfigFlags {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(Self::from_bits_retain(u64::deserialize(deserializer)?) & !Self::MISC)
+        }
+    }
+}
+
 #[test]
 fn assert_sufficient_spare_sample_type_bits() {
     assert!(ConfigFlags::SAMPLE_TYPE.bits().count_ones() >= ConfigFlags::SAMPLE_TYPE_WIDTH + 8)
 }
+
+#[test]
+fn min_sample_len_fixed_fields() {
+    use crate::endian::Little;
+
+    let config = ParseConfig::<Little>::default();
+    assert_eq!(config.min_sample_len(), 0);
+
+    let config = config.with_sample_type(
+        SampleFlags::IP | SampleFlags::TID | SampleFlags::TIME | SampleFlags::CALLCHAIN,
+    );
+    assert_eq!(config.min_sample_len(), 32);
+}
+
+#[test]
+fn infer_from_samples() {
+    use crate::endian::Little;
+
+    fn sample(callchain: &[u64]) -> Vec<u64> {
+        let size = 8 * (5 + callchain.len()) as u64;
+        let mut words = vec![
+            9 | (size << 48), // header (SAMPLE)
+            0x7F4F_9648_A000, // ip
+            0x1234_0000_1234, // pid, tid
+            0x0001_0000_0000, // time
+            callchain.len() as u64,
+        ];
+        words.extend_from_slice(callchain);
+        words
+    }
+
+    let mut data = Vec::new();
+    data.extend(sample(&[0x1000]));
+    data.extend(sample(&[0x1000, 0x2000, 0x3000]));
+    let data: Vec<u8> = data.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+    let config = ParseConfig::<Little>::infer_from_samples(&data).unwrap();
+    assert_eq!(
+        config.sample_type(),
+        SampleFlags::IP | SampleFlags::TID | SampleFlags::TIME | SampleFlags::CALLCHAIN
+    );
+    assert!(!config.size_checks());
+
+    assert!(ParseConfig::<Little>::infer_from_samples(crate::doctest::MMAP).is_none());
+}
+
+#[test]
+fn min_sample_len_read() {
+    use crate::endian::Little;
+
+    let config = ParseConfig::<Little>::default()
+        .with_sample_type(SampleFlags::READ)
+        .with_read_format(ReadFormat::TOTAL_TIME_ENABLED | ReadFormat::ID);
+    assert_eq!(config.min_sample_len(), 24);
+
+    let config = config.with_read_format(ReadFormat::GROUP | ReadFormat::ID | ReadFormat::LOST);
+    assert_eq!(config.min_sample_len(), 8);
+}
+
+#[test]
+fn eq_ignores_misc() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    use crate::endian::Little;
+
+    fn hash(config: &ParseConfig<Little>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        config.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let config = ParseConfig::<Little>::default().with_sample_type(SampleFlags::IP);
+    let with_misc = config.clone().with_misc(0x1234);
+    assert_eq!(config, with_misc);
+    assert_eq!(hash(&config), hash(&with_misc));
+
+    let other = config.clone().with_sample_type(SampleFlags::TID);
+    assert_ne!(config, other);
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn serde_roundtrip() {
+    use crate::endian::{Dynamic, Little};
+
+    let config = ParseConfig::<Little>::default()
+        .with_sample_type(SampleFlags::IP | SampleFlags::TID | SampleFlags::READ)
+        .with_read_format(ReadFormat::GROUP | ReadFormat::ID)
+        .with_mmap_sample_id(true);
+
+    let json = serde_json::to_string(&config).unwrap();
+    let parsed: ParseConfig<Little> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, config);
+
+    let config = config.with_endian(Dynamic::Big);
+    let json = serde_json::to_string(&config).unwrap();
+    let parsed: ParseConfig<Dynamic> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, config);
+    assert_eq!(parsed.endian(), &Dynamic::Big);
+}
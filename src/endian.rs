@@ -34,7 +34,8 @@ pub unsafe trait Endian: Clone {
 /// Native endian.
 ///
 /// This type performs no endianness conversion.
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Native;
 
 unsafe impl Endian for Native {
@@ -60,7 +61,8 @@ unsafe impl Endian for Native {
 }
 
 /// Little endian.
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Little;
 
 unsafe impl Endian for Little {
@@ -88,7 +90,8 @@ unsafe impl Endian for Little {
 }
 
 /// Big endian.
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Big;
 
 unsafe impl Endian for Big {
@@ -116,7 +119,23 @@ unsafe impl Endian for Big {
 }
 
 /// Either big or little endian, chosen at runtime.
+///
+/// [`Endian`] is used as a generic parameter so each endian type results in
+/// its own copy of the parsing code. `Dynamic` is the way to avoid that: code
+/// that is only ever instantiated with `Dynamic` can parse data of either
+/// endianness, at the cost of a branch on each conversion. The
+/// [`Endian`] trait itself is not object safe so there is no `dyn Endian`.
+///
+/// `Dynamic` still supports zero-copy parsing. When the selected endianness
+/// matches that of the host then [`is_native`](Endian::is_native) returns
+/// `true` and the parser will borrow data from the source buffer where it
+/// can, just as it would with [`Native`].
+///
+/// See [`Parser::new_dynamic`] for creating a parser using `Dynamic`.
+///
+/// [`Parser::new_dynamic`]: crate::parse::Parser::new_dynamic
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Dynamic {
     /// Big endian.
     Big,
@@ -125,6 +144,17 @@ pub enum Dynamic {
     Little,
 }
 
+impl Dynamic {
+    /// The endianness of the current host.
+    pub const fn native() -> Self {
+        if cfg!(target_endian = "big") {
+            Self::Big
+        } else {
+            Self::Little
+        }
+    }
+}
+
 unsafe impl Endian for Dynamic {
     fn convert_u16(&self, bytes: [u8; 2]) -> u16 {
         match self {
@@ -154,3 +184,22 @@ unsafe impl Endian for Dynamic {
         }
     }
 }
+
+/// Convert an unsigned integer of between 1 and 8 bytes from the source endian
+/// to the native endian.
+///
+/// # Panics
+/// Panics if `bytes` is longer than 8 bytes.
+pub(crate) fn convert_uint<E: Endian>(endian: &E, bytes: &[u8]) -> u64 {
+    // The bytes get placed at the low-order end of the array so that
+    // converting the full array gives the value of the narrower integer.
+    let little = endian.convert_u16([1, 0]) == 1;
+    let mut array = [0u8; 8];
+    if little {
+        array[..bytes.len()].copy_from_slice(bytes);
+    } else {
+        array[8 - bytes.len()..].copy_from_slice(bytes);
+    }
+
+    endian.convert_u64(array)
+}
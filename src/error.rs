@@ -26,6 +26,7 @@ pub type ParseResult<T> = std::result::Result<T, ParseError>;
 pub struct ParseError {
     code: ErrorKind,
     source: Option<BoxedError>,
+    offset: Option<u64>,
 }
 
 impl ParseError {
@@ -38,6 +39,7 @@ impl ParseError {
         Self {
             code: ErrorKind::External,
             source: Some(error.into()),
+            offset: None,
         }
     }
 
@@ -55,13 +57,40 @@ impl ParseError {
 
     #[inline]
     const fn from_code(code: ErrorKind) -> Self {
-        Self { code, source: None }
+        Self {
+            code,
+            source: None,
+            offset: None,
+        }
     }
 
     pub(crate) fn with_kind(self, code: ErrorKind) -> Self {
         Self { code, ..self }
     }
 
+    /// Get the offset within the data being parsed at which the
+    /// [`ParseBuf`] returned this error, if known.
+    ///
+    /// This is set for [`External`](ErrorKind::External) errors, such as I/O
+    /// errors, and for [`Eof`](ErrorKind::Eof) errors that are returned by the
+    /// [`ParseBuf`] underlying a [`Parser`].
+    /// The offset is relative to the position of the [`ParseBuf`] when the
+    /// [`Parser`] was created.
+    #[inline]
+    pub fn offset(&self) -> Option<u64> {
+        self.offset
+    }
+
+    /// Record the offset at which this error occurred, unless one has already
+    /// been recorded.
+    #[cold]
+    pub(crate) fn with_offset(self, offset: u64) -> Self {
+        Self {
+            offset: self.offset.or(Some(offset)),
+            ..self
+        }
+    }
+
     /// More input was needed before the item could be successfully parsed.
     #[cold]
     pub fn eof() -> Self {
@@ -70,6 +99,9 @@ impl ParseError {
 }
 
 /// A list specifying general categories of parse error.
+///
+/// New kinds may be added in minor releases so any `match` on an `ErrorKind`
+/// needs to include a wildcard arm.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 #[non_exhaustive]
 pub enum ErrorKind {
@@ -99,14 +131,61 @@ pub enum ErrorKind {
     ///
     /// This is used when attempting to parse a [`perf_event_attr`] that has
     /// fields from versions of the kernel that this crate does not support.
+    /// That is, its `size` field is larger than the largest version of the
+    /// struct known to this crate and the extra bytes are not all zero. It is
+    /// also emitted by [`Parser::parse_uint`] when asked to parse an integer
+    /// with an unsupported width.
     UnsupportedData,
 
+    /// The config has `sample_id_all` set but the record was too small to hold
+    /// the `sample_id` trailer that the config says should be present.
+    ///
+    /// This usually indicates that the [`ParseConfig`] being used does not
+    /// match the one that was used to generate the records, as opposed to the
+    /// record itself being corrupt.
+    TruncatedSampleId,
+
+    /// There was data left over in the [`ParseBuf`] after parsing a value that
+    /// was expected to consume all of it.
+    ///
+    /// This is emitted by [`Parser::parse_exact`].
+    UnexpectedTrailingData,
+
+    /// Parsing the record would have required copying some of its data
+    /// instead of borrowing it from the [`ParseBuf`].
+    ///
+    /// This is emitted by [`Parser::parse_record_borrowed`].
+    WouldAllocate,
+
     /// An external error, forwarded from the [`ParseBuf`] implementation.
     ///
-    /// This error will never be emitted by a parse method in this crate.
+    /// This error will never be emitted by a parse method in this crate. The
+    /// only exception is `CompressedDecoder` (enabled by the `zstd` feature),
+    /// which forwards the I/O errors returned by zstd when decompressing
+    /// invalid data.
     External,
 }
 
+impl ErrorKind {
+    /// A short, stable name for this error kind.
+    ///
+    /// The returned names are in `snake_case` (e.g. `"eof"` or
+    /// `"invalid_record"`) and will not change in future versions, making
+    /// them suitable for use as log fields or metric labels.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Eof => "eof",
+            Self::InvalidRecord => "invalid_record",
+            Self::UnsupportedConfig => "unsupported_config",
+            Self::UnsupportedData => "unsupported_data",
+            Self::TruncatedSampleId => "truncated_sample_id",
+            Self::UnexpectedTrailingData => "unexpected_trailing_data",
+            Self::WouldAllocate => "would_allocate",
+            Self::External => "external",
+        }
+    }
+}
+
 impl Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.code {
@@ -114,6 +193,9 @@ impl Display for ParseError {
             ErrorKind::InvalidRecord => f.write_str("invalid record")?,
             ErrorKind::UnsupportedData => f.write_str("unsupported serialized data")?,
             ErrorKind::UnsupportedConfig => f.write_str("unsupported config")?,
+            ErrorKind::TruncatedSampleId => f.write_str("truncated sample_id")?,
+            ErrorKind::UnexpectedTrailingData => f.write_str("unexpected trailing data")?,
+            ErrorKind::WouldAllocate => f.write_str("parsing would require a copy")?,
             ErrorKind::External => {
                 // This type should always have a source, but, however, if it doesn't then we
                 // still need to provide a default message.
@@ -131,6 +213,10 @@ impl Display for ParseError {
             source.fmt(f)?;
         }
 
+        if let Some(offset) = self.offset {
+            write!(f, " (at byte {offset})")?;
+        }
+
         Ok(())
     }
 }
@@ -160,6 +246,7 @@ impl From<BoxedError> for ParseError {
         Self {
             code: ErrorKind::External,
             source: Some(error),
+            offset: None,
         }
     }
 }
@@ -199,3 +286,20 @@ impl fmt::Display for CustomMessageError {
 }
 
 impl Error for CustomMessageError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_kind_names() {
+        assert_eq!(ErrorKind::Eof.as_str(), "eof");
+        assert_eq!(ErrorKind::InvalidRecord.as_str(), "invalid_record");
+        assert_eq!(
+            ParseError::custom(ErrorKind::WouldAllocate, "msg")
+                .kind()
+                .as_str(),
+            "would_allocate"
+        );
+    }
+}
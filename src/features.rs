@@ -0,0 +1,151 @@
+use std::fmt;
+
+use crate::prelude::*;
+
+c_enum! {
+    /// An optional feature section within a `perf.data` file.
+    ///
+    /// These correspond to the `HEADER_x` values within the `perf` tool. See
+    /// the [perf source] for documentation on what each section contains.
+    ///
+    /// [perf source]: https://sourcegraph.com/github.com/torvalds/linux@eb7081409f94a9a8608593d0fb63a1aa3d6f95d8/-/blob/tools/perf/util/header.h
+    #[derive(Copy, Clone, Eq, PartialEq, Hash)]
+    pub enum FeatureId : u8 {
+        RESERVED = 0,
+        TRACING_DATA = 1,
+        BUILD_ID = 2,
+        HOSTNAME = 3,
+        OSRELEASE = 4,
+        VERSION = 5,
+        ARCH = 6,
+        NRCPUS = 7,
+        CPUDESC = 8,
+        CPUID = 9,
+        TOTAL_MEM = 10,
+        CMDLINE = 11,
+        EVENT_DESC = 12,
+        CPU_TOPOLOGY = 13,
+        NUMA_TOPOLOGY = 14,
+        BRANCH_STACK = 15,
+        PMU_MAPPINGS = 16,
+        GROUP_DESC = 17,
+        AUXTRACE = 18,
+        STAT = 19,
+        CACHE = 20,
+        SAMPLE_TIME = 21,
+        MEM_TOPOLOGY = 22,
+        CLOCKID = 23,
+        DIR_FORMAT = 24,
+        BPF_PROG_INFO = 25,
+        BPF_BTF = 26,
+        COMPRESSED = 27,
+        CPU_PMU_CAPS = 28,
+        CLOCK_DATA = 29,
+        HYBRID_TOPOLOGY = 30,
+        PMU_CAPS = 31,
+    }
+
+    #[allow(missing_docs)]
+    impl {}
+}
+
+impl FeatureId {
+    /// Create a new `FeatureId`.
+    pub const fn new(value: u8) -> Self {
+        Self(value)
+    }
+}
+
+/// The bitmap of feature sections present in a `perf.data` file.
+///
+/// Each bit corresponds to a [`FeatureId`]. The sections for the features
+/// that are present are stored after the data section, in order of their
+/// feature ID.
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FeatureBitmap([u64; 4]);
+
+impl FeatureBitmap {
+    /// Create a `FeatureBitmap` from its raw bits.
+    pub const fn new(bits: [u64; 4]) -> Self {
+        Self(bits)
+    }
+
+    /// Get the raw bits of this `FeatureBitmap`.
+    pub const fn bits(&self) -> [u64; 4] {
+        self.0
+    }
+
+    /// Whether the section for `feature` is present.
+    pub const fn contains(&self, feature: FeatureId) -> bool {
+        let index = feature.0 as usize;
+        self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Mark the section for `feature` as present.
+    pub fn insert(&mut self, feature: FeatureId) {
+        let index = feature.0 as usize;
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    /// The number of feature sections that are present.
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Whether no feature sections are present.
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    /// Iterate over the features that are present, in increasing order.
+    ///
+    /// This is also the order in which their sections appear in the file.
+    pub fn iter(&self) -> impl Iterator<Item = FeatureId> + '_ {
+        (0..=u8::MAX)
+            .map(FeatureId::new)
+            .filter(move |&feature| self.contains(feature))
+    }
+}
+
+impl<'p> Parse<'p> for FeatureBitmap {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        Ok(Self([p.parse()?, p.parse()?, p.parse()?, p.parse()?]))
+    }
+}
+
+impl fmt::Debug for FeatureBitmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_and_iter() {
+        let mut bitmap = FeatureBitmap::default();
+        bitmap.insert(FeatureId::BUILD_ID);
+        bitmap.insert(FeatureId::COMPRESSED);
+        bitmap.insert(FeatureId::new(200));
+
+        assert!(bitmap.contains(FeatureId::BUILD_ID));
+        assert!(!bitmap.contains(FeatureId::HOSTNAME));
+        assert_eq!(bitmap.len(), 3);
+
+        let features: Vec<_> = bitmap.iter().collect();
+        assert_eq!(
+            features,
+            [
+                FeatureId::BUILD_ID,
+                FeatureId::COMPRESSED,
+                FeatureId::new(200)
+            ]
+        );
+    }
+}
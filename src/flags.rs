@@ -1,6 +1,9 @@
 #![allow(missing_docs)]
 
+use std::fmt;
+
 use bitflags::bitflags;
+use c_enum::c_enum;
 use perf_event_open_sys::bindings;
 
 use crate::Sample;
@@ -75,6 +78,230 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Flags within the `misc` field of a record header.
+    ///
+    /// A number of these flags share the same bit. Which one applies depends
+    /// on the type of the record. The low bits of `misc` also hold the CPU
+    /// mode which can be extracted using [`CPUMODE_MASK`](Self::CPUMODE_MASK).
+    ///
+    /// These values correspond to `PERF_RECORD_MISC_x` values. See the
+    /// [manpage] for documentation on what they mean.
+    ///
+    /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Default)]
+    pub struct MiscFlags : u16 {
+        const CPUMODE_MASK = bindings::PERF_RECORD_MISC_CPUMODE_MASK as _;
+        const PROC_MAP_PARSE_TIMEOUT = bindings::PERF_RECORD_MISC_PROC_MAP_PARSE_TIMEOUT as _;
+        const MMAP_DATA = bindings::PERF_RECORD_MISC_MMAP_DATA as _;
+        const COMM_EXEC = bindings::PERF_RECORD_MISC_COMM_EXEC as _;
+        const FORK_EXEC = bindings::PERF_RECORD_MISC_FORK_EXEC as _;
+        const SWITCH_OUT = bindings::PERF_RECORD_MISC_SWITCH_OUT as _;
+        const EXACT_IP = bindings::PERF_RECORD_MISC_EXACT_IP as _;
+        const SWITCH_OUT_PREEMPT = bindings::PERF_RECORD_MISC_SWITCH_OUT_PREEMPT as _;
+        const MMAP_BUILD_ID = bindings::PERF_RECORD_MISC_MMAP_BUILD_ID as _;
+        const EXT_RESERVED = bindings::PERF_RECORD_MISC_EXT_RESERVED as _;
+    }
+}
+
+c_enum! {
+    /// The CPU mode that a record was generated in, as stored in the low bits
+    /// of the `misc` field of its header.
+    ///
+    /// These values correspond to `PERF_RECORD_MISC_CPUMODE_x` values. See
+    /// the [manpage] for documentation on what they mean.
+    ///
+    /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
+    #[derive(Copy, Clone, Eq, PartialEq, Hash)]
+    pub enum CpuMode : u16 {
+        UNKNOWN = bindings::PERF_RECORD_MISC_CPUMODE_UNKNOWN as _,
+        KERNEL = bindings::PERF_RECORD_MISC_KERNEL as _,
+        USER = bindings::PERF_RECORD_MISC_USER as _,
+        HYPERVISOR = bindings::PERF_RECORD_MISC_HYPERVISOR as _,
+        GUEST_KERNEL = bindings::PERF_RECORD_MISC_GUEST_KERNEL as _,
+        GUEST_USER = bindings::PERF_RECORD_MISC_GUEST_USER as _,
+    }
+}
+
+c_enum! {
+    /// A marker within a callchain indicating the context of the frames that
+    /// follow it.
+    ///
+    /// These values correspond to `PERF_CONTEXT_x` values. They are stored
+    /// inline within the callchain of a [`Sample`], see
+    /// [`Sample::callchain_segments`] for splitting a callchain on them.
+    #[derive(Copy, Clone, Eq, PartialEq, Hash)]
+    pub enum CallchainContext : u64 {
+        HV = bindings::PERF_CONTEXT_HV as _,
+        KERNEL = bindings::PERF_CONTEXT_KERNEL as _,
+        USER = bindings::PERF_CONTEXT_USER as _,
+        GUEST = bindings::PERF_CONTEXT_GUEST as _,
+        GUEST_KERNEL = bindings::PERF_CONTEXT_GUEST_KERNEL as _,
+        GUEST_USER = bindings::PERF_CONTEXT_GUEST_USER as _,
+    }
+}
+
+c_enum! {
+    /// The type of a record, as stored in its header.
+    ///
+    /// These values correspond to `PERF_RECORD_x` values. See the [manpage]
+    /// for documentation on what they mean.
+    ///
+    /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
+    #[derive(Copy, Clone, Eq, PartialEq, Hash)]
+    pub enum RecordType : u32 {
+        MMAP = bindings::PERF_RECORD_MMAP as _,
+        LOST = bindings::PERF_RECORD_LOST as _,
+        COMM = bindings::PERF_RECORD_COMM as _,
+        EXIT = bindings::PERF_RECORD_EXIT as _,
+        THROTTLE = bindings::PERF_RECORD_THROTTLE as _,
+        UNTHROTTLE = bindings::PERF_RECORD_UNTHROTTLE as _,
+        FORK = bindings::PERF_RECORD_FORK as _,
+        READ = bindings::PERF_RECORD_READ as _,
+        SAMPLE = bindings::PERF_RECORD_SAMPLE as _,
+        MMAP2 = bindings::PERF_RECORD_MMAP2 as _,
+        AUX = bindings::PERF_RECORD_AUX as _,
+        ITRACE_START = bindings::PERF_RECORD_ITRACE_START as _,
+        LOST_SAMPLES = bindings::PERF_RECORD_LOST_SAMPLES as _,
+        SWITCH = bindings::PERF_RECORD_SWITCH as _,
+        SWITCH_CPU_WIDE = bindings::PERF_RECORD_SWITCH_CPU_WIDE as _,
+        NAMESPACES = bindings::PERF_RECORD_NAMESPACES as _,
+        KSYMBOL = bindings::PERF_RECORD_KSYMBOL as _,
+        BPF_EVENT = bindings::PERF_RECORD_BPF_EVENT as _,
+        CGROUP = bindings::PERF_RECORD_CGROUP as _,
+        TEXT_POKE = bindings::PERF_RECORD_TEXT_POKE as _,
+        AUX_OUTPUT_HW_ID = bindings::PERF_RECORD_AUX_OUTPUT_HW_ID as _,
+    }
+}
+
+impl RecordType {
+    /// Create a new `RecordType`.
+    pub const fn new(value: u32) -> Self {
+        Self(value)
+    }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        self.0 >= Self::MMAP.0 && self.0 <= Self::AUX_OUTPUT_HW_ID.0
+    }
+
+    /// The minimum length of the body of a record of this type.
+    ///
+    /// This is the total size of the fixed-size fields of the record. It does
+    /// not include the record header or the `sample_id` trailer. Returns
+    /// `None` for SAMPLE records, whose size depends on the config (see
+    /// [`ParseConfig::min_sample_len`]), and for record types that are not
+    /// known to this crate.
+    ///
+    /// [`ParseConfig::min_sample_len`]: crate::parse::ParseConfig::min_sample_len
+    pub const fn min_len(&self) -> Option<usize> {
+        Some(match *self {
+            Self::MMAP => 32,
+            Self::LOST => 16,
+            Self::COMM => 8,
+            Self::EXIT | Self::FORK => 24,
+            Self::THROTTLE | Self::UNTHROTTLE => 24,
+            Self::READ => 16,
+            Self::MMAP2 => 64,
+            Self::AUX => 24,
+            Self::ITRACE_START => 8,
+            Self::LOST_SAMPLES => 8,
+            Self::SWITCH => 0,
+            Self::SWITCH_CPU_WIDE => 8,
+            Self::NAMESPACES => 16,
+            Self::KSYMBOL => 16,
+            Self::BPF_EVENT => 16,
+            Self::CGROUP => 8,
+            Self::TEXT_POKE => 12,
+            Self::AUX_OUTPUT_HW_ID => 8,
+            _ => return None,
+        })
+    }
+}
+
+impl MiscFlags {
+    /// The CPU mode stored within the `misc` flags.
+    pub fn cpu_mode(&self) -> CpuMode {
+        CpuMode::new((*self & Self::CPUMODE_MASK).bits())
+    }
+}
+
+impl CpuMode {
+    /// Create a new `CpuMode`.
+    pub const fn new(value: u16) -> Self {
+        Self(value)
+    }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        matches!(
+            *self,
+            Self::UNKNOWN
+                | Self::KERNEL
+                | Self::USER
+                | Self::HYPERVISOR
+                | Self::GUEST_KERNEL
+                | Self::GUEST_USER
+        )
+    }
+
+    /// Whether this mode is kernel mode, either on the host or within a
+    /// guest.
+    pub const fn is_kernel(&self) -> bool {
+        matches!(*self, Self::KERNEL | Self::GUEST_KERNEL)
+    }
+}
+
+impl CallchainContext {
+    /// Any callchain entry at or above this value is a context marker instead
+    /// of an instruction pointer.
+    pub const MAX: u64 = bindings::PERF_CONTEXT_MAX as _;
+
+    /// Create a new `CallchainContext`.
+    pub const fn new(value: u64) -> Self {
+        Self(value)
+    }
+
+    /// Interpret a callchain entry as a context marker.
+    ///
+    /// Returns `None` if `ip` is an instruction pointer instead.
+    pub const fn from_entry(ip: u64) -> Option<Self> {
+        if ip >= Self::MAX {
+            Some(Self(ip))
+        } else {
+            None
+        }
+    }
+
+    /// The context that a record taken in `mode` would start its callchain
+    /// with.
+    ///
+    /// Returns `None` for [`CpuMode::UNKNOWN`] and for unknown modes.
+    pub const fn from_cpu_mode(mode: CpuMode) -> Option<Self> {
+        Some(match mode {
+            CpuMode::KERNEL => Self::KERNEL,
+            CpuMode::USER => Self::USER,
+            CpuMode::HYPERVISOR => Self::HV,
+            CpuMode::GUEST_KERNEL => Self::GUEST_KERNEL,
+            CpuMode::GUEST_USER => Self::GUEST_USER,
+            _ => return None,
+        })
+    }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        matches!(
+            *self,
+            Self::HV
+                | Self::KERNEL
+                | Self::USER
+                | Self::GUEST
+                | Self::GUEST_KERNEL
+                | Self::GUEST_USER
+        )
+    }
+}
+
 impl ReadFormat {
     // The format of a read from a group is like this
     // struct read_format {
@@ -94,6 +321,18 @@ impl ReadFormat {
     }
 }
 
+impl fmt::Display for SampleFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::util::fmt::write_flag_names(self, f)
+    }
+}
+
+impl fmt::Display for ReadFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::util::fmt::write_flag_names(self, f)
+    }
+}
+
 #[cfg(feature = "arbitrary")]
 mod fuzzing {
     use super::*;
@@ -112,3 +351,34 @@ mod fuzzing {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_sample_flags() {
+        let flags = SampleFlags::IP | SampleFlags::TID | SampleFlags::CALLCHAIN;
+        assert_eq!(flags.to_string(), "IP | TID | CALLCHAIN");
+
+        let flags = SampleFlags::from_bits_retain(SampleFlags::TIME.bits() | 1 << 63);
+        assert_eq!(flags.to_string(), "TIME | UNKNOWN(0x8000000000000000)");
+
+        assert_eq!(SampleFlags::empty().to_string(), "(empty)");
+    }
+
+    #[test]
+    fn record_type_is_known() {
+        assert!(RecordType::MMAP.is_known());
+        assert!(RecordType::AUX_OUTPUT_HW_ID.is_known());
+        assert!(!RecordType::new(0).is_known());
+        assert!(!RecordType::new(RecordType::AUX_OUTPUT_HW_ID.0 + 1).is_known());
+        assert!(!RecordType::new(crate::AuxTrace::RECORD_TYPE).is_known());
+    }
+
+    #[test]
+    fn display_read_format() {
+        let flags = ReadFormat::GROUP | ReadFormat::ID;
+        assert_eq!(flags.to_string(), "ID | GROUP");
+    }
+}
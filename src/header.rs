@@ -0,0 +1,183 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::parse::ParseError;
+use crate::prelude::*;
+
+/// The location of a section within a `perf.data` file.
+///
+/// This corresponds to `struct perf_file_section` within the `perf` tool.
+/// See the [perf source] for more documentation.
+///
+/// [perf source]: https://sourcegraph.com/github.com/torvalds/linux@eb7081409f94a9a8608593d0fb63a1aa3d6f95d8/-/blob/tools/perf/util/header.h
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FileSection {
+    /// The offset of the section from the start of the file.
+    pub offset: u64,
+
+    /// The size of the section, in bytes.
+    pub size: u64,
+}
+
+impl FileSection {
+    /// Create a `FileSection` from its fields.
+    pub const fn new(offset: u64, size: u64) -> Self {
+        Self { offset, size }
+    }
+
+    /// The range of bytes within the file covered by this section.
+    ///
+    /// The end of the range saturates at `u64::MAX`.
+    pub fn range(&self) -> Range<u64> {
+        self.offset..self.offset.saturating_add(self.size)
+    }
+
+    /// Whether this section is empty.
+    pub const fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+impl<'p> Parse<'p> for FileSection {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        Ok(Self {
+            offset: p.parse()?,
+            size: p.parse()?,
+        })
+    }
+}
+
+/// An entry within the legacy `event_types` section of a `perf.data` file.
+///
+/// Older versions of the `perf` tool used this section to map event ids to
+/// event names. Newer versions leave it empty and store the same information
+/// in the [`EVENT_DESC`] feature section instead, so the `event_types`
+/// section is only populated in files written by older versions of `perf`.
+/// Use [`EventType::parse_section`] to parse the whole section.
+///
+/// This corresponds to `struct perf_trace_event_type` within the `perf` tool.
+///
+/// [`EVENT_DESC`]: crate::FeatureId::EVENT_DESC
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct EventType {
+    /// The id of the event.
+    pub event_id: u64,
+
+    /// The name of the event, padded with trailing NUL bytes.
+    pub name: [u8; Self::NAME_LEN],
+}
+
+impl EventType {
+    /// The size of the fixed-length `name` field.
+    pub const NAME_LEN: usize = 64;
+
+    /// The size of a single `EventType` entry within the section.
+    const ENTRY_LEN: u64 = 8 + Self::NAME_LEN as u64;
+
+    /// The name of the event, up to the first NUL byte.
+    pub fn name(&self) -> &[u8] {
+        let len = self
+            .name
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(self.name.len());
+        &self.name[..len]
+    }
+
+    /// Parse all the entries within an `event_types` section.
+    ///
+    /// `p` must be positioned at the start of the section. An empty section,
+    /// as written by newer versions of the `perf` tool, results in an empty
+    /// `Vec`. Returns an [`InvalidRecord`] error if the size of the section is
+    /// not a multiple of the size of an entry.
+    ///
+    /// [`InvalidRecord`]: ErrorKind::InvalidRecord
+    pub fn parse_section<'p, B, E>(
+        p: &mut Parser<B, E>,
+        section: &FileSection,
+    ) -> ParseResult<Vec<Self>>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        if section.size % Self::ENTRY_LEN != 0 {
+            return Err(ParseError::custom(
+                ErrorKind::InvalidRecord,
+                format_args!(
+                    "event_types section size {} is not a multiple of {}",
+                    section.size,
+                    Self::ENTRY_LEN
+                ),
+            ));
+        }
+
+        let count = usize::try_from(section.size / Self::ENTRY_LEN).map_err(|_| {
+            ParseError::custom(ErrorKind::InvalidRecord, "event_types section is too large")
+        })?;
+
+        p.parse_repeated(count)
+    }
+}
+
+impl<'p> Parse<'p> for EventType {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        Ok(Self {
+            event_id: p.parse()?,
+            name: p.parse()?,
+        })
+    }
+}
+
+impl fmt::Debug for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventType")
+            .field("event_id", &self.event_id)
+            .field("name", &crate::util::fmt::ByteStr(self.name()))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    #[test]
+    fn parse_event_types_section() {
+        let mut data = Vec::new();
+        for (id, name) in [(7u64, &b"cycles"[..]), (9, &b"instructions"[..])] {
+            let mut field = [0u8; EventType::NAME_LEN];
+            field[..name.len()].copy_from_slice(name);
+
+            data.extend_from_slice(&id.to_le_bytes());
+            data.extend_from_slice(&field);
+        }
+
+        let section = FileSection::new(0x100, data.len() as u64);
+        let mut parser = Parser::new(&data[..], ParseConfig::<Little>::default());
+        let types = EventType::parse_section(&mut parser, &section).unwrap();
+
+        assert_eq!(types.len(), 2);
+        assert_eq!(types[0].event_id, 7);
+        assert_eq!(types[0].name(), b"cycles");
+        assert_eq!(types[1].event_id, 9);
+        assert_eq!(types[1].name(), b"instructions");
+
+        let empty: &[u8] = &[];
+        let mut parser = Parser::new(empty, ParseConfig::<Little>::default());
+        let types = EventType::parse_section(&mut parser, &FileSection::default()).unwrap();
+        assert!(types.is_empty());
+
+        let mut parser = Parser::new(&data[..], ParseConfig::<Little>::default());
+        let error = EventType::parse_section(&mut parser, &FileSection::new(0, 10)).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+    }
+}
@@ -0,0 +1,160 @@
+#[cfg(feature = "perf-event")]
+use perf_event_open_sys::bindings::perf_event_attr;
+
+use crate::endian::Endian;
+#[cfg(feature = "perf-event")]
+use crate::endian::Native;
+use crate::parse::{ParseBuf, ParseConfig, ParseResult, Parser};
+use crate::Record;
+
+/// An iterator over the [`Record`]s within a buffer.
+///
+/// This parses each record in turn using a single [`ParseConfig`]. It is a
+/// convenient way to walk over the raw bytes read out of a `perf_event_open`
+/// ring buffer.
+///
+/// Once an error has been returned the iterator will yield no further
+/// records.
+///
+/// Records are only parsed as they are requested, so adapters such as
+/// [`find`](Iterator::find) or [`take_while`](Iterator::take_while) stop
+/// without parsing any of the records after the one that ended the
+/// iteration. See also [`Parser::parse_records_while`] for doing the same with
+/// a custom [`Visitor`](crate::Visitor).
+///
+/// # Example
+/// ```
+/// # use perf_event_open_sys::bindings::perf_event_attr;
+/// use perf_event_data::endian::Native;
+/// use perf_event_data::parse::{ParseConfig, RecordIter};
+/// use perf_event_data::Record;
+///
+/// # let data = perf_event_data::doctest::MMAP;
+/// # let attr = perf_event_attr::default();
+/// let config = ParseConfig::<Native>::from(&attr);
+/// for record in RecordIter::new(data, config) {
+///     match record? {
+///         Record::Mmap(mmap) => println!("mmap at {:#x}", mmap.addr),
+///         _ => (),
+///     }
+/// }
+/// # Ok::<_, perf_event_data::parse::ParseError>(())
+/// ```
+pub struct RecordIter<B, E> {
+    parser: Parser<B, E>,
+    done: bool,
+}
+
+impl<'p, B, E> RecordIter<B, E>
+where
+    B: ParseBuf<'p>,
+    E: Endian,
+{
+    /// Create a new `RecordIter` that parses `data` using `config`.
+    pub fn new(data: B, config: ParseConfig<E>) -> Self {
+        Self {
+            parser: Parser::new(data, config),
+            done: false,
+        }
+    }
+
+    /// Convert this `RecordIter` back into the underlying [`Parser`].
+    pub fn into_parser(self) -> Parser<B, E> {
+        self.parser
+    }
+}
+
+#[cfg(feature = "perf-event")]
+impl<'p, B> RecordIter<B, Native>
+where
+    B: ParseBuf<'p>,
+{
+    /// Create a new `RecordIter` over the records read out of a
+    /// [`perf_event::Sampler`].
+    ///
+    /// `data` holds the bytes of the records copied out of the sampler and
+    /// `attr` is the `perf_event_attr` that the sampler was built with. The [`ParseConfig`]
+    /// is built from `attr`. Records read from a live ring buffer are always
+    /// in the endianness of the host, so this always uses [`Native`] endian.
+    ///
+    /// This requires the `perf-event` feature.
+    ///
+    /// [`perf_event::Sampler`]: https://docs.rs/perf-event2/latest/perf_event/struct.Sampler.html
+    pub fn from_sampler(data: B, attr: &perf_event_attr) -> Self {
+        Self::new(data, ParseConfig::from(attr))
+    }
+}
+
+impl<'p, B, E> Iterator for RecordIter<B, E>
+where
+    B: ParseBuf<'p>,
+    E: Endian,
+{
+    type Item = ParseResult<Record<'p>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = match self.parser.is_at_end() {
+            Ok(true) => None,
+            Ok(false) => Some(self.parser.parse()),
+            Err(e) => Some(Err(e)),
+        };
+
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    #[test]
+    fn iterate_records() {
+        let data = crate::doctest::MMAP.repeat(3);
+        let records = RecordIter::new(&data[..], ParseConfig::<Little>::default())
+            .collect::<ParseResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert!(records
+            .iter()
+            .all(|record| matches!(record, Record::Mmap(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "perf-event")]
+    fn iterate_sampler_records() {
+        use crate::SampleFlags;
+
+        let mut attr = perf_event_attr::default();
+        attr.sample_type = SampleFlags::IP.bits();
+
+        let mut data = crate::doctest::MMAP.to_vec();
+        data.extend_from_slice(&9u32.to_ne_bytes()); // type (SAMPLE)
+        data.extend_from_slice(&0u16.to_ne_bytes()); // misc
+        data.extend_from_slice(&16u16.to_ne_bytes()); // size
+        data.extend_from_slice(&0x401000u64.to_ne_bytes()); // ip
+
+        let iter = RecordIter::from_sampler(&data[..], &attr);
+        assert_eq!(iter.into_parser().config().sample_type(), SampleFlags::IP);
+
+        let mut iter = RecordIter::from_sampler(&data[..], &attr);
+        assert!(matches!(iter.next(), Some(Ok(Record::Mmap(_)))));
+        assert!(matches!(iter.next(), Some(Ok(Record::Sample(s))) if s.ip() == Some(0x401000)));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn stops_after_error() {
+        let data = &crate::doctest::MMAP[..20];
+        let mut iter = RecordIter::new(data, ParseConfig::<Little>::default());
+
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+}
@@ -53,6 +53,23 @@
 //!   native endian. You likely won't have to interact with it other than
 //!   picking one type to use when creating a [`ParseConfig`].
 //!
+//! # Constructing records
+//! The record types whose fields are all public implement [`Default`]. When
+//! constructing one (e.g. in tests), use struct update syntax so that your code
+//! keeps compiling if new fields are added later on.
+//! ```
+//! use perf_event_data::Exit;
+//!
+//! let exit = Exit {
+//!     pid: 1,
+//!     tid: 1,
+//!     ..Default::default()
+//! };
+//! ```
+//!
+//! Types with private fields, such as [`Mmap2`], instead provide builder
+//! methods that can be used on top of their [`Default`] value.
+//!
 //! # Parsing `perf.data` files
 //! This crate doesn't yet have support for this, although it could be used as
 //! part of implementing a larger parser. If you would like to do this please
@@ -66,14 +83,26 @@
 #[macro_use]
 mod macros;
 
+mod addr;
+mod attr;
 mod config;
 pub mod endian;
 mod error;
+mod features;
 mod flags;
+mod header;
 mod impls;
+mod iter;
+mod merge;
+mod mmap_page;
+mod multi_section;
 pub mod parse;
 mod parsebuf;
+mod pipe;
+mod raw_data;
 mod records;
+mod regs;
+mod stitch;
 mod util;
 mod visitor;
 
@@ -87,9 +116,18 @@ mod prelude {
     pub(crate) use c_enum::c_enum;
 }
 
+pub use crate::addr::{PhysAddr, VirtAddr};
+pub use crate::attr::{ClockId, ParsedAttrFlags, PreciseIp};
+pub use crate::features::{FeatureBitmap, FeatureId};
 pub use crate::flags::*;
+pub use crate::header::{EventType, FileSection};
+pub use crate::merge::MergeReader;
+pub use crate::mmap_page::MmapPage;
+pub use crate::multi_section::MultiSectionReader;
+pub use crate::raw_data::RawData;
 pub use crate::records::*;
-pub use crate::visitor::{RecordMetadata, Visitor};
+pub use crate::regs::X86Reg;
+pub use crate::visitor::{FilterVisitor, LazyRecordMetadata, MapVisitor, RecordMetadata, Visitor};
 
 /// Common data used in doctests.
 ///
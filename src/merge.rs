@@ -0,0 +1,119 @@
+use std::iter::Peekable;
+
+use crate::HasCommonFields;
+
+/// Merges multiple time-ordered streams of records into a single stream.
+///
+/// Each source is assumed to already be sorted by time. `MergeReader` then
+/// yields the items from all the sources ordered by their
+/// [`time`](HasCommonFields::time). Ties are broken in favour of the source
+/// that was passed in first.
+///
+/// Items without a timestamp cannot be placed relative to the other sources.
+/// Instead, they are yielded as soon as they reach the front of their own
+/// source, so they still appear in the same position relative to the other
+/// items from that source.
+///
+/// Any iterator whose items implement [`HasCommonFields`] can be merged. This
+/// includes the output of [`Parser::parse_record_and_metadata`], as well as
+/// results wrapping it. Errors have no timestamp and so are yielded as soon as
+/// they are encountered.
+///
+/// [`Parser::parse_record_and_metadata`]: crate::parse::Parser::parse_record_and_metadata
+pub struct MergeReader<I: Iterator> {
+    sources: Vec<Peekable<I>>,
+}
+
+impl<I: Iterator> MergeReader<I> {
+    /// Create a new `MergeReader` that merges the items from `sources`.
+    pub fn new(sources: impl IntoIterator<Item = I>) -> Self {
+        Self {
+            sources: sources.into_iter().map(Iterator::peekable).collect(),
+        }
+    }
+}
+
+impl<I> Iterator for MergeReader<I>
+where
+    I: Iterator,
+    I::Item: HasCommonFields,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut next: Option<(usize, u64)> = None;
+
+        for (index, source) in self.sources.iter_mut().enumerate() {
+            let time = match source.peek() {
+                Some(item) => item.time(),
+                None => continue,
+            };
+
+            match (time, next) {
+                (None, _) => return source.next(),
+                (Some(time), Some((_, best))) if best <= time => (),
+                (Some(time), _) => next = Some((index, time)),
+            }
+        }
+
+        let (index, _) = next?;
+        self.sources[index].next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    struct Timed(u32, Option<u64>);
+
+    impl HasCommonFields for Timed {
+        fn pid(&self) -> Option<u32> {
+            Some(self.0)
+        }
+
+        fn tid(&self) -> Option<u32> {
+            None
+        }
+
+        fn time(&self) -> Option<u64> {
+            self.1
+        }
+
+        fn cpu(&self) -> Option<u32> {
+            None
+        }
+    }
+
+    #[test]
+    fn merge_by_time() {
+        let a = vec![Timed(0, Some(1)), Timed(0, Some(4)), Timed(0, Some(5))];
+        let b = vec![Timed(1, Some(2)), Timed(1, Some(4)), Timed(1, Some(6))];
+
+        let merged: Vec<_> = MergeReader::new([a.into_iter(), b.into_iter()])
+            .map(|item| (item.0, item.1.unwrap()))
+            .collect();
+
+        assert_eq!(merged, [(0, 1), (1, 2), (0, 4), (1, 4), (0, 5), (1, 6)]);
+    }
+
+    #[test]
+    fn merge_untimed_in_source_order() {
+        let a = vec![Timed(0, Some(1)), Timed(0, None), Timed(0, Some(5))];
+        let b = vec![Timed(1, Some(3)), Timed(1, None)];
+
+        let merged: Vec<_> = MergeReader::new([a.into_iter(), b.into_iter()]).collect();
+
+        assert_eq!(
+            merged,
+            [
+                Timed(0, Some(1)),
+                Timed(0, None),
+                Timed(1, Some(3)),
+                Timed(1, None),
+                Timed(0, Some(5)),
+            ]
+        );
+    }
+}
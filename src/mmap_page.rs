@@ -0,0 +1,225 @@
+use crate::prelude::*;
+use crate::TimeConv;
+
+/// The metadata page at the start of a perf event ring buffer.
+///
+/// When a perf event fd is mmapped, the first page contains a
+/// `perf_event_mmap_page` struct that describes the state of the ring buffer
+/// that follows it, along with the parameters needed to convert hardware
+/// timestamps into perf timestamps. This struct contains the stable fields
+/// from that page.
+///
+/// Note that the kernel updates this page concurrently with userspace reading
+/// it. Parsing it only gives a snapshot and does not perform any of the
+/// synchronization (e.g. checking `lock` or issuing memory barriers) that is
+/// required to read a consistent view of a live page.
+///
+/// See the [manpage] for documentation on what each field means.
+///
+/// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MmapPage {
+    /// Version number of this structure.
+    pub version: u32,
+
+    /// The lowest version this is compatible with.
+    pub compat_version: u32,
+
+    /// Hardware counter identifier, used for reading the counter with `rdpmc`.
+    pub index: u32,
+
+    /// Value to add to the hardware counter value.
+    pub offset: i64,
+
+    /// Time the event was active.
+    pub time_enabled: u64,
+
+    /// Time the event was running.
+    pub time_running: u64,
+
+    /// Whether the counter can be read from userspace using `rdpmc`.
+    pub cap_user_rdpmc: bool,
+
+    /// Whether `time_shift`, `time_mult`, and `time_offset` are valid.
+    pub cap_user_time: bool,
+
+    /// Whether `time_zero` is valid.
+    pub cap_user_time_zero: bool,
+
+    /// Whether `time_cycles` and `time_mask` are valid.
+    pub cap_user_time_short: bool,
+
+    /// The bit width of the value read using `rdpmc`.
+    pub pmc_width: u16,
+
+    /// The shift used when converting hardware cycles to time.
+    pub time_shift: u16,
+
+    /// The multiplier used when converting hardware cycles to time.
+    pub time_mult: u32,
+
+    /// The offset used when converting hardware cycles to the time since the
+    /// event was enabled.
+    pub time_offset: u64,
+
+    /// The offset used when converting hardware cycles to a perf timestamp.
+    pub time_zero: u64,
+
+    /// The size of the header fields, up to the reserved space.
+    pub size: u32,
+
+    /// The cycle count which truncated cycle values are relative to.
+    pub time_cycles: u64,
+
+    /// The mask indicating how many bits of the cycle counter are valid.
+    pub time_mask: u64,
+
+    /// The head of the data section of the ring buffer.
+    pub data_head: u64,
+
+    /// The tail of the data section of the ring buffer.
+    ///
+    /// This is written by userspace to indicate how much data it has
+    /// consumed.
+    pub data_tail: u64,
+
+    /// Where the data section starts within the mmapped region.
+    pub data_offset: u64,
+
+    /// The size of the data section of the ring buffer.
+    pub data_size: u64,
+
+    /// The head of the aux section of the ring buffer.
+    pub aux_head: u64,
+
+    /// The tail of the aux section of the ring buffer.
+    pub aux_tail: u64,
+
+    /// Where the aux section starts within the mmapped region.
+    pub aux_offset: u64,
+
+    /// The size of the aux section of the ring buffer.
+    pub aux_size: u64,
+}
+
+impl MmapPage {
+    /// The size, in bytes, of the serialized `perf_event_mmap_page` struct.
+    pub const SIZE: usize = 1088;
+
+    /// Offset of the reserved bytes in the middle of the struct.
+    const RESERVED_OFFSET: usize = 96;
+
+    /// Offset of `data_head`, which follows the reserved bytes.
+    const DATA_HEAD_OFFSET: usize = 1024;
+
+    /// The parameters for converting hardware timestamps into perf timestamps.
+    ///
+    /// This produces the same values that the `perf` tool records in a
+    /// [`TimeConv`] record.
+    pub fn time_conv(&self) -> TimeConv {
+        TimeConv {
+            time_shift: self.time_shift.into(),
+            time_mult: self.time_mult.into(),
+            time_zero: self.time_zero,
+            time_cycles: self.time_cycles,
+            time_mask: self.time_mask,
+            cap_user_time_zero: self.cap_user_time_zero,
+            cap_user_time_short: self.cap_user_time_short,
+        }
+    }
+}
+
+impl<'p> Parse<'p> for MmapPage {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        let version = p.parse()?;
+        let compat_version = p.parse()?;
+        let _lock: u32 = p.parse()?;
+        let index = p.parse()?;
+        let offset = p.parse_u64()? as i64;
+        let time_enabled = p.parse()?;
+        let time_running = p.parse()?;
+        let capabilities = p.parse_u64()?;
+        let pmc_width = p.parse()?;
+        let time_shift = p.parse()?;
+        let time_mult = p.parse()?;
+        let time_offset = p.parse()?;
+        let time_zero = p.parse()?;
+        let size = p.parse()?;
+        let _reserved_1: u32 = p.parse()?;
+        let time_cycles = p.parse()?;
+        let time_mask = p.parse()?;
+
+        p.skip(Self::DATA_HEAD_OFFSET - Self::RESERVED_OFFSET)?;
+
+        Ok(Self {
+            version,
+            compat_version,
+            index,
+            offset,
+            time_enabled,
+            time_running,
+            // Bit 0 is cap_bit0 and bit 1 is cap_bit0_is_deprecated. Both are
+            // superseded by the fields below.
+            cap_user_rdpmc: capabilities & (1 << 2) != 0,
+            cap_user_time: capabilities & (1 << 3) != 0,
+            cap_user_time_zero: capabilities & (1 << 4) != 0,
+            cap_user_time_short: capabilities & (1 << 5) != 0,
+            pmc_width,
+            time_shift,
+            time_mult,
+            time_offset,
+            time_zero,
+            size,
+            time_cycles,
+            time_mask,
+            data_head: p.parse()?,
+            data_tail: p.parse()?,
+            data_offset: p.parse()?,
+            data_size: p.parse()?,
+            aux_head: p.parse()?,
+            aux_tail: p.parse()?,
+            aux_offset: p.parse()?,
+            aux_size: p.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    #[test]
+    fn parse_mmap_page() {
+        let mut data = vec![0u8; MmapPage::SIZE];
+        data[0..4].copy_from_slice(&0u32.to_le_bytes()); // version
+        data[40..48].copy_from_slice(&0b11_1100u64.to_le_bytes()); // capabilities
+        data[50..52].copy_from_slice(&10u16.to_le_bytes()); // time_shift
+        data[52..56].copy_from_slice(&1000u32.to_le_bytes()); // time_mult
+        data[64..72].copy_from_slice(&5000u64.to_le_bytes()); // time_zero
+        data[1024..1032].copy_from_slice(&0x2000u64.to_le_bytes()); // data_head
+        data[1040..1048].copy_from_slice(&4096u64.to_le_bytes()); // data_offset
+        data[1048..1056].copy_from_slice(&8192u64.to_le_bytes()); // data_size
+
+        let mut parser = Parser::new(&data[..], ParseConfig::<Little>::default());
+        let page: MmapPage = parser.parse().unwrap();
+
+        assert!(page.cap_user_rdpmc);
+        assert!(page.cap_user_time);
+        assert!(page.cap_user_time_zero);
+        assert!(page.cap_user_time_short);
+        assert_eq!(page.data_head, 0x2000);
+        assert_eq!(page.data_offset, 4096);
+        assert_eq!(page.data_size, 8192);
+        assert!(parser.is_at_end().unwrap());
+
+        let conv = page.time_conv();
+        assert_eq!(conv.time_shift, 10);
+        assert_eq!(conv.time_mult, 1000);
+        assert_eq!(conv.time_zero, 5000);
+    }
+}
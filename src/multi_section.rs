@@ -0,0 +1,421 @@
+use std::borrow::Cow;
+use std::mem;
+use std::ops::Range;
+
+use perf_event_open_sys::bindings::{perf_event_attr, perf_event_header};
+
+use crate::parse::ParseError;
+use crate::prelude::*;
+use crate::records::USER_TYPE_START;
+use crate::{AuxTrace, FeatureBitmap, FileSection, HeaderAttr, Record};
+
+/// The magic bytes at the start of a `perf.data` file, as written by a
+/// little-endian and a big-endian machine respectively.
+pub(crate) const MAGIC: [&[u8; 8]; 2] = [b"PERFILE2", b"2ELIFREP"];
+
+/// The size of the header written by `perf record` when writing to a pipe.
+pub(crate) const PIPE_HEADER_LEN: u64 = 16;
+
+/// The size of the header at the start of a regular `perf.data` file.
+const FILE_HEADER_LEN: u64 = 104;
+
+/// Check that the magic at the start of a section was written with the same
+/// endianness as `endian`.
+pub(crate) fn check_magic<E: Endian>(endian: &E, magic: [u8; 8]) -> ParseResult<()> {
+    if endian.convert_u64(magic) == u64::from_le_bytes(*MAGIC[0]) {
+        return Ok(());
+    }
+
+    let actual = if magic == *MAGIC[0] { "little" } else { "big" };
+    Err(ParseError::custom(
+        ErrorKind::InvalidRecord,
+        format_args!(
+            "perf.data magic indicates {actual}-endian data, which does not match the \
+             endianness of the parser"
+        ),
+    ))
+}
+
+/// Iterates over the records in a buffer made up of multiple `perf.data`
+/// data sections.
+///
+/// [`RecordIter`] assumes that the whole buffer is a single sequence of
+/// records parsed with a single config. That doesn't hold for the output of
+/// `perf record --switch-output` once the output files have been concatenated
+/// back together, or for other tools that glue multiple recordings together.
+/// `MultiSectionReader` instead walks over each section in turn and switches
+/// to the config of that section as it goes.
+///
+/// # Supported layouts
+/// The buffer is treated as a sequence of sections, each of which is one of:
+/// - A complete `perf.data` file. The records within its data section are
+///   parsed using the first attr within its attrs section. Everything else in
+///   the file (e.g. the feature sections) is skipped. The next section starts
+///   after the last of the file's sections.
+/// - A pipe-mode stream, as written by `perf record -o -`. This is the 16-byte
+///   pipe header followed by records. It extends until either the end of the
+///   buffer or the start of the next section.
+/// - Bare records, without any header. These are only supported at the very
+///   start of the buffer and are parsed using the config passed to
+///   [`new`](Self::new).
+///
+/// A new section is detected by the `perf.data` magic appearing where the
+/// next record header would otherwise be.
+///
+/// Within any section, a [`HeaderAttr`] record replaces the config used for
+/// the records that follow it. The [`HeaderAttr`] records themselves are
+/// still yielded, as [`Record::Unknown`], so that they can be inspected.
+/// Records of types used only by the `perf` tool are always yielded as
+/// [`Record::Unknown`] with their whole body. The payload following an
+/// [`AuxTrace`] record is skipped over.
+///
+/// # Limitations
+/// - All events within a section are assumed to share the same sample
+///   layout. If there are multiple attrs then the most recent one is used.
+/// - The endianness of the data must match `E`. A section whose magic
+///   indicates a different endianness results in an error. Use
+///   [`Dynamic`](crate::endian::Dynamic) if it is not known at compile time.
+/// - The buffer must be in memory in its entirety.
+///
+/// Once an error has been returned the iterator will yield no further
+/// records.
+///
+/// [`RecordIter`]: crate::parse::RecordIter
+pub struct MultiSectionReader<'p, E> {
+    data: &'p [u8],
+    config: ParseConfig<E>,
+    offset: usize,
+    section_end: usize,
+    next_section: usize,
+    sections: usize,
+    done: bool,
+}
+
+impl<'p, E: Endian> MultiSectionReader<'p, E> {
+    /// Create a new `MultiSectionReader`.
+    ///
+    /// `config` is only used for bare records at the start of `data`, before
+    /// any header or [`HeaderAttr`] record has been seen. Whether
+    /// [size checks](ParseConfig::with_size_checks) and
+    /// [mmap sample ids](ParseConfig::with_mmap_sample_id) are enabled is kept
+    /// when switching to the config of a later section.
+    pub fn new(data: &'p [u8], config: ParseConfig<E>) -> Self {
+        Self {
+            data,
+            config,
+            offset: 0,
+            section_end: data.len(),
+            next_section: data.len(),
+            sections: 0,
+            done: false,
+        }
+    }
+
+    /// The config that will be used to parse the next record.
+    pub fn config(&self) -> &ParseConfig<E> {
+        &self.config
+    }
+
+    /// The number of sections that have been started so far.
+    ///
+    /// Bare records at the start of the buffer are not counted as a section.
+    pub fn sections(&self) -> usize {
+        self.sections
+    }
+
+    fn slice(&self, range: Range<u64>) -> ParseResult<&'p [u8]> {
+        usize::try_from(range.start)
+            .ok()
+            .zip(usize::try_from(range.end).ok())
+            .and_then(|(start, end)| self.data.get(start..end))
+            .ok_or_else(|| {
+                ParseError::custom(
+                    ErrorKind::Eof,
+                    format_args!(
+                        "section {:#x}..{:#x} extends past the end of the data (len {:#x})",
+                        range.start,
+                        range.end,
+                        self.data.len()
+                    ),
+                )
+            })
+    }
+
+    fn is_section_start(&self, offset: usize) -> bool {
+        self.data
+            .get(offset..offset + 8)
+            .is_some_and(|magic| MAGIC.iter().any(|m| &m[..] == magic))
+    }
+
+    /// Parse the header at `start` and set things up to read the records
+    /// within the section that follows it.
+    fn start_section(&mut self, start: usize) -> ParseResult<()> {
+        let base = start as u64;
+        let mut p = Parser::new(&self.data[start..], self.config.clone());
+        let magic: [u8; 8] = p.parse()?;
+        check_magic(self.config.endian(), magic)?;
+        let size: u64 = p.parse()?;
+
+        self.sections += 1;
+
+        if size == PIPE_HEADER_LEN {
+            self.offset = start + PIPE_HEADER_LEN as usize;
+            self.section_end = self.data.len();
+            self.next_section = self.data.len();
+            return Ok(());
+        }
+
+        if size < FILE_HEADER_LEN {
+            return Err(ParseError::custom(
+                ErrorKind::InvalidRecord,
+                format_args!("perf.data header size {size} is too small to be valid"),
+            ));
+        }
+
+        let attr_size: u64 = p.parse()?;
+        let attrs: FileSection = p.parse()?;
+        let data: FileSection = p.parse()?;
+        let event_types: FileSection = p.parse()?;
+        let features: FeatureBitmap = p.parse()?;
+
+        // Offsets within the header are relative to the start of the file.
+        let within = |section: FileSection| {
+            let range = section.range();
+            base.saturating_add(range.start)..base.saturating_add(range.end)
+        };
+
+        let data_range = within(data);
+        let mut end = base.saturating_add(size).max(data_range.end);
+        for section in [attrs, event_types] {
+            end = end.max(within(section).end);
+        }
+
+        // The feature sections are described by a table that directly follows
+        // the data section.
+        let table_len = features.len() as u64 * 16;
+        let table = self.slice(data_range.end..data_range.end.saturating_add(table_len))?;
+        let mut tp = Parser::new(table, self.config.clone());
+        for _ in 0..features.len() {
+            let section: FileSection = tp.parse()?;
+            end = end.max(within(section).end);
+        }
+        end = end.max(data_range.end + table_len);
+
+        if attr_size != 0 && attrs.size >= attr_size {
+            let range = within(FileSection::new(attrs.offset, attr_size));
+            let attr: perf_event_attr =
+                Parser::new(self.slice(range)?, self.config.clone()).parse()?;
+            self.config = self.config.for_attr(&attr);
+        }
+
+        self.slice(data_range.clone())?;
+        self.offset = data_range.start as usize;
+        self.section_end = data_range.end as usize;
+        self.next_section = end.min(self.data.len() as u64) as usize;
+        Ok(())
+    }
+
+    fn next_record(&mut self) -> ParseResult<Option<Record<'p>>> {
+        loop {
+            if self.offset >= self.section_end {
+                if self.next_section >= self.data.len() {
+                    return Ok(None);
+                }
+
+                self.offset = self.next_section;
+                self.section_end = self.data.len();
+                self.next_section = self.data.len();
+            }
+
+            if !self.is_section_start(self.offset) {
+                break;
+            }
+
+            self.start_section(self.offset)?;
+        }
+
+        let header_len = mem::size_of::<perf_event_header>();
+        let rest = &self.data[self.offset..self.section_end];
+        let header: perf_event_header = Parser::new(rest, self.config.clone()).parse()?;
+        let size = header.size as usize;
+        if size < header_len {
+            return Err(ParseError::custom(
+                ErrorKind::InvalidRecord,
+                "header size was too small to be valid",
+            ));
+        }
+
+        let bytes = rest.get(..size).ok_or_else(|| {
+            ParseError::custom(
+                ErrorKind::Eof,
+                format_args!(
+                    "record of size {size} extends past the end of its section ({} bytes left)",
+                    rest.len()
+                ),
+            )
+        })?;
+        self.offset += size;
+
+        if header.type_ < USER_TYPE_START {
+            return Parser::new(bytes, self.config.clone()).parse().map(Some);
+        }
+
+        let body = &bytes[header_len..];
+        match header.type_ {
+            HeaderAttr::RECORD_TYPE => {
+                let record: HeaderAttr = Parser::new(body, self.config.clone()).parse()?;
+                self.config = self.config.for_attr(&record.attr);
+            }
+            AuxTrace::RECORD_TYPE => {
+                let record: AuxTrace = Parser::new(body, self.config.clone()).parse()?;
+                let payload = usize::try_from(record.size).unwrap_or(usize::MAX);
+                self.offset = self.offset.saturating_add(payload).min(self.section_end);
+            }
+            _ => (),
+        }
+
+        Ok(Some(Record::Unknown {
+            ty: header.type_,
+            data: Cow::Borrowed(body),
+        }))
+    }
+}
+
+impl<'p, E: Endian> Iterator for MultiSectionReader<'p, E> {
+    type Item = ParseResult<Record<'p>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.next_record().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::{Big, Little};
+    use crate::util::testdata::{record, u64s, ATTR, SAMPLE};
+
+    /// [`ATTR`] as written by a big-endian machine.
+    #[rustfmt::skip]
+    const ATTR_BE: &[u8] = &[
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, // type, size
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // config
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // sample_period
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // sample_type (IP)
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // read_format
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // flags
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // wakeup_events, bp_type
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // config1
+    ];
+
+    /// [`SAMPLE`] as written by a big-endian machine.
+    #[rustfmt::skip]
+    const SAMPLE_BE: &[u8] = &[
+        0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x10, // header (SAMPLE)
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x10, 0x00, // ip
+    ];
+
+    fn u64s_be(values: &[u64]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    /// Build a complete `perf.data` file with a single attr.
+    fn file(records: &[u8]) -> Vec<u8> {
+        build_file(MAGIC[0], ATTR, records, u64s)
+    }
+
+    /// Build a complete big-endian `perf.data` file with a single attr.
+    fn file_be(records: &[u8]) -> Vec<u8> {
+        build_file(MAGIC[1], ATTR_BE, records, u64s_be)
+    }
+
+    fn build_file(
+        magic: &[u8; 8],
+        attr: &[u8],
+        records: &[u8],
+        u64s: fn(&[u64]) -> Vec<u8>,
+    ) -> Vec<u8> {
+        let attrs = FileSection::new(FILE_HEADER_LEN, attr.len() as u64 + 16);
+        let data = FileSection::new(attrs.offset + attrs.size, records.len() as u64);
+
+        let mut file = magic.to_vec();
+        file.extend(u64s(&[FILE_HEADER_LEN, attrs.size]));
+        file.extend(u64s(&[attrs.offset, attrs.size, data.offset, data.size]));
+        file.extend(u64s(&[0, 0, 0, 0, 0, 0]));
+        file.extend_from_slice(attr);
+        file.extend(u64s(&[0, 0]));
+        file.extend_from_slice(records);
+        file
+    }
+
+    #[test]
+    fn concatenated_files() {
+        let mut data = file(SAMPLE);
+        data.extend(file(&[crate::doctest::MMAP, SAMPLE].concat()));
+
+        let records = MultiSectionReader::new(&data, ParseConfig::<Little>::default())
+            .collect::<ParseResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 3);
+        assert!(matches!(&records[0], Record::Sample(s) if s.ip() == Some(0x401000)));
+        assert!(matches!(records[1], Record::Mmap(_)));
+        assert!(matches!(&records[2], Record::Sample(s) if s.ip() == Some(0x401000)));
+    }
+
+    #[test]
+    fn big_endian_section() {
+        let data = file_be(&[SAMPLE_BE, SAMPLE_BE].concat());
+
+        let records = MultiSectionReader::new(&data, ParseConfig::<Big>::default())
+            .collect::<ParseResult<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(records.len(), 2);
+        for record in &records {
+            assert!(matches!(record, Record::Sample(s) if s.ip() == Some(0x401000)));
+        }
+    }
+
+    #[test]
+    fn mismatched_endian_section() {
+        let data = file_be(SAMPLE_BE);
+
+        let mut reader = MultiSectionReader::new(&data[..], ParseConfig::<Little>::default());
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn pipe_stream_with_header_attr() {
+        let header_attr = record(HeaderAttr::RECORD_TYPE, &[ATTR, &u64s(&[1])].concat());
+
+        let mut data = crate::doctest::MMAP.to_vec();
+        data.extend_from_slice(b"PERFILE2");
+        data.extend(u64s(&[PIPE_HEADER_LEN]));
+        data.extend_from_slice(&header_attr);
+        data.extend_from_slice(SAMPLE);
+
+        let mut reader = MultiSectionReader::new(&data[..], ParseConfig::<Little>::default());
+        assert!(matches!(reader.next(), Some(Ok(Record::Mmap(_)))));
+        assert_eq!(reader.sections(), 0);
+        assert!(matches!(
+            reader.next(),
+            Some(Ok(Record::Unknown {
+                ty: HeaderAttr::RECORD_TYPE,
+                ..
+            }))
+        ));
+        assert_eq!(reader.sections(), 1);
+        assert_eq!(reader.config().sample_type(), SampleFlags::IP);
+        assert!(matches!(reader.next(), Some(Ok(Record::Sample(_)))));
+        assert!(reader.next().is_none());
+    }
+}
@@ -97,19 +97,26 @@
 
 use std::borrow::Cow;
 use std::mem::MaybeUninit;
+use std::ops::ControlFlow;
 
 use perf_event_open_sys::bindings;
 
-use crate::endian::Endian;
+use crate::endian::{Dynamic, Endian, Native};
 use crate::parsebuf::{ParseBufCursor, TrackingParseBuf};
 use crate::util::cow::CowSliceExt;
-use crate::{Record, RecordMetadata, SampleId, Visitor};
-
-used_in_docs!(Record);
+use crate::{
+    DecodedRecord, LazyRecordMetadata, MiscFlags, Record, RecordMetadata, RecordType, SampleFlags,
+    SampleId, Visitor,
+};
 
 pub use crate::config::ParseConfig;
 pub use crate::error::{ErrorKind, ParseError, ParseResult};
-pub use crate::parsebuf::{ParseBuf, ParseBufChunk};
+pub use crate::iter::RecordIter;
+pub use crate::parsebuf::{OwnedBuf, ParseBuf, ParseBufChunk, ReadParseBuf, Take};
+pub use crate::pipe::PipeReader;
+#[cfg(feature = "zstd")]
+pub use crate::stitch::CompressedDecoder;
+pub use crate::stitch::{RecordStitcher, RecordStream};
 
 /// A type that can be parsed
 pub trait Parse<'p>: Sized {
@@ -118,6 +125,53 @@ pub trait Parse<'p>: Sized {
     where
         E: Endian,
         B: ParseBuf<'p>;
+
+    /// Parse an array of `N` elements of `Self`.
+    ///
+    /// This is what the [`Parse`] impl for arrays uses. It exists so that byte
+    /// arrays can be copied out of the buffer in one go instead of one byte at
+    /// a time. There should be no need to override it.
+    #[doc(hidden)]
+    fn parse_array<B, E, const N: usize>(p: &mut Parser<B, E>) -> ParseResult<[Self; N]>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        /// Drops the elements that have been initialized so far if parsing
+        /// one of the later elements fails.
+        struct Guard<T, const N: usize> {
+            array: [MaybeUninit<T>; N],
+            len: usize,
+        }
+
+        impl<T, const N: usize> Drop for Guard<T, N> {
+            fn drop(&mut self) {
+                for elem in &mut self.array[..self.len] {
+                    // SAFETY: The first `len` elements have been initialized.
+                    unsafe { elem.assume_init_drop() };
+                }
+            }
+        }
+
+        let mut guard = Guard::<Self, N> {
+            // SAFETY: An array of MaybeUninit does not require initialization.
+            array: unsafe { MaybeUninit::uninit().assume_init() },
+            len: 0,
+        };
+
+        while guard.len < N {
+            guard.array[guard.len] = MaybeUninit::new(p.parse()?);
+            guard.len += 1;
+        }
+
+        // SAFETY: All N elements have been initialized and [MaybeUninit<T>; N] has
+        //         the same layout as [T; N]. The guard is forgotten so the elements
+        //         will not be dropped twice.
+        let array = unsafe { std::ptr::read(guard.array.as_ptr() as *const [Self; N]) };
+        std::mem::forget(guard);
+
+        Ok(array)
+    }
 }
 
 /// A [`ParseConfig`] combined with a [`ParseBuf`].
@@ -147,6 +201,29 @@ pub struct Parser<B, E> {
     data: TrackingParseBuf<B>,
 }
 
+impl<'p, B> Parser<B, Dynamic>
+where
+    B: ParseBuf<'p>,
+{
+    /// Create a new parser whose endianness is chosen at runtime.
+    ///
+    /// This replaces the endian within `config` with `endian`. See
+    /// [`Dynamic`] for more details on parsing with a runtime endianness.
+    ///
+    /// ```
+    /// # use perf_event_data::endian::{Dynamic, Native};
+    /// # use perf_event_data::parse::{ParseConfig, Parser};
+    /// let data: &[u8] = &[0x00, 0x00, 0x00, 0x01];
+    /// let config = ParseConfig::<Native>::default();
+    /// let mut parser = Parser::new_dynamic(data, config, Dynamic::Big);
+    /// assert_eq!(parser.parse_u32()?, 1);
+    /// # Ok::<_, perf_event_data::parse::ParseError>(())
+    /// ```
+    pub fn new_dynamic<E>(data: B, config: ParseConfig<E>, endian: Dynamic) -> Self {
+        Self::new(data, config.with_endian(endian))
+    }
+}
+
 impl<'p, B, E> Parser<B, E>
 where
     E: Endian,
@@ -172,6 +249,15 @@ where
         self.config.endian()
     }
 
+    /// Consume this `Parser`, returning the underlying [`ParseBuf`].
+    ///
+    /// The returned buffer will be positioned right after the last byte that
+    /// was parsed. For a `&[u8]` buffer this means that it will contain
+    /// exactly the bytes that have not yet been parsed.
+    pub fn into_inner(self) -> B {
+        self.data.into_inner()
+    }
+
     /// Advance the current parser by `offset` and return a new parser for the
     /// data within.
     pub(crate) fn split_at(&mut self, offset: usize) -> ParseResult<Parser<ParseBufCursor<'p>, E>> {
@@ -214,21 +300,26 @@ where
         Ok(Some(&chunk[..len]))
     }
 
-    /// Safe implementation for when we cannot preallocate the buffer.
-    #[cold]
-    fn parse_bytes_slow(&mut self, mut len: usize) -> ParseResult<Vec<u8>> {
-        let mut bytes = Vec::with_capacity(self.safe_capacity_bound::<u8>().min(len));
-
+    /// Pass the next `len` bytes to `f`, one chunk at a time.
+    fn parse_chunks(&mut self, mut len: usize, mut f: impl FnMut(&[u8])) -> ParseResult<()> {
         while len > 0 {
             let mut chunk = self.data.chunk()?;
             chunk.truncate(len);
-            bytes.extend_from_slice(&chunk);
+            f(&chunk);
 
             let chunk_len = chunk.len();
             len -= chunk_len;
             self.data.advance(chunk_len);
         }
 
+        Ok(())
+    }
+
+    /// Safe implementation for when we cannot preallocate the buffer.
+    #[cold]
+    fn parse_bytes_slow(&mut self, len: usize) -> ParseResult<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(self.safe_capacity_bound::<u8>().min(len));
+        self.parse_chunks(len, |chunk| bytes.extend_from_slice(chunk))?;
         Ok(bytes)
     }
 
@@ -249,9 +340,50 @@ where
         Ok(Cow::Owned(bytes))
     }
 
+    /// Parse `len` bytes, copying them into `bump` if they cannot be borrowed
+    /// from the source buffer.
+    ///
+    /// This behaves like [`parse_bytes`](Parser::parse_bytes) except that the
+    /// bytes are allocated within the arena instead of within a [`Vec`].
+    #[cfg(feature = "bumpalo")]
+    pub fn parse_bytes_in<'b>(
+        &mut self,
+        len: usize,
+        bump: &'b bumpalo::Bump,
+    ) -> ParseResult<&'b [u8]>
+    where
+        'p: 'b,
+    {
+        if let Some(bytes) = self.parse_bytes_direct(len)? {
+            return Ok(bytes);
+        }
+
+        // Without a size hint we can't know that len is reasonable so we avoid
+        // allocating all of it up front.
+        match self.data.remaining_hint() {
+            Some(hint) if hint >= len => (),
+            _ => {
+                let capacity = self.safe_capacity_bound::<u8>().min(len);
+                let mut bytes = bumpalo::collections::Vec::with_capacity_in(capacity, bump);
+                self.parse_chunks(len, |chunk| bytes.extend_from_slice(chunk))?;
+                return Ok(bytes.into_bump_slice());
+            }
+        }
+
+        let bytes = bump.alloc_slice_fill_copy(len, 0u8);
+        self.parse_to_slice(unsafe { bytes.align_to_mut().1 })?;
+        Ok(bytes)
+    }
+
     /// Advance the stream by a number of bytes (with checking) but ignore the
     /// resulting bytes.
     fn parse_bytes_ignored(&mut self, mut len: usize) -> ParseResult<()> {
+        // Fast path: if we know there isn't enough data then there is no need to
+        //            walk through the buffer to find that out.
+        if matches!(self.data.remaining_hint(), Some(hint) if hint < len) {
+            return Err(ParseError::eof());
+        }
+
         while len > 0 {
             let chunk = self.data.chunk()?;
             let consumed = chunk.len().min(len);
@@ -263,6 +395,32 @@ where
         Ok(())
     }
 
+    /// Skip over the next `len` bytes without parsing them.
+    ///
+    /// This is useful for skipping reserved or padding bytes. Returns an
+    /// [`ErrorKind::Eof`] error if there are fewer than `len` bytes
+    /// remaining.
+    pub fn skip(&mut self, len: usize) -> ParseResult<()> {
+        self.parse_bytes_ignored(len)
+    }
+
+    /// Parse the next `len` bytes and then skip over the padding needed to
+    /// bring the total number of bytes consumed up to a multiple of `align`.
+    ///
+    /// Only the `len` data bytes are returned. Note that the padding is
+    /// computed from `len` alone so this is only correct when the data starts
+    /// at an `align`-aligned offset.
+    ///
+    /// # Panics
+    /// Panics if `align` is 0.
+    pub fn parse_aligned_bytes(&mut self, len: usize, align: usize) -> ParseResult<Cow<'p, [u8]>> {
+        assert_ne!(align, 0, "align must be non-zero");
+
+        let bytes = self.parse_bytes(len)?;
+        self.parse_bytes_ignored((align - len % align) % align)?;
+        Ok(bytes)
+    }
+
     /// Parse a slice in its entirety. If this returns successfully then the
     /// entire slice has been initialized.
     fn parse_to_slice(&mut self, slice: &mut [MaybeUninit<u8>]) -> ParseResult<()> {
@@ -312,6 +470,41 @@ where
         P::parse(self)
     }
 
+    /// Parse a type and then verify that the entire buffer has been consumed.
+    ///
+    /// If there is any data left over after parsing then this will return an
+    /// error with kind [`ErrorKind::UnexpectedTrailingData`].
+    pub fn parse_exact<P: Parse<'p>>(&mut self) -> ParseResult<P> {
+        let value = self.parse()?;
+        if self.is_at_end()? {
+            return Ok(value);
+        }
+
+        let remaining = match self.data.remaining_hint() {
+            Some(remaining) => remaining,
+            None => self.data.chunk()?.len(),
+        };
+
+        Err(ParseError::custom(
+            ErrorKind::UnexpectedTrailingData,
+            format_args!("{remaining} bytes were left over after parsing"),
+        ))
+    }
+
+    /// Get a hint for how many bytes are remaining in this parser.
+    pub(crate) fn remaining_hint(&self) -> Option<usize> {
+        self.data.remaining_hint()
+    }
+
+    /// Check whether all the data in this parser has been consumed.
+    pub(crate) fn is_at_end(&mut self) -> ParseResult<bool> {
+        match self.data.chunk() {
+            Ok(_) => Ok(false),
+            Err(e) if e.kind() == ErrorKind::Eof => Ok(true),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Parse with an explicit parsing function.
     pub fn parse_with<F, R>(&mut self, func: F) -> ParseResult<R>
     where
@@ -336,6 +529,61 @@ where
         }
     }
 
+    /// Parse a type only if `flag` is set in the `sample_type` of the config.
+    ///
+    /// This is a shorthand for calling [`parse_if`](Self::parse_if) with
+    /// `self.config().sample_type().contains(flag)`, which makes it easier to
+    /// write parsers for custom sample layouts. If `flag` contains multiple
+    /// flags then all of them must be set.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> perf_event_data::parse::ParseResult<()> {
+    /// use perf_event_data::endian::{Endian, Little};
+    /// use perf_event_data::parse::{Parse, ParseBuf, ParseConfig, ParseResult, Parser};
+    /// use perf_event_data::SampleFlags;
+    ///
+    /// struct CustomSample {
+    ///     ip: Option<u64>,
+    ///     time: Option<u64>,
+    /// }
+    ///
+    /// impl<'p> Parse<'p> for CustomSample {
+    ///     fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    ///     where
+    ///         E: Endian,
+    ///         B: ParseBuf<'p>,
+    ///     {
+    ///         Ok(Self {
+    ///             ip: p.parse_flagged(SampleFlags::IP)?,
+    ///             time: p.parse_flagged(SampleFlags::TIME)?,
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// # let mut attr = perf_event_open_sys::bindings::perf_event_attr::default();
+    /// # attr.sample_type = SampleFlags::TIME.bits();
+    /// # let config = ParseConfig::<Little>::from(attr);
+    /// let data: &[u8] = &[0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    /// let sample: CustomSample = Parser::new(data, config).parse()?;
+    /// assert_eq!(sample.ip, None);
+    /// assert_eq!(sample.time, Some(0x10));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse_flagged<P: Parse<'p>>(&mut self, flag: SampleFlags) -> ParseResult<Option<P>> {
+        self.parse_flagged_with(flag, P::parse)
+    }
+
+    /// `parse_flagged` but using an explicit parsing function.
+    pub fn parse_flagged_with<F, R>(&mut self, flag: SampleFlags, func: F) -> ParseResult<Option<R>>
+    where
+        F: FnOnce(&mut Self) -> ParseResult<R>,
+    {
+        let present = self.config().sample_type().contains(flag);
+        self.parse_if_with(present, func)
+    }
+
     /// Parse some input and advance the [`ParseBuf`] so a multiple of `padding`
     /// bytes are consumed.
     ///
@@ -387,6 +635,61 @@ where
         Ok(self.endian().convert_u64(array))
     }
 
+    /// Parse an unsigned integer that is `width` bytes wide out of the source
+    /// data.
+    ///
+    /// This is meant for odd-sized fields (e.g. 3-byte integers) that show up
+    /// within tracepoint data. The bytes are interpreted using the endianness
+    /// of the [`ParseConfig`].
+    ///
+    /// Returns an error with kind [`ErrorKind::UnsupportedData`] if `width` is
+    /// not within `1..=8`.
+    pub fn parse_uint(&mut self, width: usize) -> ParseResult<u64> {
+        if !(1..=8).contains(&width) {
+            return Err(ParseError::custom(
+                ErrorKind::UnsupportedData,
+                format_args!("cannot parse a {width}-byte integer into a u64"),
+            ));
+        }
+
+        let mut array = [0u8; 8];
+        let bytes = &mut array[..width];
+        self.parse_to_slice(unsafe { bytes.align_to_mut().1 })?;
+        Ok(crate::endian::convert_uint(self.endian(), bytes))
+    }
+
+    /// Parse an unsigned LEB128-encoded integer out of the source data.
+    ///
+    /// The records emitted by the kernel only use fixed-width integers but
+    /// some data generated by tooling uses variable-length integers instead.
+    /// LEB128 is independent of endianness so the endianness of the
+    /// [`ParseConfig`] is ignored.
+    ///
+    /// Returns an error with kind [`ErrorKind::InvalidRecord`] if the encoded
+    /// value does not fit within a `u64`.
+    pub fn parse_leb128_u64(&mut self) -> ParseResult<u64> {
+        let mut value = 0u64;
+
+        for i in 0..10 {
+            let byte: u8 = self.parse()?;
+
+            // The 10th byte only has room for the top bit of the u64.
+            if i == 9 && byte > 1 {
+                break;
+            }
+
+            value |= u64::from(byte & 0x7F) << (i * 7);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(ParseError::custom(
+            ErrorKind::InvalidRecord,
+            "LEB128-encoded integer overflowed a u64",
+        ))
+    }
+
     /// Consume the rest of the buffer and return it as a slice.
     pub fn parse_rest(&mut self) -> ParseResult<Cow<'p, [u8]>> {
         let mut bytes = self.data.chunk()?.to_cow();
@@ -454,19 +757,72 @@ where
                 "array length in bytes larger than usize::MAX",
             )
         })?;
-        let bytes = match self.parse_bytes_direct(byte_len)? {
-            Some(bytes) => bytes,
-            None => return Ok(None),
+        let chunk = match self.data.chunk()? {
+            ParseBufChunk::External(chunk) if chunk.len() >= byte_len => &chunk[..byte_len],
+            _ => return Ok(None),
         };
-        let (head, slice, tail) = bytes.align_to();
 
+        // The alignment needs to be checked before advancing the buffer so that
+        // the caller can fall back to parsing the same bytes.
+        let (head, slice, tail) = chunk.align_to();
         if !head.is_empty() || !tail.is_empty() {
             return Ok(None);
         }
 
+        self.data.advance(byte_len);
         Ok(Some(slice))
     }
 
+    /// Parse a slice of `len` `u64`s.
+    ///
+    /// This is equivalent to [`parse_slice::<u64>`](Self::parse_slice) but is
+    /// much faster when the slice cannot be borrowed from the source buffer.
+    /// Instead of parsing each element one at a time, the bytes for the whole
+    /// slice are read out at once and then converted to the native endian in
+    /// bulk. This matters for large arrays, such as callchains, parsed from
+    /// data with a non-native endianness.
+    pub fn parse_u64_slice(&mut self, len: usize) -> ParseResult<Cow<'p, [u64]>> {
+        // SAFETY: any 8 bytes are a valid u64.
+        if let Some(slice) = unsafe { self.parse_slice_direct(len)? } {
+            return Ok(Cow::Borrowed(slice));
+        }
+
+        let byte_len = len.checked_mul(std::mem::size_of::<u64>()).ok_or_else(|| {
+            ParseError::custom(
+                ErrorKind::InvalidRecord,
+                "array length in bytes larger than usize::MAX",
+            )
+        })?;
+        let bytes = self.parse_bytes(byte_len)?;
+        let endian = self.endian();
+
+        Ok(Cow::Owned(
+            bytes
+                .chunks_exact(std::mem::size_of::<u64>())
+                .map(|chunk| endian.convert_u64(chunk.try_into().unwrap()))
+                .collect(),
+        ))
+    }
+
+    /// Parse a `u64` element count for a slice of `T`s.
+    ///
+    /// This returns an [`ErrorKind::InvalidRecord`] error if the size of the
+    /// slice, in bytes, would not fit in a `usize`.
+    pub(crate) fn parse_slice_len<T>(&mut self) -> ParseResult<usize> {
+        let error = || {
+            ParseError::custom(
+                ErrorKind::InvalidRecord,
+                "array length in bytes larger than usize::MAX",
+            )
+        };
+
+        let len = usize::try_from(self.parse_u64()?).map_err(|_| error())?;
+        len.checked_mul(std::mem::size_of::<T>())
+            .ok_or_else(error)?;
+
+        Ok(len)
+    }
+
     /// Attempt to directly transmute a slice in the source buffer and, if that
     /// fails, parse it instead.
     ///
@@ -486,7 +842,20 @@ where
 
     /// Parse a sequence of `len` `T`s.
     pub fn parse_repeated<T: Parse<'p>>(&mut self, len: usize) -> ParseResult<Vec<T>> {
-        let mut vec = Vec::with_capacity(len.min(self.safe_capacity_bound::<T>()));
+        self.parse_repeated_with(len, T::parse)
+    }
+
+    /// Parse a sequence of `len` `T`s into a [`Vec`] allocated within `bump`.
+    ///
+    /// [`Vec`]: bumpalo::collections::Vec
+    #[cfg(feature = "bumpalo")]
+    pub fn parse_repeated_in<'b, T: Parse<'p>>(
+        &mut self,
+        len: usize,
+        bump: &'b bumpalo::Bump,
+    ) -> ParseResult<bumpalo::collections::Vec<'b, T>> {
+        let capacity = len.min(self.safe_capacity_bound::<T>());
+        let mut vec = bumpalo::collections::Vec::with_capacity_in(capacity, bump);
         for _ in 0..len {
             vec.push(self.parse()?);
         }
@@ -494,6 +863,23 @@ where
         Ok(vec)
     }
 
+    /// Parse a sequence of `len` elements using an explicit parsing function.
+    ///
+    /// This is useful when the layout of each element depends on the config
+    /// or on some other state so that it cannot be described by a [`Parse`]
+    /// impl.
+    pub fn parse_repeated_with<F, T>(&mut self, len: usize, mut func: F) -> ParseResult<Vec<T>>
+    where
+        F: FnMut(&mut Self) -> ParseResult<T>,
+    {
+        let mut vec = Vec::with_capacity(len.min(self.safe_capacity_bound::<T>()));
+        for _ in 0..len {
+            vec.push(func(self)?);
+        }
+
+        Ok(vec)
+    }
+
     /// Parse record metadata and return a parser for the bytes of the record.
     ///
     /// If you have already read the record header, use
@@ -506,41 +892,81 @@ where
         self.parse_metadata_with_header(header)
     }
 
-    fn parse_metadata_with_header_impl(
+    /// Parse the record header and split off the record body without parsing
+    /// the `sample_id` trailer.
+    ///
+    /// This is like [`parse_metadata`](Parser::parse_metadata) except that the
+    /// [`SampleId`] is only parsed when it is requested from the returned
+    /// [`LazyRecordMetadata`]. Use this when you will be discarding most
+    /// records based on their type.
+    pub fn parse_metadata_lazy(
+        &mut self,
+    ) -> ParseResult<(Parser<impl ParseBuf<'p>, E>, LazyRecordMetadata<'p, E>)> {
+        let header = self.parse()?;
+        self.split_metadata(header)
+    }
+
+    fn split_metadata(
         &mut self,
         header: bindings::perf_event_header,
-    ) -> ParseResult<(Parser<ParseBufCursor<'p>, E>, RecordMetadata)> {
+    ) -> ParseResult<(Parser<ParseBufCursor<'p>, E>, LazyRecordMetadata<'p, E>)> {
         use perf_event_open_sys::bindings::*;
-        use std::mem;
 
-        let data_len = (header.size as usize)
-            .checked_sub(mem::size_of_val(&header))
-            .ok_or_else(|| {
-                ParseError::custom(
-                    ErrorKind::InvalidRecord,
-                    "header size was too small to be valid",
-                )
-            })?;
+        let data_len = record_data_len(&header)?;
         let mut rp = self.split_at(data_len)?;
-        // MMAP and SAMPLE records do not have the sample_id struct.
-        // All other records do.
-        let (p, sample_id) = match header.type_ {
-            PERF_RECORD_MMAP | PERF_RECORD_SAMPLE => (rp, SampleId::default()),
+        // SAMPLE records do not have the sample_id struct. MMAP records only
+        // have it if the config says so. All other records do, but only if
+        // sample_id_all is set. Checking for that here avoids splitting the
+        // record a second time.
+        let (p, sample_id, body_len) = match header.type_ {
+            PERF_RECORD_SAMPLE => (rp, None, data_len),
+            PERF_RECORD_MMAP if !rp.config().mmap_sample_id() => (rp, None, data_len),
+            _ if !rp.config().sample_id_all() => (rp, None, data_len),
             _ => {
-                let remaining_len = data_len
-                    .checked_sub(SampleId::estimate_len(rp.config()))
-                    .ok_or_else(|| ParseError::custom(
-                        ErrorKind::InvalidRecord,
-                        "config has sample_id_all bit set but record does not have enough data to store the sample_id"
-                    ))?;
+                let sample_id_len = SampleId::estimate_len(rp.config());
+                let remaining_len = data_len.checked_sub(sample_id_len).ok_or_else(|| {
+                    ParseError::custom(
+                        ErrorKind::TruncatedSampleId,
+                        format_args!(
+                            "config has sample_id_all bit set but record does not have enough \
+                             data to store the sample_id (expected {sample_id_len} bytes, \
+                             record has {data_len} bytes)"
+                        ),
+                    )
+                })?;
 
                 let p = rp.split_at(remaining_len)?;
-                (p, rp.parse()?)
+                (p, Some(rp), remaining_len)
             }
         };
 
-        let metadata = RecordMetadata::new(header, sample_id);
-        Ok((p, metadata))
+        if p.config().size_checks() {
+            let ty = RecordType::new(header.type_);
+            let min_len = match ty {
+                RecordType::SAMPLE => Some(p.config().min_sample_len()),
+                _ => ty.min_len(),
+            };
+
+            if let Some(min_len) = min_len.filter(|&min_len| body_len < min_len) {
+                return Err(ParseError::custom(
+                    ErrorKind::InvalidRecord,
+                    format_args!(
+                        "{ty:?} record body is {body_len} bytes but records of that type must \
+                         be at least {min_len} bytes"
+                    ),
+                ));
+            }
+        }
+
+        Ok((p, LazyRecordMetadata::new(header, sample_id)))
+    }
+
+    fn parse_metadata_with_header_impl(
+        &mut self,
+        header: bindings::perf_event_header,
+    ) -> ParseResult<(Parser<ParseBufCursor<'p>, E>, RecordMetadata)> {
+        let (p, metadata) = self.split_metadata(header)?;
+        Ok((p, metadata.into_metadata()?))
     }
 
     /// Parse the record metadata and return a parser for only the record bytes.
@@ -557,6 +983,248 @@ where
         self.parse_record_with_header(visitor, header)
     }
 
+    /// Parse a [`Record`] and pass it to `f`.
+    ///
+    /// This is shorthand for parsing a [`Record`] and then calling `f` on it.
+    /// It is convenient for one-off cases where writing out a full
+    /// [`Visitor`] impl would be overkill. Use [`parse_record`] with a
+    /// [`Visitor`] instead if you want to avoid building the [`Record`] for
+    /// record types that you are not interested in.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> perf_event_data::parse::ParseResult<()> {
+    /// # use perf_event_data::endian::Little;
+    /// # use perf_event_data::parse::{ParseConfig, Parser};
+    /// use perf_event_data::Record;
+    ///
+    /// # let data = perf_event_data::doctest::MMAP;
+    /// # let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+    /// let is_mmap = parser.visit_record_with(|record| matches!(record, Record::Mmap(_)))?;
+    /// # assert!(is_mmap);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`parse_record`]: Self::parse_record
+    pub fn visit_record_with<F, T>(&mut self, f: F) -> ParseResult<T>
+    where
+        F: FnOnce(Record<'p>) -> T,
+    {
+        self.parse::<Record>().map(f)
+    }
+
+    /// Parse a record using `config` instead of the config of this `Parser`.
+    ///
+    /// This is useful when different records within the same stream need
+    /// different configs, such as in a `perf.data` file containing multiple
+    /// events. `config` is used for the entire record, including its header,
+    /// and the config of this `Parser` is left unchanged afterwards.
+    pub fn parse_record_with_config<V: Visitor<'p>>(
+        &mut self,
+        visitor: V,
+        config: &ParseConfig<E>,
+    ) -> ParseResult<V::Output> {
+        let original = std::mem::replace(&mut self.config, config.clone());
+        let result = self.parse_record(visitor);
+        self.config = original;
+        result
+    }
+
+    /// Parse a [`perf_event_attr`] and build the [`ParseConfig`] for the
+    /// records emitted by its event.
+    ///
+    /// This is the same as parsing a [`perf_event_attr`] and converting it
+    /// into a [`ParseConfig`], except that the endian of this `Parser` is
+    /// carried over to the new config. This makes it a single step to go
+    /// from the attrs stored within a `perf.data` file to a config that can
+    /// parse the records that follow. All the fields of the attr that affect
+    /// parsing are used: `sample_type`, `read_format`, `sample_id_all`,
+    /// `sample_regs_user`, `sample_regs_intr`, and the `HW_INDEX` and
+    /// `COUNTERS` bits of `branch_sample_type`. Options that are not part of
+    /// the attr, such as [`with_size_checks`], are left at their defaults.
+    ///
+    /// [`perf_event_attr`]: bindings::perf_event_attr
+    /// [`with_size_checks`]: ParseConfig::with_size_checks
+    pub fn parse_attr_config(&mut self) -> ParseResult<ParseConfig<E>> {
+        let attr: bindings::perf_event_attr = self.parse()?;
+        Ok(ParseConfig::<Native>::from(&attr).with_endian(self.endian().clone()))
+    }
+
+    /// Parse a [`Record`] along with its [`RecordMetadata`].
+    ///
+    /// This is equivalent to parsing a [`Record`] except that the metadata,
+    /// which would normally be discarded, is returned as well.
+    pub fn parse_record_and_metadata(&mut self) -> ParseResult<(Record<'p>, RecordMetadata)> {
+        self.parse_record(crate::records::RecordMetadataVisitor)
+    }
+
+    /// Parse a [`Record`] along with its [`RecordMetadata`] as a single
+    /// [`DecodedRecord`].
+    ///
+    /// This is the same as [`parse_record_and_metadata`] except that the
+    /// result is wrapped up in a named type instead of a tuple.
+    ///
+    /// [`parse_record_and_metadata`]: Self::parse_record_and_metadata
+    pub fn parse_decoded(&mut self) -> ParseResult<DecodedRecord<'p>> {
+        self.parse_record_and_metadata().map(DecodedRecord::from)
+    }
+
+    /// Parse a [`Record`], failing if any of its data would have to be copied.
+    ///
+    /// Normally, any data that cannot be borrowed from the underlying buffer
+    /// is copied into an owned [`Cow`]. This happens when the endianness is
+    /// not native, when an array within the record is not properly aligned,
+    /// or when a record is split across multiple chunks of the [`ParseBuf`].
+    /// This method instead returns an error with kind
+    /// [`ErrorKind::WouldAllocate`] in those cases. In practice, that means it
+    /// is only useful when parsing from a `&[u8]` with the [`Native`] endian.
+    ///
+    /// This only detects copies, it does not prevent them. Apart from the
+    /// non-native endian case, which is checked up front, the record is
+    /// fully parsed, copying whatever it needs to, before the check is made.
+    /// Either way, the record is consumed from the buffer when a
+    /// [`ErrorKind::WouldAllocate`] error is returned. Note that
+    /// [`Record::Sample`] always boxes the sample and so will still allocate.
+    ///
+    /// [`Native`]: crate::endian::Native
+    pub fn parse_record_borrowed(&mut self) -> ParseResult<Record<'p>> {
+        if !self.endian().is_native() {
+            self.skip_record()?;
+            return Err(ParseError::custom(
+                ErrorKind::WouldAllocate,
+                "records parsed with a non-native endian cannot be borrowed",
+            ));
+        }
+
+        let record: Record = self.parse()?;
+        if !record.is_borrowed() {
+            return Err(ParseError::custom(
+                ErrorKind::WouldAllocate,
+                "some of the data within the record could not be borrowed",
+            ));
+        }
+
+        Ok(record)
+    }
+
+    /// Parse every remaining record in the buffer.
+    ///
+    /// Reaching the end of the buffer in between two records is not an error.
+    /// However, if the buffer ends partway through a record then the error is
+    /// returned and the records parsed before it are discarded. Use
+    /// [`RecordIter`] if you need access to those records.
+    pub fn parse_all(mut self) -> ParseResult<Vec<Record<'p>>> {
+        let mut records = Vec::new();
+        while !self.is_at_end()? {
+            records.push(self.parse()?);
+        }
+
+        Ok(records)
+    }
+
+    /// Parse the header of the next record and decode its fields.
+    ///
+    /// Returns the record type, misc flags, and total size of the record
+    /// (including the header). Only the header is consumed. To continue
+    /// parsing the record either reconstruct the [`perf_event_header`] and
+    /// pass it to [`parse_record_with_header`](Self::parse_record_with_header)
+    /// or skip over the `size - 8` bytes of the record body.
+    ///
+    /// [`perf_event_header`]: bindings::perf_event_header
+    pub fn read_header(&mut self) -> ParseResult<(RecordType, MiscFlags, u16)> {
+        let header: bindings::perf_event_header = self.parse()?;
+
+        Ok((
+            RecordType::new(header.type_),
+            MiscFlags::from_bits_retain(header.misc),
+            header.size,
+        ))
+    }
+
+    /// Skip over the next record without parsing its body.
+    ///
+    /// Returns the header of the record that was skipped.
+    pub fn skip_record(&mut self) -> ParseResult<bindings::perf_event_header> {
+        let header = self.parse()?;
+        self.parse_bytes_ignored(record_data_len(&header)?)?;
+        Ok(header)
+    }
+
+    /// Parse the next record whose type is within `types`, skipping over any
+    /// records that come before it.
+    ///
+    /// This stops at the first matching record and leaves the parser
+    /// positioned just after it. Call it repeatedly to find every matching
+    /// record.
+    ///
+    /// Records that are skipped only have their header parsed, which makes
+    /// this much faster than parsing every record when only a few record
+    /// types are of interest. Returns `None` if the end of the buffer was
+    /// reached before a matching record was found.
+    pub fn next_record_of_type<V: Visitor<'p>>(
+        &mut self,
+        types: &[u32],
+        visitor: V,
+    ) -> ParseResult<Option<V::Output>> {
+        loop {
+            if self.is_at_end()? {
+                return Ok(None);
+            }
+
+            let header: bindings::perf_event_header = self.parse()?;
+            if types.contains(&header.type_) {
+                return self.parse_record_with_header(visitor, header).map(Some);
+            }
+
+            self.parse_bytes_ignored(record_data_len(&header)?)?;
+        }
+    }
+
+    /// Parse records using `visitor` until `f` asks to stop.
+    ///
+    /// Each record is parsed using a clone of `visitor` and the result is
+    /// passed to `f`. As soon as `f` returns [`ControlFlow::Break`] this
+    /// returns the break value. The parser is left positioned just after the
+    /// record that `f` stopped on so none of the records that follow it are
+    /// parsed, and they can still be parsed later on. Returns `None` if the
+    /// end of the buffer was reached without `f` breaking.
+    ///
+    /// # Example
+    /// Find the first MMAP record without parsing any of the records after it.
+    /// ```
+    /// # fn main() -> perf_event_data::parse::ParseResult<()> {
+    /// # use perf_event_data::endian::Little;
+    /// # use perf_event_data::parse::{ParseConfig, Parser};
+    /// # use perf_event_data::{MapVisitor, Record};
+    /// use std::ops::ControlFlow;
+    ///
+    /// # let data = perf_event_data::doctest::MMAP.repeat(2);
+    /// # let mut parser = Parser::new(&data[..], ParseConfig::<Little>::default());
+    /// let mmap = parser.parse_records_while(MapVisitor::new(|record| record), |record| {
+    ///     match record {
+    ///         Record::Mmap(mmap) => ControlFlow::Break(mmap),
+    ///         _ => ControlFlow::Continue(()),
+    ///     }
+    /// })?;
+    /// # assert!(mmap.is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse_records_while<V, F, R>(&mut self, visitor: V, mut f: F) -> ParseResult<Option<R>>
+    where
+        V: Visitor<'p> + Clone,
+        F: FnMut(V::Output) -> ControlFlow<R>,
+    {
+        while !self.is_at_end()? {
+            if let ControlFlow::Break(value) = f(self.parse_record(visitor.clone())?) {
+                return Ok(Some(value));
+            }
+        }
+
+        Ok(None)
+    }
+
     fn parse_record_impl<V: Visitor<'p>>(
         self,
         visitor: V,
@@ -566,7 +1234,7 @@ where
 
         let mut p = Parser::new(self.data, self.config.with_misc(metadata.misc()));
 
-        Ok(match metadata.ty() {
+        let output = match metadata.ty() {
             PERF_RECORD_MMAP => visitor.visit_mmap(p.parse()?, metadata),
             PERF_RECORD_LOST => visitor.visit_lost(p.parse()?, metadata),
             PERF_RECORD_COMM => visitor.visit_comm(p.parse()?, metadata),
@@ -580,6 +1248,7 @@ where
             PERF_RECORD_AUX => visitor.visit_aux(p.parse()?, metadata),
             PERF_RECORD_ITRACE_START => visitor.visit_itrace_start(p.parse()?, metadata),
             PERF_RECORD_LOST_SAMPLES => visitor.visit_lost_samples(p.parse()?, metadata),
+            PERF_RECORD_SWITCH => visitor.visit_switch(p.parse()?, metadata),
             PERF_RECORD_SWITCH_CPU_WIDE => visitor.visit_switch_cpu_wide(p.parse()?, metadata),
             PERF_RECORD_NAMESPACES => visitor.visit_namespaces(p.parse()?, metadata),
             PERF_RECORD_KSYMBOL => visitor.visit_ksymbol(p.parse()?, metadata),
@@ -588,7 +1257,30 @@ where
             PERF_RECORD_TEXT_POKE => visitor.visit_text_poke(p.parse()?, metadata),
             PERF_RECORD_AUX_OUTPUT_HW_ID => visitor.visit_aux_output_hw_id(p.parse()?, metadata),
             _ => visitor.visit_unknown(p.parse_rest()?, metadata),
-        })
+        };
+
+        if p.config().size_checks() {
+            p.check_consumed()?;
+        }
+
+        Ok(output)
+    }
+
+    /// Check that there is no data left over after parsing a record body.
+    fn check_consumed(&mut self) -> ParseResult<()> {
+        if self.is_at_end()? {
+            return Ok(());
+        }
+
+        let consumed = self.data.offset();
+        let remaining = self.parse_rest()?.len();
+        Err(ParseError::custom(
+            ErrorKind::InvalidRecord,
+            format_args!(
+                "record body was {} bytes but only {consumed} bytes were parsed",
+                consumed + remaining
+            ),
+        ))
     }
 
     /// Same as [`parse_record`](Self::parse_record) but required that the
@@ -614,6 +1306,18 @@ where
     }
 }
 
+/// The length of the record body described by `header`.
+fn record_data_len(header: &bindings::perf_event_header) -> ParseResult<usize> {
+    (header.size as usize)
+        .checked_sub(std::mem::size_of_val(header))
+        .ok_or_else(|| {
+            ParseError::custom(
+                ErrorKind::InvalidRecord,
+                "header size was too small to be valid",
+            )
+        })
+}
+
 impl<'p> Parse<'p> for u8 {
     fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
     where
@@ -622,6 +1326,14 @@ impl<'p> Parse<'p> for u8 {
     {
         p.parse_u8()
     }
+
+    fn parse_array<B, E, const N: usize>(p: &mut Parser<B, E>) -> ParseResult<[Self; N]>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        p.parse_array()
+    }
 }
 
 impl<'p> Parse<'p> for u16 {
@@ -654,13 +1366,16 @@ impl<'p> Parse<'p> for u64 {
     }
 }
 
-impl<'p, const N: usize> Parse<'p> for [u8; N] {
+impl<'p, T, const N: usize> Parse<'p> for [T; N]
+where
+    T: Parse<'p>,
+{
     fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
     where
         E: Endian,
         B: ParseBuf<'p>,
     {
-        p.parse_array()
+        T::parse_array(p)
     }
 }
 
@@ -681,7 +1396,7 @@ impl<'p> Parse<'p> for bindings::perf_event_header {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::endian::Native;
+    use crate::endian::{Big, Little, Native};
 
     #[test]
     fn parse_rest() {
@@ -691,4 +1406,548 @@ mod tests {
 
         assert_eq!(data, &*rest);
     }
+
+    #[test]
+    fn skip_bytes() {
+        let data: &[u8] = &[1, 2, 3, 4, 5];
+        let mut parser = Parser::new(data, ParseConfig::<Native>::default());
+
+        parser.skip(3).unwrap();
+        assert_eq!(parser.parse_u8().unwrap(), 4);
+        assert_eq!(parser.skip(2).unwrap_err().kind(), ErrorKind::Eof);
+    }
+
+    #[test]
+    fn size_checks_reject_leftover_data() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x28, 0x00, // header (FORK)
+            0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // pid, ppid
+            0x03, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, // tid, ptid
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // time
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, // extra data
+        ];
+
+        let config = ParseConfig::<Little>::default();
+        let mut parser = Parser::new(data, config.clone());
+        parser.parse::<Record>().unwrap();
+
+        let mut parser = Parser::new(data, config.with_size_checks(true));
+        let error = parser.parse::<Record>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+        assert!(error.to_string().contains("was 32 bytes but only 24"));
+
+        let mut data = data[..32].to_vec();
+        data[6] = 0x20;
+        let config = ParseConfig::<Little>::default().with_size_checks(true);
+        let mut parser = Parser::new(&data[..], config);
+        parser.parse::<Record>().unwrap();
+    }
+
+    #[test]
+    fn size_checks_reject_undersized_records() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x00, // header (FORK)
+            0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // pid, ppid
+            0x03, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, // tid, ptid
+        ];
+
+        let config = ParseConfig::<Little>::default();
+        let mut parser = Parser::new(data, config.clone());
+        let error = parser.parse::<Record>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+
+        let mut parser = Parser::new(data, config.with_size_checks(true));
+        let error = parser.parse_metadata().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+        assert!(error.to_string().contains("must be at least 24 bytes"));
+    }
+
+    #[test]
+    fn parse_aligned_bytes() {
+        let data: &[u8] = &[1, 2, 3, 0, 5, 6, 7, 8, 9];
+        let mut parser = Parser::new(data, ParseConfig::<Native>::default());
+
+        assert_eq!(&*parser.parse_aligned_bytes(3, 4).unwrap(), &[1, 2, 3]);
+        assert_eq!(&*parser.parse_aligned_bytes(4, 4).unwrap(), &[5, 6, 7, 8]);
+        assert_eq!(
+            parser.parse_aligned_bytes(1, 4).unwrap_err().kind(),
+            ErrorKind::Eof
+        );
+    }
+
+    #[test]
+    fn parse_all_records() {
+        let data = crate::doctest::MMAP.repeat(2);
+        let parser = Parser::new(&data[..], ParseConfig::<Little>::default());
+        let records = parser.parse_all().unwrap();
+        assert_eq!(records.len(), 2);
+
+        let data = &data[..crate::doctest::MMAP.len() + 8];
+        let parser = Parser::new(data, ParseConfig::<Little>::default());
+        assert_eq!(parser.parse_all().unwrap_err().kind(), ErrorKind::Eof);
+    }
+
+    #[test]
+    fn parse_uint_odd_widths() {
+        let data: &[u8] = &[0x01, 0x02, 0x03];
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        assert_eq!(parser.parse_uint(3).unwrap(), 0x030201);
+
+        let mut parser = Parser::new(data, ParseConfig::<Big>::default());
+        assert_eq!(parser.parse_uint(3).unwrap(), 0x010203);
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        assert_eq!(
+            parser.parse_uint(9).unwrap_err().kind(),
+            ErrorKind::UnsupportedData
+        );
+        assert_eq!(parser.parse_uint(4).unwrap_err().kind(), ErrorKind::Eof);
+    }
+
+    #[test]
+    #[cfg(feature = "bumpalo")]
+    fn parse_into_bump() {
+        let bump = bumpalo::Bump::new();
+        let data: &[u8] = &[
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C,
+        ];
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        assert_eq!(parser.parse_bytes_in(4, &bump).unwrap(), &[1, 2, 3, 4]);
+
+        let values = parser.parse_repeated_in::<u32>(2, &bump).unwrap();
+        assert_eq!(&values[..], &[0x08070605, 0x0C0B0A09]);
+
+        assert_eq!(
+            parser.parse_bytes_in(1, &bump).unwrap_err().kind(),
+            ErrorKind::Eof
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bumpalo")]
+    fn parse_into_bump_without_hint() {
+        use crate::parse::ReadParseBuf;
+
+        let bump = bumpalo::Bump::new();
+        let data: &[u8] = &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+
+        // A small buffer forces the bytes to be gathered from multiple chunks.
+        let buf = ReadParseBuf::with_capacity(3, data);
+        let mut parser = Parser::new(buf, ParseConfig::<Little>::default());
+        assert_eq!(parser.parse_bytes_in(5, &bump).unwrap(), &[1, 2, 3, 4, 5]);
+        assert_eq!(parser.parse_u8().unwrap(), 6);
+
+        assert_eq!(
+            parser.parse_bytes_in(4, &bump).unwrap_err().kind(),
+            ErrorKind::Eof
+        );
+    }
+
+    #[test]
+    fn dynamic_native_is_zero_copy() {
+        let data: &[u64] = &[1, 2];
+        let bytes: &[u8] = unsafe { data.align_to().1 };
+
+        let config = ParseConfig::<Native>::default();
+        let mut parser = Parser::new_dynamic(bytes, config, Dynamic::native());
+        assert!(parser.endian().is_native());
+
+        let slice = unsafe { parser.parse_slice::<u64>(2).unwrap() };
+        assert!(matches!(slice, Cow::Borrowed(_)));
+        assert_eq!(&*slice, &[1, 2]);
+    }
+
+    #[test]
+    fn parse_u64_slice() {
+        let data: &[u64] = &[0, 1, 2, 3];
+        let bytes: &[u8] = unsafe { data.align_to().1 };
+
+        let mut parser = Parser::new(bytes, ParseConfig::<Native>::default());
+        let slice = parser.parse_u64_slice(2).unwrap();
+        assert!(matches!(slice, Cow::Borrowed(_)));
+        assert_eq!(&*slice, &[0, 1]);
+
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0xFF,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02,
+        ];
+
+        let mut parser = Parser::new(data, ParseConfig::<Big>::default());
+        parser.skip(1).unwrap();
+        let slice = parser.parse_u64_slice(2).unwrap();
+        assert!(matches!(slice, Cow::Owned(_)));
+        assert_eq!(&*slice, &[0x1, 0x102]);
+
+        let mut parser = Parser::new(data, ParseConfig::<Big>::default());
+        assert_eq!(
+            parser.parse_u64_slice(3).unwrap_err().kind(),
+            ErrorKind::Eof
+        );
+    }
+
+    #[test]
+    fn misaligned_slice_falls_back_to_parsing() {
+        // Offset the values by one byte so that they are misaligned.
+        let mut storage = [0u64; 3];
+        let bytes: &mut [u8] = unsafe { storage.align_to_mut().1 };
+        bytes[1..9].copy_from_slice(&7u64.to_ne_bytes());
+        bytes[9..17].copy_from_slice(&9u64.to_ne_bytes());
+
+        let mut parser = Parser::new(&bytes[1..17], ParseConfig::<Native>::default());
+        let slice = unsafe { parser.parse_slice::<u64>(2).unwrap() };
+        assert!(matches!(slice, Cow::Owned(_)));
+        assert_eq!(&*slice, &[7, 9]);
+    }
+
+    #[test]
+    fn parse_record_with_other_config() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // header (SAMPLE)
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // time
+        ];
+
+        let config = ParseConfig::<Little>::default().with_sample_type(crate::SampleFlags::TIME);
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        let record = parser
+            .parse_record_with_config(crate::records::RecordVisitor, &config)
+            .unwrap();
+
+        match record {
+            Record::Sample(sample) => assert_eq!(sample.time(), Some(1)),
+            _ => panic!("expected a SAMPLE record, got {record:?}"),
+        }
+        assert_eq!(parser.config().sample_type(), crate::SampleFlags::empty());
+    }
+
+    #[test]
+    fn parse_leb128() {
+        let data: &[u8] = &[0x00, 0x7F, 0xE5, 0x8E, 0x26];
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        assert_eq!(parser.parse_leb128_u64().unwrap(), 0);
+        assert_eq!(parser.parse_leb128_u64().unwrap(), 0x7F);
+        assert_eq!(parser.parse_leb128_u64().unwrap(), 624485);
+        assert_eq!(
+            parser.parse_leb128_u64().unwrap_err().kind(),
+            ErrorKind::Eof
+        );
+
+        let data: &[u8] = &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01];
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        assert_eq!(parser.parse_leb128_u64().unwrap(), u64::MAX);
+
+        let data: &[u8] = &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02];
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        assert_eq!(
+            parser.parse_leb128_u64().unwrap_err().kind(),
+            ErrorKind::InvalidRecord
+        );
+    }
+
+    #[test]
+    fn parse_metadata_lazy_sample_id() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00, // header (LOST)
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // id
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // lost
+            0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sample_id time
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00, // header (LOST)
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // id
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // lost
+            0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sample_id time
+        ];
+
+        let mut attr = bindings::perf_event_attr::default();
+        attr.sample_type = bindings::PERF_SAMPLE_TIME as _;
+        attr.set_sample_id_all(1);
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::from(&attr));
+
+        // Discard the first record without parsing its sample_id.
+        let (_, metadata) = parser.parse_metadata_lazy().unwrap();
+        assert_eq!(metadata.ty(), bindings::PERF_RECORD_LOST);
+
+        let (mut rp, metadata) = parser.parse_metadata_lazy().unwrap();
+        let lost: crate::Lost = rp.parse().unwrap();
+        assert_eq!(lost.id, 4);
+        assert_eq!(metadata.sample_id().unwrap().time(), Some(6));
+        assert!(parser.is_at_end().unwrap());
+    }
+
+    #[test]
+    fn truncated_sample_id() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // header (LOST)
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut attr = bindings::perf_event_attr::default();
+        attr.sample_type = (bindings::PERF_SAMPLE_TIME | bindings::PERF_SAMPLE_ID) as _;
+        attr.set_sample_id_all(1);
+
+        let config = ParseConfig::<Little>::from(attr);
+        let mut parser = Parser::new(data, config);
+        let error = parser.parse::<Record>().unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::TruncatedSampleId);
+    }
+
+    #[test]
+    fn parse_exact() {
+        let data: &[u8] = &[1, 2, 3, 4, 5, 6, 7, 8];
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        assert_eq!(parser.parse_exact::<u64>().unwrap(), 0x0807060504030201);
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        let error = parser.parse_exact::<u32>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedTrailingData);
+    }
+
+    #[test]
+    fn into_inner() {
+        let data: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        let _ = parser.parse_u32().unwrap();
+
+        assert_eq!(parser.into_inner(), &[5, 6]);
+    }
+
+    #[test]
+    fn next_record_of_type() {
+        let mut data = Vec::new();
+        data.extend_from_slice(crate::doctest::MMAP);
+        data.extend_from_slice(&[
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x00, // header (LOST)
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // id
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // lost
+        ]);
+        data.extend_from_slice(crate::doctest::MMAP);
+
+        let mut parser = Parser::new(&data[..], ParseConfig::<Little>::default());
+        let record = parser
+            .next_record_of_type(&[bindings::PERF_RECORD_LOST], crate::records::RecordVisitor)
+            .unwrap();
+        assert!(matches!(record, Some(Record::Lost(_))));
+
+        let record = parser
+            .next_record_of_type(&[bindings::PERF_RECORD_LOST], crate::records::RecordVisitor)
+            .unwrap();
+        assert!(record.is_none());
+    }
+
+    #[test]
+    fn parse_records_while() {
+        let mut data = Vec::new();
+        data.extend_from_slice(crate::doctest::MMAP);
+        data.extend_from_slice(&[
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x00, // header (LOST)
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // id
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // lost
+        ]);
+        data.extend_from_slice(&[0xFF; 8]);
+
+        let mut parsed = 0;
+        let mut parser = Parser::new(&data[..], ParseConfig::<Little>::default());
+        let lost = parser
+            .parse_records_while(crate::records::RecordVisitor, |record| {
+                parsed += 1;
+                match record {
+                    Record::Lost(lost) => ControlFlow::Break(lost),
+                    _ => ControlFlow::Continue(()),
+                }
+            })
+            .unwrap();
+
+        assert_eq!(parsed, 2);
+        assert_eq!(lost.map(|lost| lost.lost), Some(2));
+        assert_eq!(&*parser.parse_rest().unwrap(), &[0xFF; 8]);
+
+        let mut parser = Parser::new(&data[..72], ParseConfig::<Little>::default());
+        let result = parser
+            .parse_records_while(crate::records::RecordVisitor, |_| {
+                ControlFlow::<()>::Continue(())
+            })
+            .unwrap();
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn parse_attr_config() {
+        use perf_event_open_sys::bindings::PERF_SAMPLE_BRANCH_HW_INDEX;
+
+        use crate::config::PERF_SAMPLE_BRANCH_COUNTERS;
+
+        let sample_type = SampleFlags::IP | SampleFlags::BRANCH_STACK | SampleFlags::REGS_USER;
+        let read_format = ReadFormat::GROUP | ReadFormat::ID;
+        let branch_sample_type = PERF_SAMPLE_BRANCH_HW_INDEX as u64 | PERF_SAMPLE_BRANCH_COUNTERS;
+
+        let fields: [u64; 12] = [
+            0x68,               // type, size (VER4)
+            0,                  // config
+            0,                  // sample_period
+            sample_type.bits(), // sample_type
+            read_format.bits(), // read_format
+            1 << 18,            // flags (sample_id_all)
+            0,                  // wakeup_events, bp_type
+            0,                  // config1
+            0,                  // config2
+            branch_sample_type, // branch_sample_type
+            0b1010,             // sample_regs_user
+            0,                  // sample_stack_user, clockid
+        ];
+        let mut data: Vec<u8> = fields.iter().flat_map(|v| v.to_be_bytes()).collect();
+        data.extend_from_slice(&0b1100u64.to_be_bytes()); // sample_regs_intr
+
+        let mut parser = Parser::new(&data[..], ParseConfig::<Big>::default());
+        let config = parser.parse_attr_config().unwrap();
+
+        assert_eq!(config.sample_type(), sample_type);
+        assert_eq!(config.read_format(), read_format);
+        assert!(config.sample_id_all());
+        assert!(config.branch_hw_index());
+        assert!(config.branch_counters());
+        assert_eq!(config.regs_user(), 0b1010);
+        assert_eq!(config.regs_intr(), 0b1100);
+        assert!(parser.is_at_end().unwrap());
+    }
+
+    #[test]
+    fn visit_record_with() {
+        let data = crate::doctest::MMAP.repeat(2);
+        let mut parser = Parser::new(&data[..], ParseConfig::<Little>::default());
+
+        let pid = parser
+            .visit_record_with(|record| match record {
+                Record::Mmap(mmap) => Some(mmap.pid),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(pid, Some(0x14C16));
+
+        let record = parser.visit_record_with(Record::into_owned).unwrap();
+        assert!(matches!(record, Record::Mmap(_)));
+        assert!(parser.visit_record_with(|_| ()).is_err());
+    }
+
+    #[test]
+    fn parse_record_and_metadata() {
+        let mut parser = Parser::new(crate::doctest::MMAP, ParseConfig::<Little>::default());
+        let (record, metadata) = parser.parse_record_and_metadata().unwrap();
+
+        assert!(matches!(record, Record::Mmap(_)));
+        assert_eq!(metadata.ty(), bindings::PERF_RECORD_MMAP);
+    }
+
+    #[test]
+    fn parse_record_borrowed() {
+        let mut parser = Parser::new(crate::doctest::MMAP, ParseConfig::<Native>::default());
+        let record = parser.parse_record_borrowed().unwrap();
+        assert!(matches!(record, Record::Mmap(_)));
+
+        let data = std::io::BufReader::new(crate::doctest::MMAP);
+        let mut parser = Parser::new(data, ParseConfig::<Native>::default());
+        let error = parser.parse_record_borrowed().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::WouldAllocate);
+
+        // An empty SAMPLE record in the opposite endianness to the host.
+        let (endian, data) = if cfg!(target_endian = "little") {
+            (Dynamic::Big, [0, 0, 0, 9, 0, 0, 0, 8])
+        } else {
+            (Dynamic::Little, [9, 0, 0, 0, 0, 0, 8, 0])
+        };
+        let config = ParseConfig::<Native>::default();
+        let mut parser = Parser::new_dynamic(&data[..], config, endian);
+        let error = parser.parse_record_borrowed().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::WouldAllocate);
+        assert!(parser.is_at_end().unwrap());
+    }
+
+    #[test]
+    fn read_header() {
+        let mut parser = Parser::new(crate::doctest::MMAP, ParseConfig::<Little>::default());
+        let (ty, misc, size) = parser.read_header().unwrap();
+
+        assert_eq!(ty, RecordType::MMAP);
+        assert_eq!(misc, MiscFlags::empty());
+        assert_eq!(size as usize, crate::doctest::MMAP.len());
+    }
+
+    #[test]
+    fn parse_generic_array() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x01, 0x00, 0x02, 0x00, 0x03, 0x00,
+            0x04, 0x00, 0x00, 0x00,
+        ];
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        let values: [u16; 3] = parser.parse().unwrap();
+        assert_eq!(values, [1, 2, 3]);
+
+        let error = parser.parse::<[u32; 2]>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Eof);
+    }
+
+    #[test]
+    fn parse_byte_array() {
+        let data: &[u8] = &[1, 2, 3, 4, 5, 6, 7];
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        assert_eq!(parser.parse::<[u8; 4]>().unwrap(), [1, 2, 3, 4]);
+        assert_eq!(
+            parser.parse::<[u8; 4]>().unwrap_err().kind(),
+            ErrorKind::Eof
+        );
+
+        // The array straddles chunks of the underlying buffer.
+        let buf = ReadParseBuf::with_capacity(3, data);
+        let mut parser = Parser::new(buf, ParseConfig::<Little>::default());
+        assert_eq!(parser.parse::<[u8; 2]>().unwrap(), [1, 2]);
+        assert_eq!(parser.parse::<[u8; 5]>().unwrap(), [3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn parse_repeated_with() {
+        let data: &[u8] = &[1, 2, 3, 4, 5, 6];
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        let values = parser
+            .parse_repeated_with(3, |p| Ok(p.parse_u8()? + p.parse_u8()?))
+            .unwrap();
+
+        assert_eq!(values, [3, 7, 11]);
+    }
+
+    #[test]
+    fn parse_flagged() {
+        let data: &[u8] = &[1, 0, 0, 0, 2, 0, 0, 0];
+        let config = ParseConfig::<Little>::default().with_sample_type(SampleFlags::CPU);
+        let mut parser = Parser::new(data, config);
+
+        assert_eq!(parser.parse_flagged::<u32>(SampleFlags::TID).unwrap(), None);
+        assert_eq!(
+            parser.parse_flagged::<u32>(SampleFlags::CPU).unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            parser
+                .parse_flagged_with(SampleFlags::CPU | SampleFlags::TID, |p| p.parse_u32())
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            parser
+                .parse_flagged_with(SampleFlags::CPU, |p| p.parse_u32())
+                .unwrap(),
+            Some(2)
+        );
+    }
 }
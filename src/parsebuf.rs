@@ -1,7 +1,9 @@
 use std::borrow::Cow;
+use std::fmt;
 use std::io::{BufRead, BufReader, Read};
 use std::ops::Deref;
 
+use crate::error::ErrorKind;
 use crate::parse::{ParseError, ParseResult, Parser};
 
 used_in_docs!(Parser);
@@ -106,6 +108,39 @@ pub unsafe trait ParseBuf<'p> {
     fn remaining_hint(&self) -> Option<usize> {
         None
     }
+
+    /// Create an adapter which will read at most `limit` bytes from this
+    /// buffer.
+    ///
+    /// This is the equivalent of [`Read::take`]. Taking `&mut buf` allows
+    /// parsing a sub-section of a larger buffer and then continuing on with
+    /// the rest of it afterwards. See [`Take`] for more details.
+    fn limit(self, limit: u64) -> Take<Self>
+    where
+        Self: Sized,
+    {
+        Take::new(self, limit)
+    }
+}
+
+unsafe impl<'p, B> ParseBuf<'p> for &mut B
+where
+    B: ParseBuf<'p> + ?Sized,
+{
+    #[inline]
+    fn chunk(&mut self) -> ParseResult<ParseBufChunk<'_, 'p>> {
+        (**self).chunk()
+    }
+
+    #[inline]
+    fn advance(&mut self, count: usize) {
+        (**self).advance(count)
+    }
+
+    #[inline]
+    fn remaining_hint(&self) -> Option<usize> {
+        (**self).remaining_hint()
+    }
 }
 
 unsafe impl<'p> ParseBuf<'p> for &'p [u8] {
@@ -131,6 +166,12 @@ unsafe impl<'p> ParseBuf<'p> for &'p [u8] {
 
 // This impl would work for any type that implements BufRead. Unfortunately,
 // that conflicts with the implementation of ParseBuf for &[u8]
+//
+// The chunks returned here are always Temporary, even if a whole record fits
+// within the current buffer. The contents of the buffer are overwritten by the
+// next call to fill_buf so there is no way to hand out data borrowed from it
+// that outlives the reader. Parsing from a BufReader therefore always copies
+// the record data. See benches/bufreader.rs for a measurement of the overhead.
 unsafe impl<'p, R> ParseBuf<'p> for BufReader<R>
 where
     R: Read,
@@ -152,6 +193,258 @@ where
     }
 }
 
+/// A [`ParseBuf`] that owns its data.
+///
+/// Since the data is owned by the buffer, nothing can be borrowed from it for
+/// longer than the buffer itself lives. All chunks are therefore returned as
+/// [`ParseBufChunk::Temporary`] and any parsed records will contain owned
+/// data. In exchange, a [`Parser`] over an `OwnedBuf` can produce records with
+/// a `'static` lifetime without needing to keep the source data around.
+#[derive(Clone, Debug, Default)]
+pub struct OwnedBuf {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl OwnedBuf {
+    /// Create a new `OwnedBuf` that will parse from the start of `data`.
+    pub fn new(data: Vec<u8>) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    /// The offset of the current position from the start of the data.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Consume this buffer, returning the underlying data.
+    ///
+    /// This returns all the data, including that which has already been
+    /// parsed.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+impl From<Vec<u8>> for OwnedBuf {
+    fn from(data: Vec<u8>) -> Self {
+        Self::new(data)
+    }
+}
+
+unsafe impl ParseBuf<'static> for OwnedBuf {
+    #[inline]
+    fn chunk(&mut self) -> ParseResult<ParseBufChunk<'_, 'static>> {
+        match &self.data[self.pos..] {
+            [] => Err(ParseError::eof()),
+            chunk => Ok(ParseBufChunk::Temporary(chunk)),
+        }
+    }
+
+    #[inline]
+    fn advance(&mut self, count: usize) {
+        assert!(
+            count <= self.data.len() - self.pos,
+            "attempted to advance past the end of the buffer"
+        );
+
+        self.pos += count;
+    }
+
+    #[inline]
+    fn remaining_hint(&self) -> Option<usize> {
+        Some(self.data.len() - self.pos)
+    }
+}
+
+/// A [`ParseBuf`] that reads directly from an [`io::Read`](Read) instance.
+///
+/// This is similar to using a [`BufReader`] except that the default buffer is
+/// large enough to hold the largest possible record (64KiB). The buffer size
+/// can be tuned with [`with_capacity`](ReadParseBuf::with_capacity).
+///
+/// Like with [`BufReader`], all chunks are returned as
+/// [`ParseBufChunk::Temporary`] so any parsed records will contain owned
+/// data.
+pub struct ReadParseBuf<R> {
+    reader: R,
+    buf: Box<[u8]>,
+    start: usize,
+    end: usize,
+    pos: u64,
+}
+
+impl<R: Read> ReadParseBuf<R> {
+    /// The default buffer size. This is the maximum size of a single record.
+    pub const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+    /// Create a new `ReadParseBuf` with the default buffer size.
+    pub fn new(reader: R) -> Self {
+        Self::with_capacity(Self::DEFAULT_CAPACITY, reader)
+    }
+
+    /// Create a new `ReadParseBuf` which reads up to `capacity` bytes at a
+    /// time.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is 0.
+    pub fn with_capacity(capacity: usize, reader: R) -> Self {
+        assert!(capacity > 0, "ReadParseBuf capacity must be non-zero");
+
+        Self {
+            reader,
+            buf: vec![0; capacity].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            pos: 0,
+        }
+    }
+
+    /// The number of bytes that have been consumed from the reader so far.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Get a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consume this `ReadParseBuf`, returning the underlying reader.
+    ///
+    /// Any data that has been read into the buffer but not yet consumed will
+    /// be lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for ReadParseBuf<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadParseBuf")
+            .field("reader", &self.reader)
+            .field(
+                "buffer",
+                &format_args!("{}/{}", self.end - self.start, self.buf.len()),
+            )
+            .field("position", &self.pos)
+            .finish()
+    }
+}
+
+unsafe impl<'p, R: Read> ParseBuf<'p> for ReadParseBuf<R> {
+    fn chunk(&mut self) -> ParseResult<ParseBufChunk<'_, 'p>> {
+        if self.start == self.end {
+            let len = loop {
+                match self.reader.read(&mut self.buf) {
+                    Ok(len) => break len,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            };
+
+            if len == 0 {
+                return Err(ParseError::eof());
+            }
+
+            self.start = 0;
+            self.end = len;
+        }
+
+        Ok(ParseBufChunk::Temporary(&self.buf[self.start..self.end]))
+    }
+
+    fn advance(&mut self, count: usize) {
+        assert!(
+            count <= self.end - self.start,
+            "attempted to advance past the end of the buffer"
+        );
+
+        self.start += count;
+        self.pos += count as u64;
+    }
+}
+
+/// A [`ParseBuf`] adapter that stops after a fixed number of bytes.
+///
+/// This is the [`ParseBuf`] equivalent of [`io::Take`](std::io::Take). It is
+/// useful when the data to be parsed is followed by other, unrelated data that
+/// must not be parsed. For example, the data section of a `perf.data` file is
+/// described by a [`FileSection`] and is directly followed by the feature
+/// sections. Bounding the parser to the size of the data section ensures that
+/// it reports the end of the data instead of parsing the feature sections as
+/// if they were records.
+///
+/// Once the limit has been reached [`chunk`](ParseBuf::chunk) returns an
+/// [`Eof`](ErrorKind::Eof) error, even if the underlying buffer has more data.
+/// [`remaining_hint`](ParseBuf::remaining_hint) is the smaller of the limit
+/// and the hint of the underlying buffer, or `None` if the underlying buffer
+/// does not provide one.
+///
+/// A `Take` is usually created through [`ParseBuf::limit`].
+///
+/// [`FileSection`]: crate::FileSection
+#[derive(Clone, Debug)]
+pub struct Take<B> {
+    buf: B,
+    limit: u64,
+}
+
+impl<B> Take<B> {
+    /// Create a new `Take` that will read at most `limit` bytes from `buf`.
+    pub fn new(buf: B, limit: u64) -> Self {
+        Self { buf, limit }
+    }
+
+    /// The number of bytes that can still be read before the limit is
+    /// reached.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Get a reference to the underlying buffer.
+    pub fn get_ref(&self) -> &B {
+        &self.buf
+    }
+
+    /// Consume this `Take`, returning the underlying buffer.
+    ///
+    /// The underlying buffer will be positioned just after the last byte that
+    /// was consumed through this `Take`.
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+unsafe impl<'p, B: ParseBuf<'p>> ParseBuf<'p> for Take<B> {
+    fn chunk(&mut self) -> ParseResult<ParseBufChunk<'_, 'p>> {
+        if self.limit == 0 {
+            return Err(ParseError::eof());
+        }
+
+        let mut chunk = self.buf.chunk()?;
+        chunk.truncate(usize::try_from(self.limit).unwrap_or(usize::MAX));
+        Ok(chunk)
+    }
+
+    fn advance(&mut self, count: usize) {
+        assert!(
+            count as u64 <= self.limit,
+            "attempted to advance past the end of the buffer"
+        );
+
+        self.limit -= count as u64;
+        self.buf.advance(count);
+    }
+
+    fn remaining_hint(&self) -> Option<usize> {
+        // If the underlying buffer can't tell us its length then we can't give
+        // an accurate hint either, since it may end before the limit.
+        let remaining = self.buf.remaining_hint()?;
+        Some(remaining.min(usize::try_from(self.limit).unwrap_or(usize::MAX)))
+    }
+}
+
 pub(crate) struct ParseBufCursor<'p> {
     chunks: Vec<Cow<'p, [u8]>>,
     offset: usize,
@@ -265,6 +558,10 @@ impl<B> TrackingParseBuf<B> {
     pub fn offset(&self) -> usize {
         self.offset
     }
+
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
 }
 
 impl<'p> TrackingParseBuf<ParseBufCursor<'p>> {
@@ -278,7 +575,11 @@ where
     B: ParseBuf<'p>,
 {
     fn chunk(&mut self) -> ParseResult<ParseBufChunk<'_, 'p>> {
-        self.buf.chunk()
+        let offset = self.offset;
+        self.buf.chunk().map_err(|e| match e.kind() {
+            ErrorKind::External | ErrorKind::Eof => e.with_offset(offset as u64),
+            _ => e,
+        })
     }
 
     fn advance(&mut self, count: usize) {
@@ -330,4 +631,151 @@ mod tests {
         let mut buf = ChunkBuf(vec![b"", b"01234"]);
         let _cursor = ParseBufCursor::new(&mut buf, 4);
     }
+
+    #[test]
+    fn owned_buf_parses_static_record() {
+        use crate::endian::Little;
+        use crate::parse::ParseConfig;
+        use crate::Record;
+
+        let buf = OwnedBuf::new(crate::doctest::MMAP.to_vec());
+        let mut parser = Parser::new(buf, ParseConfig::<Little>::default());
+        let record: Record<'static> = parser.parse().unwrap();
+
+        match record {
+            Record::Mmap(mmap) => assert_eq!(&*mmap.filename, b"//anon"),
+            _ => panic!("expected a MMAP record"),
+        }
+
+        let buf = parser.into_inner();
+        assert_eq!(buf.position(), crate::doctest::MMAP.len());
+        assert!(buf.clone().chunk().is_err());
+    }
+
+    #[test]
+    fn read_buf_small_capacity() {
+        use crate::endian::Little;
+        use crate::parse::ParseConfig;
+        use crate::Record;
+
+        let data = crate::doctest::MMAP.repeat(2);
+        let buf = ReadParseBuf::with_capacity(7, &data[..]);
+        let mut parser = Parser::new(buf, ParseConfig::<Little>::default());
+
+        for _ in 0..2 {
+            match parser.parse::<Record>().unwrap() {
+                Record::Mmap(mmap) => assert_eq!(&*mmap.filename, b"//anon"),
+                record => panic!("expected a MMAP record, got {record:?}"),
+            }
+        }
+
+        let mut buf = parser.into_inner();
+        assert_eq!(buf.position(), data.len() as u64);
+        assert!(buf.chunk().is_err());
+    }
+
+    #[test]
+    fn take_stops_at_limit() {
+        use crate::endian::Little;
+        use crate::parse::ParseConfig;
+        use crate::Record;
+
+        // The bytes following the first record would parse as a second,
+        // perfectly valid, MMAP record if the buffer wasn't bounded.
+        let data = crate::doctest::MMAP.repeat(2);
+        let len = crate::doctest::MMAP.len();
+
+        let parser = Parser::new(
+            Take::new(&data[..], len as u64),
+            ParseConfig::<Little>::default(),
+        );
+        let records = parser.parse_all().unwrap();
+        assert_eq!(records.len(), 1);
+        assert!(matches!(records[0], Record::Mmap(_)));
+
+        let buf = ReadParseBuf::with_capacity(7, &data[..]);
+        let mut parser = Parser::new(Take::new(buf, len as u64), ParseConfig::<Little>::default());
+        parser.parse::<Record>().unwrap();
+        assert_eq!(parser.parse::<Record>().unwrap_err().kind(), ErrorKind::Eof);
+
+        let buf = parser.into_inner();
+        assert_eq!(buf.limit(), 0);
+        assert_eq!(buf.into_inner().position(), len as u64);
+
+        // A limit that ends partway through a record is an error.
+        let parser = Parser::new(Take::new(&data[..], 20), ParseConfig::<Little>::default());
+        assert_eq!(parser.parse_all().unwrap_err().kind(), ErrorKind::Eof);
+    }
+
+    #[test]
+    fn take_sub_section() {
+        use crate::endian::Little;
+        use crate::parse::ParseConfig;
+        use crate::Record;
+
+        let data = crate::doctest::MMAP.repeat(3);
+        let len = crate::doctest::MMAP.len();
+        let mut buf = &data[..];
+
+        let take = (&mut buf).limit(2 * len as u64);
+        assert_eq!(take.remaining_hint(), Some(2 * len));
+
+        let mut parser = Parser::new(take, ParseConfig::<Little>::default());
+        parser.parse::<Record>().unwrap();
+        parser.parse::<Record>().unwrap();
+        assert_eq!(parser.parse::<Record>().unwrap_err().kind(), ErrorKind::Eof);
+        drop(parser);
+
+        // The underlying buffer picks up right after the sub-section.
+        assert_eq!(buf.len(), len);
+        assert_eq!(buf.limit(100).remaining_hint(), Some(len));
+    }
+
+    #[test]
+    fn io_error_offset() {
+        use std::io;
+
+        use crate::endian::Little;
+        use crate::parse::ParseConfig;
+        use crate::Record;
+
+        struct FailingReader<'a>(&'a [u8]);
+
+        impl Read for FailingReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if self.0.is_empty() {
+                    return Err(io::Error::other("device error"));
+                }
+
+                self.0.read(buf)
+            }
+        }
+
+        let data = crate::doctest::MMAP;
+        let buf = ReadParseBuf::with_capacity(16, FailingReader(data));
+        let mut parser = Parser::new(buf, ParseConfig::<Little>::default());
+
+        parser.parse::<Record>().unwrap();
+        let error = parser.parse::<Record>().unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::External);
+        assert_eq!(error.offset(), Some(data.len() as u64));
+        assert!(error.to_string().ends_with("device error (at byte 48)"));
+    }
+
+    #[test]
+    fn eof_offset() {
+        use crate::endian::Little;
+        use crate::parse::ParseConfig;
+
+        let data: &[u8] = &[0; 12];
+        let buf = ReadParseBuf::with_capacity(16, data);
+        let mut parser = Parser::new(buf, ParseConfig::<Little>::default());
+
+        parser.parse_u64().unwrap();
+        let error = parser.parse_u64().unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::Eof);
+        assert_eq!(error.offset(), Some(12));
+    }
 }
@@ -0,0 +1,338 @@
+use std::borrow::Cow;
+use std::mem;
+
+use perf_event_open_sys::bindings::perf_event_header;
+
+use crate::multi_section::{check_magic, MAGIC, PIPE_HEADER_LEN};
+use crate::parse::RecordStitcher;
+use crate::prelude::*;
+use crate::records::USER_TYPE_START;
+use crate::{AuxTrace, HeaderAttr, Record};
+
+/// A streaming parser for the output of `perf record -o -`.
+///
+/// When writing to a pipe, `perf record` emits a 16-byte pipe header followed
+/// by a stream of records. Unlike the records read straight from a ring
+/// buffer, this stream contains a number of records that are synthesized by
+/// the `perf` tool itself. `PipeReader` takes care of these as they go past:
+/// - The pipe header at the start of the stream is skipped, if present.
+/// - A [`HeaderAttr`] record replaces the config used for the records that
+///   follow it. The [`HeaderAttr`] record itself is still yielded.
+/// - The payload following an [`AuxTrace`] record is skipped over.
+/// - With the `zstd` feature enabled, [`Compressed`] records are decompressed
+///   and the records within them are yielded in their place. Without it, they
+///   are yielded like any other record and decompressing them is left to the
+///   caller.
+///
+/// Records of types used only by the `perf` tool, such as [`FinishedRound`],
+/// are yielded as [`Record::Unknown`] with their whole body.
+///
+/// Like [`RecordStream`], the data may be [`feed`](Self::feed)ed in chunks of
+/// any size. [`next_record`](Self::next_record) returns `Ok(None)` until
+/// enough data has been fed in to complete the next record.
+///
+/// # Example
+/// ```ignore
+/// let mut reader = PipeReader::new(ParseConfig::<Little>::default());
+/// let mut buf = [0u8; 4096];
+///
+/// loop {
+///     let len = stdin.read(&mut buf)?;
+///     if len == 0 {
+///         break;
+///     }
+///
+///     reader.feed(&buf[..len]);
+///     while let Some(record) = reader.next_record()? {
+///         // ...
+///     }
+/// }
+/// ```
+///
+/// [`Compressed`]: crate::Compressed
+/// [`FinishedRound`]: crate::FinishedRound
+/// [`RecordStream`]: crate::parse::RecordStream
+#[derive(Debug)]
+pub struct PipeReader<E> {
+    config: ParseConfig<E>,
+    stitcher: RecordStitcher<E>,
+    #[cfg(feature = "zstd")]
+    decoder: Option<crate::parse::CompressedDecoder<E>>,
+    started: bool,
+    skip: u64,
+}
+
+impl<E: Endian> PipeReader<E> {
+    /// Create a new, empty, `PipeReader`.
+    ///
+    /// `config` is used until the first [`HeaderAttr`] record is seen.
+    /// Whether [size checks](ParseConfig::with_size_checks) and
+    /// [mmap sample ids](ParseConfig::with_mmap_sample_id) are enabled is kept
+    /// when switching to the config of a [`HeaderAttr`] record.
+    pub fn new(config: ParseConfig<E>) -> Self {
+        Self {
+            stitcher: RecordStitcher::new(config.endian().clone()),
+            config,
+            #[cfg(feature = "zstd")]
+            decoder: None,
+            started: false,
+            skip: 0,
+        }
+    }
+
+    /// The config that will be used to parse the next record.
+    pub fn config(&self) -> &ParseConfig<E> {
+        &self.config
+    }
+
+    /// Add more data to the end of the stream.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.stitcher.push(data);
+    }
+
+    /// Parse the next record in the stream.
+    ///
+    /// Returns `Ok(None)` if the next record has not been fully fed in yet.
+    pub fn next_record(&mut self) -> ParseResult<Option<Record<'_>>> {
+        loop {
+            // Records decompressed out of an earlier COMPRESSED record come
+            // before anything that follows it in the outer stream.
+            #[cfg(feature = "zstd")]
+            {
+                let decoded = match &self.decoder {
+                    Some(decoder) => decoder.peek_header()?,
+                    None => None,
+                };
+
+                if decoded.is_some() {
+                    let bytes = self
+                        .decoder
+                        .as_mut()
+                        .and_then(|decoder| decoder.next_record().transpose())
+                        .expect("peeked record should be complete")?;
+                    return Self::parse_record(&mut self.config, bytes).map(Some);
+                }
+            }
+
+            if !self.skip_pipe_header()? {
+                return Ok(None);
+            }
+
+            if self.skip != 0 {
+                let len = usize::try_from(self.skip).unwrap_or(usize::MAX);
+                self.skip -= self.stitcher.consume(len) as u64;
+
+                if self.skip != 0 {
+                    return Ok(None);
+                }
+            }
+
+            let ty = match self.stitcher.peek_header()? {
+                Some((ty, _)) => ty,
+                None => return Ok(None),
+            };
+
+            #[cfg(feature = "zstd")]
+            if ty == crate::Compressed::RECORD_TYPE {
+                if self.decoder.is_none() {
+                    let endian = self.config.endian().clone();
+                    self.decoder = Some(crate::parse::CompressedDecoder::new(endian)?);
+                }
+
+                let bytes = self
+                    .stitcher
+                    .next_record()?
+                    .expect("peeked record should be complete");
+                let body = &bytes[mem::size_of::<perf_event_header>()..];
+                let record: crate::Compressed = Parser::new(body, self.config.clone()).parse()?;
+
+                if let Some(decoder) = &mut self.decoder {
+                    decoder.push(&record)?;
+                }
+                continue;
+            }
+
+            let bytes = self
+                .stitcher
+                .next_record()?
+                .expect("peeked record should be complete");
+
+            if ty == AuxTrace::RECORD_TYPE {
+                let body = &bytes[mem::size_of::<perf_event_header>()..];
+                let record: AuxTrace = Parser::new(body, self.config.clone()).parse()?;
+                self.skip = record.size;
+            }
+
+            return Self::parse_record(&mut self.config, bytes).map(Some);
+        }
+    }
+
+    /// The number of bytes that have been fed in but not yet parsed.
+    ///
+    /// This does not include decompressed data.
+    pub fn pending(&self) -> usize {
+        self.stitcher.pending()
+    }
+
+    /// Skip over the pipe header at the start of the stream, if there is one.
+    ///
+    /// Returns `false` if more data is needed to tell. Returns an error if the
+    /// endianness of the pipe header does not match the config.
+    fn skip_pipe_header(&mut self) -> ParseResult<bool> {
+        if self.started {
+            return Ok(true);
+        }
+
+        let rest = self.stitcher.peek();
+        let magic: [u8; 8] = match rest.get(..8) {
+            Some(magic) => magic.try_into().expect("slice should have a length of 8"),
+            None => return Ok(false),
+        };
+
+        if MAGIC.iter().any(|m| **m == magic) {
+            if rest.len() < PIPE_HEADER_LEN as usize {
+                return Ok(false);
+            }
+
+            check_magic(self.config.endian(), magic)?;
+            self.stitcher.consume(PIPE_HEADER_LEN as usize);
+        }
+
+        self.started = true;
+        Ok(true)
+    }
+
+    fn parse_record<'a>(config: &mut ParseConfig<E>, bytes: &'a [u8]) -> ParseResult<Record<'a>> {
+        let header_len = mem::size_of::<perf_event_header>();
+        let ty = config
+            .endian()
+            .convert_u32([bytes[0], bytes[1], bytes[2], bytes[3]]);
+
+        if ty < USER_TYPE_START {
+            return Parser::new(bytes, config.clone()).parse();
+        }
+
+        let body = &bytes[header_len..];
+        if ty == HeaderAttr::RECORD_TYPE {
+            let record: HeaderAttr = Parser::new(body, config.clone()).parse()?;
+            *config = config.for_attr(&record.attr);
+        }
+
+        Ok(Record::Unknown {
+            ty,
+            data: Cow::Borrowed(body),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+    use crate::util::testdata::{record, u64s, ATTR, SAMPLE};
+    use crate::FinishedRound;
+
+    /// A zstd frame containing three copies of [`SAMPLE`] each followed by a
+    /// FINISHED_ROUND record.
+    #[cfg(feature = "zstd")]
+    #[rustfmt::skip]
+    const SAMPLES_ZSTD: &[u8] = &[
+        0x28, 0xb5, 0x2f, 0xfd, 0x20, 0x48, 0xc5, 0x00, 0x00, 0x92, 0xc1, 0x03,
+        0x07, 0xf0, 0x39, 0x40, 0xb3, 0xf3, 0xfe, 0xa6, 0x0b, 0x55, 0xa7, 0xaa,
+        0xa2, 0x51, 0xf5, 0x01, 0x00, 0xb1, 0xd1, 0x1c, 0x03,
+    ];
+
+    /// The pipe header followed by a HEADER_ATTR record for [`ATTR`].
+    fn pipe_header() -> Vec<u8> {
+        let mut data = b"PERFILE2".to_vec();
+        data.extend_from_slice(&PIPE_HEADER_LEN.to_le_bytes());
+        data.extend(record(
+            HeaderAttr::RECORD_TYPE,
+            &[ATTR, &u64s(&[1])].concat(),
+        ));
+        data
+    }
+
+    fn read_all(data: &[u8], chunk: usize) -> (Vec<Record<'static>>, PipeReader<Little>) {
+        let mut reader = PipeReader::new(ParseConfig::<Little>::default());
+        let mut records = Vec::new();
+
+        for chunk in data.chunks(chunk) {
+            reader.feed(chunk);
+
+            while let Some(record) = reader.next_record().unwrap() {
+                records.push(record.into_owned());
+            }
+        }
+
+        (records, reader)
+    }
+
+    fn is_unknown(record: &Record, expected: u32) -> bool {
+        matches!(record, Record::Unknown { ty, .. } if *ty == expected)
+    }
+
+    #[test]
+    fn pipe_stream_with_aux_trace() {
+        let mut aux_trace = Vec::new();
+        for value in [4u64, 0, 0] {
+            aux_trace.extend_from_slice(&value.to_le_bytes());
+        }
+        aux_trace.extend_from_slice(&[0; 16]);
+
+        let mut data = pipe_header();
+        data.extend_from_slice(SAMPLE);
+        data.extend(record(AuxTrace::RECORD_TYPE, &aux_trace));
+        data.extend_from_slice(&[0xFF; 4]);
+        data.extend(record(FinishedRound::RECORD_TYPE, &[]));
+        data.extend_from_slice(SAMPLE);
+
+        let (records, reader) = read_all(&data, 1);
+
+        assert_eq!(records.len(), 5);
+        assert!(is_unknown(&records[0], HeaderAttr::RECORD_TYPE));
+        assert!(matches!(&records[1], Record::Sample(s) if s.ip() == Some(0x401000)));
+        assert!(is_unknown(&records[2], AuxTrace::RECORD_TYPE));
+        assert!(is_unknown(&records[3], FinishedRound::RECORD_TYPE));
+        assert!(matches!(&records[4], Record::Sample(s) if s.ip() == Some(0x401000)));
+        assert_eq!(reader.config().sample_type(), SampleFlags::IP);
+        assert_eq!(reader.pending(), 0);
+    }
+
+    #[test]
+    fn mismatched_endian_pipe_header() {
+        let mut reader = PipeReader::new(ParseConfig::<Little>::default());
+        reader.feed(b"2ELIFREP");
+        reader.feed(&PIPE_HEADER_LEN.to_be_bytes());
+
+        let error = reader.next_record().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn pipe_stream_with_compressed_records() {
+        use crate::Compressed;
+
+        // Split the frame across two COMPRESSED records so that the second
+        // sample straddles the boundary.
+        let (first, second) = SAMPLES_ZSTD.split_at(17);
+
+        let mut data = pipe_header();
+        data.extend(record(Compressed::RECORD_TYPE, first));
+        data.extend(record(Compressed::RECORD_TYPE, second));
+        data.extend(record(FinishedRound::RECORD_TYPE, &[]));
+
+        let (records, reader) = read_all(&data, 7);
+
+        assert_eq!(records.len(), 8);
+        assert!(is_unknown(&records[0], HeaderAttr::RECORD_TYPE));
+        for pair in records[1..7].chunks(2) {
+            assert!(matches!(&pair[0], Record::Sample(s) if s.ip() == Some(0x401000)));
+            assert!(is_unknown(&pair[1], FinishedRound::RECORD_TYPE));
+        }
+        assert!(is_unknown(&records[7], FinishedRound::RECORD_TYPE));
+        assert_eq!(reader.config().sample_type(), SampleFlags::IP);
+        assert_eq!(reader.pending(), 0);
+    }
+}
@@ -0,0 +1,272 @@
+use std::fmt;
+
+use crate::endian::convert_uint;
+use crate::parse::ParseError;
+use crate::prelude::*;
+
+/// Typed access to the fields within the raw data of a tracepoint sample.
+///
+/// When a sample is taken for a tracepoint event, the [`raw`] field of the
+/// [`Sample`] contains the tracepoint's data laid out as described by the
+/// `format` file for that tracepoint in tracefs. Each field in the format
+/// description has an offset and a size. `RawData` takes care of reading the
+/// field at those offsets and converting it from the source endianness.
+///
+/// Fields declared as `__data_loc` or `__rel_loc` do not store their data
+/// inline. Use [`data_loc`](Self::data_loc) and [`rel_loc`](Self::rel_loc) to
+/// follow them to the dynamic data stored later within the buffer.
+///
+/// All methods return an error with kind [`ErrorKind::Eof`] if the requested
+/// field extends past the end of the raw data.
+///
+/// [`raw`]: crate::Sample::raw
+/// [`Sample`]: crate::Sample
+#[derive(Clone)]
+pub struct RawData<'a, E> {
+    data: &'a [u8],
+    endian: E,
+}
+
+impl<'a, E: Endian> RawData<'a, E> {
+    /// Create a new `RawData` for the raw bytes of a sample.
+    pub fn new(data: &'a [u8], endian: E) -> Self {
+        Self { data, endian }
+    }
+
+    /// The raw bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Get the `size` bytes of the field at `offset`.
+    pub fn bytes(&self, offset: usize, size: usize) -> ParseResult<&'a [u8]> {
+        offset
+            .checked_add(size)
+            .and_then(|end| self.data.get(offset..end))
+            .ok_or_else(|| {
+                ParseError::custom(
+                    ErrorKind::Eof,
+                    format_args!(
+                        "field at offset {offset} with size {size} extends past the end of \
+                         the raw data (len {})",
+                        self.data.len()
+                    ),
+                )
+            })
+    }
+
+    fn array<const N: usize>(&self, offset: usize) -> ParseResult<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.bytes(offset, N)?);
+        Ok(array)
+    }
+
+    /// Read a `u8` field.
+    pub fn u8(&self, offset: usize) -> ParseResult<u8> {
+        let [byte] = self.array(offset)?;
+        Ok(byte)
+    }
+
+    /// Read a `u16` field.
+    pub fn u16(&self, offset: usize) -> ParseResult<u16> {
+        Ok(self.endian.convert_u16(self.array(offset)?))
+    }
+
+    /// Read a `u32` field.
+    pub fn u32(&self, offset: usize) -> ParseResult<u32> {
+        Ok(self.endian.convert_u32(self.array(offset)?))
+    }
+
+    /// Read a `u64` field.
+    pub fn u64(&self, offset: usize) -> ParseResult<u64> {
+        Ok(self.endian.convert_u64(self.array(offset)?))
+    }
+
+    /// Read an `i8` field.
+    pub fn i8(&self, offset: usize) -> ParseResult<i8> {
+        Ok(self.u8(offset)? as i8)
+    }
+
+    /// Read an `i16` field.
+    pub fn i16(&self, offset: usize) -> ParseResult<i16> {
+        Ok(self.u16(offset)? as i16)
+    }
+
+    /// Read an `i32` field.
+    pub fn i32(&self, offset: usize) -> ParseResult<i32> {
+        Ok(self.u32(offset)? as i32)
+    }
+
+    /// Read an `i64` field.
+    pub fn i64(&self, offset: usize) -> ParseResult<i64> {
+        Ok(self.u64(offset)? as i64)
+    }
+
+    /// Read an unsigned integer field that is `size` bytes wide.
+    ///
+    /// Returns an error with kind [`ErrorKind::UnsupportedData`] if `size` is
+    /// not within `1..=8`.
+    pub fn uint(&self, offset: usize, size: usize) -> ParseResult<u64> {
+        check_int_size(size)?;
+        Ok(convert_uint(&self.endian, self.bytes(offset, size)?))
+    }
+
+    /// Read a signed integer field that is `size` bytes wide.
+    ///
+    /// The value is sign-extended from `size` bytes to an `i64`. Returns an
+    /// error with kind [`ErrorKind::UnsupportedData`] if `size` is not within
+    /// `1..=8`.
+    pub fn int(&self, offset: usize, size: usize) -> ParseResult<i64> {
+        let value = self.uint(offset, size)?;
+        let shift = 64 - 8 * size as u32;
+        Ok(((value << shift) as i64) >> shift)
+    }
+
+    /// Read a fixed-size string field (e.g. `char comm[16]`).
+    ///
+    /// The returned bytes end just before the first NUL byte within the
+    /// field, or at the end of the field if there is none.
+    pub fn string(&self, offset: usize, size: usize) -> ParseResult<&'a [u8]> {
+        Ok(until_nul(self.bytes(offset, size)?))
+    }
+
+    /// Read a fixed-size array field of unsigned integers that are each
+    /// `elem_size` bytes wide.
+    ///
+    /// `size` is the size of the whole field, as given in the format
+    /// description. Returns an error with kind [`ErrorKind::UnsupportedData`]
+    /// if `elem_size` is not within `1..=8` or `size` is not a multiple of
+    /// `elem_size`.
+    pub fn array_of(
+        &self,
+        offset: usize,
+        size: usize,
+        elem_size: usize,
+    ) -> ParseResult<impl Iterator<Item = u64> + 'a>
+    where
+        E: 'a,
+    {
+        check_int_size(elem_size)?;
+        if size % elem_size != 0 {
+            return Err(ParseError::custom(
+                ErrorKind::UnsupportedData,
+                format_args!("array size {size} is not a multiple of its element size {elem_size}"),
+            ));
+        }
+
+        let endian = self.endian.clone();
+        Ok(self
+            .bytes(offset, size)?
+            .chunks_exact(elem_size)
+            .map(move |chunk| convert_uint(&endian, chunk)))
+    }
+
+    /// Follow a `__data_loc` field to the dynamic data it refers to.
+    ///
+    /// A `__data_loc` field is a `u32` whose low 16 bits are the offset of the
+    /// data from the start of the raw data and whose high 16 bits are its
+    /// length.
+    pub fn data_loc(&self, offset: usize) -> ParseResult<&'a [u8]> {
+        let loc = self.u32(offset)?;
+        self.bytes((loc & 0xFFFF) as usize, (loc >> 16) as usize)
+    }
+
+    /// Follow a `__rel_loc` field to the dynamic data it refers to.
+    ///
+    /// This is like [`data_loc`](Self::data_loc) except that the offset is
+    /// relative to the end of the `__rel_loc` field itself.
+    pub fn rel_loc(&self, offset: usize) -> ParseResult<&'a [u8]> {
+        let loc = self.u32(offset)?;
+        let start = offset + 4 + (loc & 0xFFFF) as usize;
+        self.bytes(start, (loc >> 16) as usize)
+    }
+
+    /// Follow a `__data_loc char[]` field to the string it refers to.
+    ///
+    /// The returned bytes do not include the trailing NUL byte.
+    pub fn data_loc_string(&self, offset: usize) -> ParseResult<&'a [u8]> {
+        Ok(until_nul(self.data_loc(offset)?))
+    }
+}
+
+impl<E> fmt::Debug for RawData<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RawData")
+            .field(&crate::util::fmt::HexStr(self.data))
+            .finish()
+    }
+}
+
+fn check_int_size(size: usize) -> ParseResult<()> {
+    if !(1..=8).contains(&size) {
+        return Err(ParseError::custom(
+            ErrorKind::UnsupportedData,
+            format_args!("cannot read a {size}-byte integer into a u64"),
+        ));
+    }
+
+    Ok(())
+}
+
+fn until_nul(bytes: &[u8]) -> &[u8] {
+    match bytes.iter().position(|&b| b == 0) {
+        Some(len) => &bytes[..len],
+        None => bytes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::{Big, Little};
+
+    #[rustfmt::skip]
+    const DATA: &[u8] = &[
+        0x2A, 0x00,                                     // common_type
+        0xFE,                                           // common_flags
+        0x00,                                           // common_preempt_count
+        0x10, 0x00, 0x00, 0x00,                         // common_pid
+        b'b', b'a', b's', b'h', 0x00, 0x00, 0x00, 0x00, // comm[8]
+        0x18, 0x00, 0x05, 0x00,                         // __data_loc char[] filename
+        0x00, 0x00, 0x05, 0x00,                         // __rel_loc char[] path
+        b'/', b't', b'm', b'p', 0x00,                   // filename data
+        0xFF, 0xFF, 0x7F,                               // 3-byte int
+    ];
+
+    #[test]
+    fn read_fields() {
+        let raw = RawData::new(DATA, Little);
+
+        assert_eq!(raw.u16(0).unwrap(), 0x2A);
+        assert_eq!(raw.u8(2).unwrap(), 0xFE);
+        assert_eq!(raw.i8(2).unwrap(), -2);
+        assert_eq!(raw.i32(4).unwrap(), 0x10);
+        assert_eq!(raw.string(8, 8).unwrap(), b"bash");
+        assert_eq!(raw.data_loc(16).unwrap(), b"/tmp\0");
+        assert_eq!(raw.data_loc_string(16).unwrap(), b"/tmp");
+        assert_eq!(raw.rel_loc(20).unwrap(), b"/tmp\0");
+        assert_eq!(raw.uint(29, 3).unwrap(), 0x7FFFFF);
+        assert_eq!(raw.int(29, 2).unwrap(), -1);
+
+        let values: Vec<_> = raw.array_of(0, 4, 2).unwrap().collect();
+        assert_eq!(values, [0x2A, 0xFE]);
+
+        assert_eq!(RawData::new(DATA, Big).u16(0).unwrap(), 0x2A00);
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        let raw = RawData::new(DATA, Little);
+
+        assert_eq!(raw.u64(28).unwrap_err().kind(), ErrorKind::Eof);
+        assert_eq!(raw.bytes(usize::MAX, 2).unwrap_err().kind(), ErrorKind::Eof);
+        assert_eq!(
+            raw.uint(0, 9).unwrap_err().kind(),
+            ErrorKind::UnsupportedData
+        );
+        assert_eq!(
+            raw.array_of(0, 3, 2).err().unwrap().kind(),
+            ErrorKind::UnsupportedData
+        );
+    }
+}
@@ -9,7 +9,7 @@ use crate::prelude::*;
 /// documentation.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 #[allow(missing_docs)]
 pub struct Aux {
     pub aux_offset: u64,
@@ -25,7 +25,7 @@ bitflags! {
     ///
     /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
     /// [source]: https://sourcegraph.com/github.com/torvalds/linux@eb7081409f94a9a8608593d0fb63a1aa3d6f95d8/-/blob/tools/include/uapi/linux/perf_event.h?L1248
-    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+    #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
     pub struct AuxFlags : u64 {
         /// The data returned was truncated to fit within the buffer size.
         const TRUNCATED = bindings::PERF_AUX_FLAG_TRUNCATED as _;
@@ -61,6 +61,11 @@ impl AuxPmuFormatType {
     pub const fn new(value: u8) -> Self {
         Self(value)
     }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        matches!(*self, Self::CORESIGHT | Self::CORESIGHT_RAW)
+    }
 }
 
 impl AuxFlags {
@@ -70,6 +75,25 @@ impl AuxFlags {
     }
 }
 
+impl Aux {
+    /// Whether the data in the aux buffer covered by this record can be
+    /// trusted.
+    ///
+    /// This returns `false` if any of the [`TRUNCATED`], [`PARTIAL`], or
+    /// [`COLLISION`] flags are set. In that case the aux data may be missing
+    /// parts or may have been overwritten and so decoding it may produce
+    /// incorrect results.
+    ///
+    /// [`TRUNCATED`]: AuxFlags::TRUNCATED
+    /// [`PARTIAL`]: AuxFlags::PARTIAL
+    /// [`COLLISION`]: AuxFlags::COLLISION
+    pub fn is_reliable(&self) -> bool {
+        !self
+            .flags
+            .intersects(AuxFlags::TRUNCATED | AuxFlags::PARTIAL | AuxFlags::COLLISION)
+    }
+}
+
 impl<'p> Parse<'p> for Aux {
     fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
     where
@@ -93,3 +117,23 @@ impl<'p> Parse<'p> for AuxFlags {
         p.parse().map(Self::from_bits_retain)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reliable_flags() {
+        let aux = |flags| Aux {
+            aux_offset: 0,
+            aux_size: 0,
+            flags,
+        };
+
+        assert!(aux(AuxFlags::empty()).is_reliable());
+        assert!(aux(AuxFlags::OVERWRITE).is_reliable());
+        assert!(!aux(AuxFlags::TRUNCATED).is_reliable());
+        assert!(!aux(AuxFlags::PARTIAL | AuxFlags::OVERWRITE).is_reliable());
+        assert!(!aux(AuxFlags::COLLISION).is_reliable());
+    }
+}
@@ -0,0 +1,286 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use crate::prelude::*;
+use crate::Aux;
+
+/// AUXTRACE records contain a chunk of data copied out of the aux buffer.
+///
+/// Unlike the other records in this crate, these are not emitted by the
+/// kernel. Instead, they are synthesized by the `perf` tool when writing a
+/// `perf.data` file. The record itself only contains the fields below. It is
+/// followed by [`size`](AuxTrace::size) bytes of payload which are _not_
+/// included in the size within the record header.
+///
+/// This struct corresponds to `PERF_RECORD_AUXTRACE`. See the [perf source]
+/// for more documentation.
+///
+/// [perf source]: https://sourcegraph.com/github.com/torvalds/linux@eb7081409f94a9a8608593d0fb63a1aa3d6f95d8/-/blob/tools/lib/perf/include/perf/event.h?L304
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AuxTrace {
+    /// The size of the payload following this record, in bytes.
+    pub size: u64,
+
+    /// The offset of the payload within the aux buffer.
+    pub offset: u64,
+
+    /// A unique reference for this chunk of the trace.
+    pub reference: u64,
+
+    /// The index of the mmapped aux buffer that the payload was read from.
+    pub idx: u32,
+
+    /// The thread ID that the trace data belongs to.
+    pub tid: u32,
+
+    /// The CPU that the trace data was recorded on.
+    pub cpu: u32,
+}
+
+impl AuxTrace {
+    /// The record type used for AUXTRACE records within `perf.data` files.
+    pub const RECORD_TYPE: u32 = 71;
+}
+
+impl<'p> Parse<'p> for AuxTrace {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        let record = Self {
+            size: p.parse()?,
+            offset: p.parse()?,
+            reference: p.parse()?,
+            idx: p.parse()?,
+            tid: p.parse()?,
+            cpu: p.parse()?,
+        };
+
+        // reserved field for alignment
+        let _ = p.parse_u32()?;
+
+        Ok(record)
+    }
+}
+
+/// Reassembles the payloads of [`AuxTrace`] records into contiguous buffers.
+///
+/// Payloads are grouped by `(cpu, tid)` and then concatenated in order of
+/// their offset within the aux buffer. Any bytes that overlap with a payload
+/// at a lower offset are dropped. Gaps between payloads are not filled in so
+/// a decoder may need to resynchronize at those points.
+#[derive(Clone, Debug, Default)]
+pub struct AuxTraceAssembler<'a> {
+    chunks: BTreeMap<(u32, u32), Vec<(u64, Cow<'a, [u8]>)>>,
+}
+
+impl<'a> AuxTraceAssembler<'a> {
+    /// Create a new, empty, `AuxTraceAssembler`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the payload for an [`AuxTrace`] record.
+    pub fn push(&mut self, record: &AuxTrace, payload: impl Into<Cow<'a, [u8]>>) {
+        self.chunks
+            .entry((record.cpu, record.tid))
+            .or_default()
+            .push((record.offset, payload.into()));
+    }
+
+    /// Whether any payloads have been added to this assembler.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Assemble the payloads into one contiguous buffer for each
+    /// `(cpu, tid)` pair.
+    pub fn finish(self) -> BTreeMap<(u32, u32), Vec<u8>> {
+        self.chunks
+            .into_iter()
+            .map(|(key, mut chunks)| {
+                chunks.sort_by_key(|(offset, _)| *offset);
+
+                let mut data = Vec::new();
+                let mut end = match chunks.first() {
+                    Some((offset, _)) => *offset,
+                    None => 0,
+                };
+
+                for (offset, chunk) in chunks {
+                    let skip = end.saturating_sub(offset).min(chunk.len() as u64) as usize;
+                    data.extend_from_slice(&chunk[skip..]);
+                    end = end.max(offset.saturating_add(chunk.len() as u64));
+                }
+
+                (key, data)
+            })
+            .collect()
+    }
+}
+
+/// Indexes the payloads of [`AuxTrace`] records so that the data referred to
+/// by an [`Aux`] record can be looked up.
+///
+/// The kernel emits an [`Aux`] record whenever new data is written to the aux
+/// buffer, while the `perf` tool stores the data itself in [`AuxTrace`]
+/// records. Both use the same offsets into the aux buffer so, once the
+/// payloads have been added here, [`window`](AuxTraceIndex::window) returns
+/// the bytes described by an [`Aux`] record.
+///
+/// Payloads are keyed by the [`idx`](AuxTrace::idx) of the mmapped aux buffer
+/// they were read from. The caller is responsible for knowing which buffer an
+/// [`Aux`] record refers to. When recording per-cpu, `perf` uses one buffer
+/// per recorded CPU so this is usually the index of the CPU that emitted the
+/// [`Aux`] record within the list of recorded CPUs.
+#[derive(Clone, Debug, Default)]
+pub struct AuxTraceIndex<'a> {
+    chunks: BTreeMap<u32, BTreeMap<u64, Cow<'a, [u8]>>>,
+}
+
+impl<'a> AuxTraceIndex<'a> {
+    /// Create a new, empty, `AuxTraceIndex`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the payload for an [`AuxTrace`] record.
+    pub fn push(&mut self, record: &AuxTrace, payload: impl Into<Cow<'a, [u8]>>) {
+        self.chunks
+            .entry(record.idx)
+            .or_default()
+            .insert(record.offset, payload.into());
+    }
+
+    /// Whether any payloads have been added to this index.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Get the bytes within aux buffer `idx` that are described by `aux`.
+    ///
+    /// The returned data is borrowed when it is contained within a single
+    /// payload and copied when it spans multiple payloads. Returns `None` if
+    /// any part of the window is not covered by the payloads added so far.
+    pub fn window(&self, idx: u32, aux: &Aux) -> Option<Cow<'_, [u8]>> {
+        let start = aux.aux_offset;
+        let end = start.checked_add(aux.aux_size)?;
+
+        if start == end {
+            return Some(Cow::Borrowed(&[]));
+        }
+
+        let chunks = self.chunks.get(&idx)?;
+        let (&first, _) = chunks
+            .range(..=start)
+            .rev()
+            .find(|(&offset, chunk)| offset.saturating_add(chunk.len() as u64) > start)?;
+
+        let mut data: Cow<'_, [u8]> = Cow::Borrowed(&[]);
+        let mut pos = start;
+
+        for (&offset, chunk) in chunks.range(first..end) {
+            if offset > pos {
+                return None;
+            }
+
+            let chunk_end = offset.saturating_add(chunk.len() as u64);
+            if chunk_end <= pos {
+                continue;
+            }
+
+            let piece = &chunk[(pos - offset) as usize..(end.min(chunk_end) - offset) as usize];
+            if data.is_empty() {
+                data = Cow::Borrowed(piece);
+            } else {
+                data.to_mut().extend_from_slice(piece);
+            }
+
+            pos = end.min(chunk_end);
+            if pos == end {
+                return Some(data);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    #[test]
+    fn test_parse() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0xEF, 0xBE, 0xAD, 0xDE, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut parser: Parser<_, Little> = Parser::new(bytes, ParseConfig::default());
+        let record: AuxTrace = parser.parse().unwrap();
+
+        assert_eq!(record.size, 0x1000);
+        assert_eq!(record.offset, 0x2000);
+        assert_eq!(record.reference, 0xDEADBEEF);
+        assert_eq!(record.idx, 1);
+        assert_eq!(record.tid, 2);
+        assert_eq!(record.cpu, 3);
+    }
+
+    #[test]
+    fn index_window() {
+        use crate::AuxFlags;
+
+        let record = |offset, size| AuxTrace {
+            size,
+            offset,
+            reference: 0,
+            idx: 2,
+            tid: 1,
+            cpu: 0,
+        };
+        let aux = |aux_offset, aux_size| Aux {
+            aux_offset,
+            aux_size,
+            flags: AuxFlags::empty(),
+        };
+
+        let mut index = AuxTraceIndex::new();
+        index.push(&record(0, 4), &b"abcd"[..]);
+        index.push(&record(4, 4), &b"efgh"[..]);
+
+        let window = index.window(2, &aux(1, 2)).unwrap();
+        assert!(matches!(window, Cow::Borrowed(_)));
+        assert_eq!(&*window, b"bc");
+
+        assert_eq!(&*index.window(2, &aux(2, 5)).unwrap(), b"cdefg");
+        assert!(index.window(2, &aux(6, 4)).is_none());
+        assert!(index.window(0, &aux(0, 4)).is_none());
+    }
+
+    #[test]
+    fn assemble_out_of_order() {
+        let record = |offset, size| AuxTrace {
+            size,
+            offset,
+            reference: 0,
+            idx: 0,
+            tid: 1,
+            cpu: 0,
+        };
+
+        let mut assembler = AuxTraceAssembler::new();
+        assembler.push(&record(4, 4), &b"efgh"[..]);
+        assembler.push(&record(0, 6), &b"abcdef"[..]);
+
+        let buffers = assembler.finish();
+        assert_eq!(buffers[&(0, 1)], b"abcdefgh");
+    }
+}
@@ -1,13 +1,23 @@
+use std::collections::BTreeMap;
+
 use crate::prelude::*;
+use crate::{KSymbol, KSymbolFlags, KSymbolType};
 use perf_event_open_sys::bindings;
 
 /// BPF_EVENT records indicate when a BPF program is loaded or unloaded.
 ///
+/// When a program is loaded, the kernel first emits one [`KSymbol`] record
+/// for each of the program's JITed functions (one per subprogram, or just one
+/// if the program has no subprograms) and then emits the `BpfEvent` record.
+/// The `tag` here is the tag of the main program so it will not necessarily
+/// match the tags embedded within the names of the subprogram symbols. Use
+/// [`BpfSymbolIndex`] to group the symbols by program.
+///
 /// This struct corresponds to `PERF_RECORD_BPF_EVENT`. See the [manpage] for
 /// more documentation.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default)]
 #[allow(missing_docs)]
 pub struct BpfEvent {
     pub ty: BpfEventType,
@@ -18,7 +28,7 @@ pub struct BpfEvent {
 
 c_enum! {
     /// Indicates the type of a [`BpfEvent`]
-    #[derive(Copy, Clone, Eq, PartialEq, Hash)]
+    #[derive(Copy, Clone, Default, Eq, PartialEq, Hash)]
     pub enum BpfEventType : u16 {
         /// The event type is unknown.
         UNKNOWN = bindings::PERF_BPF_EVENT_UNKNOWN as _,
@@ -36,6 +46,11 @@ impl BpfEventType {
     pub const fn new(value: u16) -> Self {
         Self(value)
     }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        matches!(*self, Self::UNKNOWN | Self::PROG_LOAD | Self::PROG_UNLOAD)
+    }
 }
 
 impl<'p> Parse<'p> for BpfEventType {
@@ -62,3 +77,156 @@ impl<'p> Parse<'p> for BpfEvent {
         })
     }
 }
+
+/// The JITed symbols belonging to a single loaded BPF program.
+#[derive(Clone, Debug)]
+pub struct BpfProgram<'a> {
+    /// The ID of the BPF program.
+    pub id: u32,
+
+    /// The tag of the main BPF program.
+    pub tag: [u8; 8],
+
+    /// The symbols for the program and all of its subprograms.
+    pub symbols: Vec<KSymbol<'a>>,
+}
+
+/// Groups [`KSymbol`] records by the BPF program that they belong to.
+///
+/// The kernel does not record which program a BPF [`KSymbol`] belongs to.
+/// Instead, the symbols for a program are emitted immediately before the
+/// [`BpfEvent`] for that program. This means that records must be pushed in
+/// the order they were emitted by the kernel and that records from different
+/// ring buffers should not be interleaved.
+///
+/// Programs are removed from the index when their
+/// [`PROG_UNLOAD`](BpfEventType::PROG_UNLOAD) event is seen.
+#[derive(Clone, Debug, Default)]
+pub struct BpfSymbolIndex<'a> {
+    pending: Vec<KSymbol<'a>>,
+    programs: BTreeMap<u32, BpfProgram<'a>>,
+}
+
+impl<'a> BpfSymbolIndex<'a> {
+    /// Create a new, empty, `BpfSymbolIndex`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a [`KSymbol`] record to the index.
+    ///
+    /// Symbols that are not BPF symbols, or that are being unregistered, are
+    /// ignored.
+    pub fn push_ksymbol(&mut self, ksymbol: KSymbol<'a>) {
+        if ksymbol.ksym_type != KSymbolType::BPF {
+            return;
+        }
+
+        if ksymbol.flags.contains(KSymbolFlags::UNREGISTER) {
+            return;
+        }
+
+        self.pending.push(ksymbol);
+    }
+
+    /// Add a [`BpfEvent`] record to the index.
+    ///
+    /// On load, all BPF symbols pushed since the last `BpfEvent` are
+    /// associated with the program.
+    pub fn push_bpf_event(&mut self, event: &BpfEvent) {
+        match event.ty {
+            BpfEventType::PROG_LOAD => {
+                let symbols = std::mem::take(&mut self.pending);
+                self.programs.insert(
+                    event.id,
+                    BpfProgram {
+                        id: event.id,
+                        tag: event.tag,
+                        symbols,
+                    },
+                );
+            }
+            BpfEventType::PROG_UNLOAD => {
+                self.pending.clear();
+                self.programs.remove(&event.id);
+            }
+            _ => (),
+        }
+    }
+
+    /// Get the program with the provided ID.
+    pub fn program(&self, id: u32) -> Option<&BpfProgram<'a>> {
+        self.programs.get(&id)
+    }
+
+    /// Get all the symbols associated with the program with the provided ID.
+    pub fn symbols(&self, id: u32) -> &[KSymbol<'a>] {
+        match self.programs.get(&id) {
+            Some(program) => &program.symbols,
+            None => &[],
+        }
+    }
+
+    /// Get all the loaded programs with the provided tag.
+    ///
+    /// Loading the same program multiple times results in multiple programs
+    /// with the same tag but different IDs.
+    pub fn programs_with_tag(&self, tag: [u8; 8]) -> impl Iterator<Item = &BpfProgram<'a>> {
+        self.programs
+            .values()
+            .filter(move |program| program.tag == tag)
+    }
+
+    /// Iterate over all the loaded programs, ordered by ID.
+    pub fn programs(&self) -> impl Iterator<Item = &BpfProgram<'a>> {
+        self.programs.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ksymbol(addr: u64, flags: KSymbolFlags) -> KSymbol<'static> {
+        KSymbol {
+            addr,
+            len: 0x10,
+            ksym_type: KSymbolType::BPF,
+            flags,
+            name: b"bpf_prog_0000000000000000_f"[..].into(),
+        }
+    }
+
+    fn event(ty: BpfEventType, id: u32) -> BpfEvent {
+        BpfEvent {
+            ty,
+            flags: 0,
+            id,
+            tag: [id as u8; 8],
+        }
+    }
+
+    #[test]
+    fn group_subprogs() {
+        let mut index = BpfSymbolIndex::new();
+
+        index.push_ksymbol(ksymbol(0x100, KSymbolFlags::empty()));
+        index.push_ksymbol(ksymbol(0x200, KSymbolFlags::empty()));
+        index.push_bpf_event(&event(BpfEventType::PROG_LOAD, 1));
+        index.push_ksymbol(ksymbol(0x300, KSymbolFlags::empty()));
+        index.push_bpf_event(&event(BpfEventType::PROG_LOAD, 2));
+
+        let addrs: Vec<_> = index.symbols(1).iter().map(|sym| sym.addr).collect();
+        assert_eq!(addrs, [0x100, 0x200]);
+        assert_eq!(index.symbols(2).len(), 1);
+        assert_eq!(index.programs_with_tag([2; 8]).count(), 1);
+
+        index.push_ksymbol(ksymbol(0x100, KSymbolFlags::UNREGISTER));
+        index.push_ksymbol(ksymbol(0x200, KSymbolFlags::UNREGISTER));
+        index.push_bpf_event(&event(BpfEventType::PROG_UNLOAD, 1));
+
+        assert!(index.program(1).is_none());
+        assert!(index.symbols(1).is_empty());
+        assert_eq!(index.programs().count(), 1);
+    }
+}
@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::fmt;
 
 use crate::prelude::*;
+use crate::util::cow::CowSliceExt;
 
 /// CGROUP records indicate when a new cgroup is created and activated.
 ///
@@ -9,7 +10,7 @@ use crate::prelude::*;
 /// documentation.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct CGroup<'a> {
     /// The cgroup ID.
     pub id: u64,
@@ -36,6 +37,12 @@ impl<'a> CGroup<'a> {
             ..self
         }
     }
+
+    /// Whether the data in this `CGroup` is borrowed from the buffer it was
+    /// parsed from.
+    pub fn is_borrowed(&self) -> bool {
+        self.path.is_borrowed()
+    }
 }
 
 impl<'p> Parse<'p> for CGroup<'p> {
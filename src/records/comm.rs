@@ -3,6 +3,7 @@ use std::ffi::OsStr;
 use std::fmt;
 
 use crate::prelude::*;
+use crate::util::cow::CowSliceExt;
 
 used_in_docs!(OsStr);
 
@@ -22,7 +23,7 @@ used_in_docs!(OsStr);
 /// [`prctl(PR_SET_NAME)`]: https://man7.org/linux/man-pages/man2/prctl.2.html
 /// [`COMM_EXEC`]: MiscFlags::COMM_EXEC
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Comm<'a> {
     /// The process ID.
     pub pid: u32,
@@ -52,6 +53,12 @@ impl<'a> Comm<'a> {
             ..self
         }
     }
+
+    /// Whether the data in this `Comm` is borrowed from the buffer it was
+    /// parsed from.
+    pub fn is_borrowed(&self) -> bool {
+        self.comm.is_borrowed()
+    }
 }
 
 impl<'p> Parse<'p> for Comm<'p> {
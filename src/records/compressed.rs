@@ -0,0 +1,55 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::prelude::*;
+
+/// COMPRESSED records contain a chunk of compressed record data.
+///
+/// Like [`AuxTrace`](crate::AuxTrace), these are not emitted by the kernel.
+/// They are written by the `perf` tool when `perf record` is run with `-z`.
+/// The payload is a chunk of a single zstd stream. Decompressing it yields
+/// more records, but a record may be split across multiple COMPRESSED
+/// records, so the decompressor state must be kept across records and the
+/// output fed through a [`RecordStitcher`](crate::parse::RecordStitcher).
+///
+/// This struct corresponds to `PERF_RECORD_COMPRESSED`. See the
+/// [perf source] for more documentation.
+///
+/// [perf source]: https://sourcegraph.com/github.com/torvalds/linux@eb7081409f94a9a8608593d0fb63a1aa3d6f95d8/-/blob/tools/lib/perf/include/perf/event.h?L434
+#[derive(Clone, Default)]
+pub struct Compressed<'a> {
+    /// The compressed data.
+    pub data: Cow<'a, [u8]>,
+}
+
+impl<'a> Compressed<'a> {
+    /// The record type used for COMPRESSED records within `perf.data` files.
+    pub const RECORD_TYPE: u32 = 81;
+
+    /// Convert all the borrowed data in this `Compressed` into owned data.
+    pub fn into_owned(self) -> Compressed<'static> {
+        Compressed {
+            data: self.data.into_owned().into(),
+        }
+    }
+}
+
+impl<'p> Parse<'p> for Compressed<'p> {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        Ok(Self {
+            data: p.parse_rest()?,
+        })
+    }
+}
+
+impl fmt::Debug for Compressed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Compressed")
+            .field("len", &self.data.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -6,7 +6,7 @@ use crate::prelude::*;
 /// documentation.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 #[allow(missing_docs)]
 pub struct Exit {
     pub pid: u32,
@@ -56,4 +56,18 @@ mod tests {
         assert_eq!(exit.ptid, 0x02);
         assert_eq!(exit.time, 0x0400000003);
     }
+
+    #[test]
+    fn construct() {
+        let exit = Exit {
+            pid: 1,
+            tid: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(exit.pid, 1);
+        assert_eq!(exit.tid, 2);
+        assert_eq!(exit.ppid, 0);
+        assert_eq!(exit.time, 0);
+    }
 }
@@ -0,0 +1,35 @@
+use crate::prelude::*;
+
+/// FINISHED_ROUND records mark the end of a round of records.
+///
+/// Like [`AuxTrace`](crate::AuxTrace), these are not emitted by the kernel.
+/// Instead, the `perf` tool writes one to a `perf.data` file or pipe-mode
+/// stream once it has written out everything it read from the ring buffers
+/// in one pass. All records before a FINISHED_ROUND can be sorted by time and
+/// flushed, as later records will not have an earlier timestamp than those
+/// from the round before the previous one.
+///
+/// The record has no body.
+///
+/// This struct corresponds to `PERF_RECORD_FINISHED_ROUND`. See the
+/// [perf source] for more documentation.
+///
+/// [perf source]: https://sourcegraph.com/github.com/torvalds/linux@eb7081409f94a9a8608593d0fb63a1aa3d6f95d8/-/blob/tools/lib/perf/include/perf/event.h
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct FinishedRound;
+
+impl FinishedRound {
+    /// The record type used for FINISHED_ROUND records within `perf.data`
+    /// files.
+    pub const RECORD_TYPE: u32 = 68;
+}
+
+impl<'p> Parse<'p> for FinishedRound {
+    fn parse<B, E>(_: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        Ok(Self)
+    }
+}
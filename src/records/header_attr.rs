@@ -0,0 +1,114 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use perf_event_open_sys::bindings::perf_event_attr;
+
+use crate::prelude::*;
+
+/// HEADER_ATTR records describe an event within a `perf.data` stream.
+///
+/// Like [`AuxTrace`](crate::AuxTrace), these are not emitted by the kernel.
+/// They are written by the `perf` tool in place of the attrs section of the
+/// file header when the file header is not available, such as when
+/// `perf record` writes to a pipe. They may also appear partway through a
+/// stream when new events are added, in which case the records following them
+/// need to be parsed with a config built from the new
+/// [`attr`](HeaderAttr::attr).
+///
+/// This struct corresponds to `PERF_RECORD_HEADER_ATTR`. See the
+/// [perf source] for more documentation.
+///
+/// [perf source]: https://sourcegraph.com/github.com/torvalds/linux@eb7081409f94a9a8608593d0fb63a1aa3d6f95d8/-/blob/tools/lib/perf/include/perf/event.h?L193
+#[derive(Clone, Default)]
+pub struct HeaderAttr<'a> {
+    /// The attr used to create the event.
+    pub attr: perf_event_attr,
+
+    /// The kernel-assigned IDs of the counters that were created using
+    /// [`attr`](HeaderAttr::attr).
+    pub ids: Cow<'a, [u64]>,
+}
+
+impl<'a> HeaderAttr<'a> {
+    /// The record type used for HEADER_ATTR records within `perf.data` files.
+    pub const RECORD_TYPE: u32 = 64;
+
+    /// Convert all the borrowed data in this `HeaderAttr` into owned data.
+    pub fn into_owned(self) -> HeaderAttr<'static> {
+        HeaderAttr {
+            ids: self.ids.into_owned().into(),
+            ..self
+        }
+    }
+}
+
+impl<'p> Parse<'p> for HeaderAttr<'p> {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        let attr = p.parse()?;
+        let ids = match p.remaining_hint() {
+            Some(len) => p.parse_u64_slice(len / 8)?,
+            None => {
+                let mut ids = Vec::new();
+                while !p.is_at_end()? {
+                    ids.push(p.parse()?);
+                }
+                ids.into()
+            }
+        };
+
+        Ok(Self { attr, ids })
+    }
+}
+
+impl fmt::Debug for HeaderAttr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HeaderAttr")
+            .field("type", &self.attr.type_)
+            .field("config", &self.attr.config)
+            .field(
+                "sample_type",
+                &SampleFlags::from_bits_retain(self.attr.sample_type),
+            )
+            .field(
+                "read_format",
+                &ReadFormat::from_bits_retain(self.attr.read_format),
+            )
+            .field("ids", &self.ids)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    #[test]
+    fn parse_header_attr() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, // type, size
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // config
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sample_period
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sample_type
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // read_format
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // flags
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // wakeup_events, bp_type
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // config1
+            0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // id
+            0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // id
+        ];
+
+        let record: HeaderAttr = Parser::new(data, ParseConfig::<Little>::default())
+            .parse()
+            .unwrap();
+
+        assert_eq!(record.attr.config, 1);
+        assert_eq!(record.attr.sample_type, SampleFlags::IP.bits());
+        assert_eq!(&*record.ids, [7, 8]);
+    }
+}
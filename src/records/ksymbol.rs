@@ -1,10 +1,12 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt;
 
 use bitflags::bitflags;
 use perf_event_open_sys::bindings;
 
 use crate::prelude::*;
+use crate::util::cow::CowSliceExt;
 
 /// KSYMBOL records indicate symbols being registered or unregistered within
 /// the kernel.
@@ -13,7 +15,7 @@ use crate::prelude::*;
 /// more documentation.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone)]
+#[derive(Clone, Default)]
 #[allow(missing_docs)]
 pub struct KSymbol<'a> {
     pub addr: u64,
@@ -31,6 +33,12 @@ impl<'a> KSymbol<'a> {
             ..self
         }
     }
+
+    /// Whether the data in this `KSymbol` is borrowed from the buffer it was
+    /// parsed from.
+    pub fn is_borrowed(&self) -> bool {
+        self.name.is_borrowed()
+    }
 }
 
 impl fmt::Debug for KSymbol<'_> {
@@ -47,7 +55,7 @@ impl fmt::Debug for KSymbol<'_> {
 
 c_enum! {
     /// The type of the kernel symbol.
-    #[derive(Copy, Clone, Eq, PartialEq, Hash)]
+    #[derive(Copy, Clone, Default, Eq, PartialEq, Hash)]
     pub enum KSymbolType : u16 {
         /// The symbol is of an unknown type.
         UNKNOWN = bindings::PERF_RECORD_KSYMBOL_TYPE_UNKNOWN as _,
@@ -69,11 +77,16 @@ impl KSymbolType {
     pub const fn new(value: u16) -> Self {
         Self(value)
     }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        matches!(*self, Self::UNKNOWN | Self::BPF | Self::OOL)
+    }
 }
 
 bitflags! {
     /// Flags for [`KSymbol`].
-    #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+    #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
     pub struct KSymbolFlags : u16 {
         /// If set, this means that the symbol is being unregistered.
         const UNREGISTER = bindings::PERF_RECORD_KSYMBOL_FLAGS_UNREGISTER as _;
@@ -115,3 +128,217 @@ impl<'p> Parse<'p> for KSymbol<'p> {
         })
     }
 }
+
+/// An index of the kernel symbols described by [`KSymbol`] records, for
+/// looking up the symbol that contains an address.
+///
+/// This tracks both register and unregister records. Records must be pushed
+/// in the order they were emitted by the kernel:
+/// - Registering a symbol that overlaps with existing symbols shadows the
+///   overlapping parts of those symbols. Older symbols that end up completely
+///   shadowed are dropped from the index.
+/// - Unregistering a symbol removes the symbol registered at the same address.
+///   Any older symbols that it shadowed are not restored.
+///
+/// Symbols of any [`KSymbolType`] are indexed. See
+/// [`BpfSymbolIndex`](crate::BpfSymbolIndex) for grouping BPF symbols by
+/// their program.
+#[derive(Clone, Debug, Default)]
+pub struct KSymbolIndex<'a> {
+    /// The registered symbols, keyed by their address.
+    symbols: BTreeMap<u64, KSymbol<'a>>,
+
+    /// Non-overlapping address ranges, keyed by start address. Each value
+    /// holds the end of the range and the address of the symbol that owns it.
+    ranges: BTreeMap<u64, (u64, u64)>,
+}
+
+impl<'a> KSymbolIndex<'a> {
+    /// Create a new, empty, `KSymbolIndex`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a [`KSymbol`] record to the index.
+    ///
+    /// If the record has the [`UNREGISTER`](KSymbolFlags::UNREGISTER) flag
+    /// set then the symbol at its address is removed. Otherwise, it is
+    /// registered. Symbols with a length of 0 are ignored.
+    pub fn push(&mut self, ksymbol: KSymbol<'a>) {
+        if ksymbol.flags.contains(KSymbolFlags::UNREGISTER) {
+            self.remove(ksymbol.addr);
+            return;
+        }
+
+        if ksymbol.len == 0 {
+            return;
+        }
+
+        let start = ksymbol.addr;
+        let end = symbol_end(&ksymbol);
+
+        // A registration at the same address replaces the old symbol entirely.
+        self.remove(start);
+
+        let mut shadowed = Vec::new();
+        let overlapping: Vec<_> = self
+            .ranges
+            .range(..end)
+            .rev()
+            .take_while(|(_, &(range_end, _))| range_end > start)
+            .map(|(&range_start, &range)| (range_start, range))
+            .collect();
+
+        for (range_start, (range_end, owner)) in overlapping {
+            self.ranges.remove(&range_start);
+            if range_start < start {
+                self.ranges.insert(range_start, (start, owner));
+            }
+            if range_end > end {
+                self.ranges.insert(end, (range_end, owner));
+            }
+
+            shadowed.push(owner);
+        }
+
+        self.ranges.insert(start, (end, start));
+        self.symbols.insert(start, ksymbol);
+
+        for owner in shadowed {
+            if !self.has_ranges(owner) {
+                self.symbols.remove(&owner);
+            }
+        }
+    }
+
+    /// Look up the symbol that contains `addr`.
+    pub fn lookup(&self, addr: u64) -> Option<&KSymbol<'a>> {
+        let (_, &(end, owner)) = self.ranges.range(..=addr).next_back()?;
+        if addr >= end {
+            return None;
+        }
+
+        self.symbols.get(&owner)
+    }
+
+    /// The number of symbols within the index.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Whether the index contains no symbols.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Iterate over the symbols within the index, ordered by address.
+    pub fn symbols(&self) -> impl Iterator<Item = &KSymbol<'a>> {
+        self.symbols.values()
+    }
+
+    /// Remove the symbol registered at `addr`, along with all its ranges.
+    fn remove(&mut self, addr: u64) {
+        let symbol = match self.symbols.remove(&addr) {
+            Some(symbol) => symbol,
+            None => return,
+        };
+
+        let owned: Vec<_> = self
+            .ranges
+            .range(addr..symbol_end(&symbol))
+            .filter(|(_, &(_, owner))| owner == addr)
+            .map(|(&start, _)| start)
+            .collect();
+
+        for start in owned {
+            self.ranges.remove(&start);
+        }
+    }
+
+    /// Whether the symbol at `addr` still owns any ranges.
+    fn has_ranges(&self, addr: u64) -> bool {
+        let end = match self.symbols.get(&addr) {
+            Some(symbol) => symbol_end(symbol),
+            None => return false,
+        };
+
+        self.ranges
+            .range(addr..end)
+            .any(|(_, &(_, owner))| owner == addr)
+    }
+}
+
+fn symbol_end(symbol: &KSymbol) -> u64 {
+    symbol.addr.saturating_add(symbol.len as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ksymbol(addr: u64, len: u32, name: &'static str) -> KSymbol<'static> {
+        KSymbol {
+            addr,
+            len,
+            ksym_type: KSymbolType::BPF,
+            name: name.as_bytes().into(),
+            ..Default::default()
+        }
+    }
+
+    fn unregister(addr: u64, len: u32) -> KSymbol<'static> {
+        KSymbol {
+            addr,
+            len,
+            ksym_type: KSymbolType::BPF,
+            flags: KSymbolFlags::UNREGISTER,
+            ..Default::default()
+        }
+    }
+
+    fn name<'i>(index: &'i KSymbolIndex, addr: u64) -> Option<&'i str> {
+        index
+            .lookup(addr)
+            .map(|sym| std::str::from_utf8(&sym.name).unwrap())
+    }
+
+    #[test]
+    fn lookup_and_unregister() {
+        let mut index = KSymbolIndex::new();
+        index.push(ksymbol(0x100, 0x100, "a"));
+        index.push(ksymbol(0x300, 0x10, "b"));
+
+        assert_eq!(name(&index, 0x0FF), None);
+        assert_eq!(name(&index, 0x100), Some("a"));
+        assert_eq!(name(&index, 0x1FF), Some("a"));
+        assert_eq!(name(&index, 0x200), None);
+        assert_eq!(name(&index, 0x30F), Some("b"));
+
+        index.push(unregister(0x100, 0x100));
+        assert_eq!(name(&index, 0x180), None);
+        assert_eq!(index.len(), 1);
+    }
+
+    #[test]
+    fn overlapping_registration() {
+        let mut index = KSymbolIndex::new();
+        index.push(ksymbol(0x100, 0x100, "a"));
+        index.push(ksymbol(0x140, 0x40, "b"));
+
+        assert_eq!(name(&index, 0x13F), Some("a"));
+        assert_eq!(name(&index, 0x140), Some("b"));
+        assert_eq!(name(&index, 0x17F), Some("b"));
+        assert_eq!(name(&index, 0x180), Some("a"));
+        assert_eq!(index.len(), 2);
+
+        // Completely shadows both "a" and "b".
+        index.push(ksymbol(0x80, 0x200, "c"));
+        assert_eq!(name(&index, 0x100), Some("c"));
+        assert_eq!(name(&index, 0x150), Some("c"));
+        assert_eq!(index.len(), 1);
+
+        index.push(unregister(0x80, 0x200));
+        assert_eq!(name(&index, 0x150), None);
+        assert!(index.is_empty());
+    }
+}
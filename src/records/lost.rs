@@ -4,7 +4,7 @@ use crate::prelude::*;
 ///
 /// This will happen when the sampler ring buffer fills up and there is no
 /// space left for events to be inserted.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default)]
 pub struct Lost {
     /// The unique event ID for the samples that were lost.
     pub id: u64,
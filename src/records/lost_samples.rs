@@ -7,7 +7,7 @@ use crate::prelude::*;
 /// for more documentation.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct LostSamples {
     /// The number of potentially lost samples.
     pub lost: u64,
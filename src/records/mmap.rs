@@ -1,9 +1,11 @@
 use std::borrow::Cow;
 use std::ffi::OsStr;
 use std::fmt;
+use std::ops::Range;
 
 use crate::prelude::*;
-use crate::Mmap2;
+use crate::util::cow::CowSliceExt;
+use crate::{Mmap2, VirtAddr};
 
 /// MMAP events record memory mappings.
 ///
@@ -11,7 +13,7 @@ use crate::Mmap2;
 /// documentation here.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Mmap<'a> {
     /// The process ID.
     pub pid: u32,
@@ -64,6 +66,46 @@ impl<'a> Mmap<'a> {
         OsStrExt::from_bytes(&self.filename)
     }
 
+    /// The filename with any trailing ` (deleted)` suffix removed.
+    ///
+    /// The kernel appends ` (deleted)` to the path of files which have been
+    /// unlinked since they were mapped.
+    pub fn filename_clean(&self) -> &[u8] {
+        crate::util::filename::clean(&self.filename)
+    }
+
+    /// Whether this mapping is of anonymous memory.
+    ///
+    /// This uses the same names as the `perf` tool: `//anon`, `/dev/zero`, and
+    /// `/anon_hugepage`.
+    pub fn is_anonymous(&self) -> bool {
+        crate::util::filename::is_anonymous(&self.filename)
+    }
+
+    /// Whether the filename is a special name provided by the kernel (e.g.
+    /// `[vdso]`, `[stack]`, or `[heap]`) instead of a path.
+    pub fn is_special(&self) -> bool {
+        crate::util::filename::is_special(&self.filename)
+    }
+
+    /// The address that the mapping was placed at, as a [`VirtAddr`].
+    pub fn start_addr(&self) -> VirtAddr {
+        VirtAddr::new(self.addr)
+    }
+
+    /// The range of addresses covered by this mapping.
+    ///
+    /// The end of the range saturates at `u64::MAX` instead of overflowing
+    /// for mappings at the very top of the address space.
+    pub fn range(&self) -> Range<u64> {
+        self.addr..self.addr.saturating_add(self.len)
+    }
+
+    /// Whether `addr` falls within this mapping.
+    pub fn contains(&self, addr: u64) -> bool {
+        self.range().contains(&addr)
+    }
+
     /// Convert all the borrowed data in this `Mmap` into owned data.
     pub fn into_owned(self) -> Mmap<'static> {
         Mmap {
@@ -71,6 +113,12 @@ impl<'a> Mmap<'a> {
             ..self
         }
     }
+
+    /// Whether the data in this `Mmap` is borrowed from the buffer it was
+    /// parsed from.
+    pub fn is_borrowed(&self) -> bool {
+        self.filename.is_borrowed()
+    }
 }
 
 impl<'p> Parse<'p> for Mmap<'p> {
@@ -132,4 +180,57 @@ mod tests {
         assert_eq!(mmap.pgoff, 0x7FBD8176A000);
         assert_eq!(&*mmap.filename, b"//anon");
     }
+
+    #[test]
+    fn parse_with_sample_id() {
+        use perf_event_open_sys::bindings::perf_event_attr;
+
+        use crate::Record;
+
+        #[rustfmt::skip]
+        let trailer: &[u8] = &[
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // time
+        ];
+        let mut data = crate::doctest::MMAP.to_vec();
+        data[6] += trailer.len() as u8;
+        data.extend_from_slice(trailer);
+
+        let mut attr = perf_event_attr::default();
+        attr.sample_type = SampleFlags::TIME.bits();
+        attr.set_sample_id_all(1);
+        let config = ParseConfig::<Little>::from(&attr).with_mmap_sample_id(true);
+
+        let mut parser = Parser::new(&data[..], config);
+        let (record, metadata) = parser.parse_record_and_metadata().unwrap();
+
+        match record {
+            Record::Mmap(mmap) => assert_eq!(&*mmap.filename, b"//anon"),
+            _ => panic!("expected a MMAP record, got {record:?}"),
+        }
+        assert_eq!(metadata.sample_id().time(), Some(0x1000));
+    }
+
+    #[test]
+    fn address_range() {
+        let mmap = Mmap {
+            addr: 0x1000,
+            len: 0x1000,
+            filename: b"//anon"[..].into(),
+            ..Default::default()
+        };
+        assert_eq!(mmap.range(), 0x1000..0x2000);
+        assert!(mmap.contains(0x1000));
+        assert!(mmap.contains(0x1FFF));
+        assert!(!mmap.contains(0x2000));
+        assert!(!mmap.contains(0xFFF));
+
+        let mmap = Mmap {
+            addr: u64::MAX - 0xFFF,
+            len: 0x2000,
+            filename: b"[vsyscall]"[..].into(),
+            ..Default::default()
+        };
+        assert_eq!(mmap.range().end, u64::MAX);
+        assert!(mmap.contains(u64::MAX - 1));
+    }
 }
@@ -1,12 +1,14 @@
 use std::borrow::Cow;
 use std::ffi::OsStr;
 use std::fmt;
+use std::ops::Range;
 
 use perf_event_open_sys::bindings;
 
 use crate::error::ParseError;
 use crate::prelude::*;
-use crate::Mmap;
+use crate::util::cow::CowSliceExt;
+use crate::{Mmap, VirtAddr};
 
 used_in_docs!(OsStr);
 
@@ -17,7 +19,7 @@ used_in_docs!(OsStr);
 /// documentation here.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct Mmap2<'a> {
     /// The process ID.
     pub pid: u32,
@@ -67,12 +69,68 @@ enum MmapDetail {
         ino_generation: u64,
     },
     BuildId {
-        build_id: [u8; 20],
+        build_id: [u8; Mmap2::BUILD_ID_MAX_LEN],
         len: u8,
     },
 }
 
+impl Default for MmapDetail {
+    fn default() -> Self {
+        Self::Default {
+            maj: 0,
+            min: 0,
+            ino: 0,
+            ino_generation: 0,
+        }
+    }
+}
+
 impl<'a> Mmap2<'a> {
+    /// The maximum length of a build id within a MMAP2 record.
+    ///
+    /// This is part of the kernel ABI. The record reserves a fixed 20-byte
+    /// buffer for the build id (enough for a SHA-1 hash) and the kernel
+    /// will not emit a build id that is longer than that. Binaries with a
+    /// longer build id will need it to be read from the binary itself.
+    pub const BUILD_ID_MAX_LEN: usize = 20;
+
+    /// Set the device and inode of the file that is being mapped.
+    ///
+    /// This replaces the build id, if one was set.
+    pub fn with_inode(mut self, maj: u32, min: u32, ino: u64, ino_generation: u64) -> Self {
+        self.detail = MmapDetail::Default {
+            maj,
+            min,
+            ino,
+            ino_generation,
+        };
+        self
+    }
+
+    /// Set the build id of the file that is being mapped.
+    ///
+    /// This replaces the device and inode, if they were set.
+    ///
+    /// # Panics
+    /// Panics if `build_id` is longer than
+    /// [`BUILD_ID_MAX_LEN`](Self::BUILD_ID_MAX_LEN).
+    pub fn with_build_id(mut self, build_id: &[u8]) -> Self {
+        assert!(
+            build_id.len() <= Self::BUILD_ID_MAX_LEN,
+            "build id was {} bytes long but MMAP2 records can store at most {} bytes",
+            build_id.len(),
+            Self::BUILD_ID_MAX_LEN
+        );
+
+        let mut buf = [0; Self::BUILD_ID_MAX_LEN];
+        buf[..build_id.len()].copy_from_slice(build_id);
+        self.detail = MmapDetail::BuildId {
+            build_id: buf,
+            len: build_id.len() as u8,
+        };
+        self
+    }
+
     /// The path to the file that is being mapped, as an [`OsStr`].
     ///
     /// # Notes
@@ -92,6 +150,46 @@ impl<'a> Mmap2<'a> {
         OsStrExt::from_bytes(&self.filename)
     }
 
+    /// The filename with any trailing ` (deleted)` suffix removed.
+    ///
+    /// The kernel appends ` (deleted)` to the path of files which have been
+    /// unlinked since they were mapped.
+    pub fn filename_clean(&self) -> &[u8] {
+        crate::util::filename::clean(&self.filename)
+    }
+
+    /// Whether this mapping is of anonymous memory.
+    ///
+    /// This uses the same names as the `perf` tool: `//anon`, `/dev/zero`, and
+    /// `/anon_hugepage`.
+    pub fn is_anonymous(&self) -> bool {
+        crate::util::filename::is_anonymous(&self.filename)
+    }
+
+    /// Whether the filename is a special name provided by the kernel (e.g.
+    /// `[vdso]`, `[stack]`, or `[heap]`) instead of a path.
+    pub fn is_special(&self) -> bool {
+        crate::util::filename::is_special(&self.filename)
+    }
+
+    /// The address that the mapping was placed at, as a [`VirtAddr`].
+    pub fn start_addr(&self) -> VirtAddr {
+        VirtAddr::new(self.addr)
+    }
+
+    /// The range of addresses covered by this mapping.
+    ///
+    /// The end of the range saturates at `u64::MAX` instead of overflowing
+    /// for mappings at the very top of the address space.
+    pub fn range(&self) -> Range<u64> {
+        self.addr..self.addr.saturating_add(self.len)
+    }
+
+    /// Whether `addr` falls within this mapping.
+    pub fn contains(&self, addr: u64) -> bool {
+        self.range().contains(&addr)
+    }
+
     /// The major ID of the underlying device of the fd being mapped.
     pub fn maj(&self) -> Option<u32> {
         match &self.detail {
@@ -127,14 +225,35 @@ impl<'a> Mmap2<'a> {
     /// The build id of the binary being mapped.
     ///
     /// This variant will only be generated if `build_id` was set when building
-    /// the counter.
+    /// the counter. The returned slice is never longer than
+    /// [`BUILD_ID_MAX_LEN`](Self::BUILD_ID_MAX_LEN) bytes.
     pub fn build_id(&self) -> Option<&[u8]> {
         match &self.detail {
-            MmapDetail::BuildId { build_id, len } => Some(&build_id[..*len as usize]),
+            MmapDetail::BuildId { build_id, len } => {
+                let len = (*len as usize).min(build_id.len());
+                Some(&build_id[..len])
+            }
             _ => None,
         }
     }
 
+    /// The build id of the binary being mapped, formatted as a lowercase hex
+    /// string.
+    ///
+    /// This is the format used by most symbolizers when looking up debug info
+    /// by build id.
+    pub fn build_id_hex(&self) -> Option<String> {
+        use std::fmt::Write;
+
+        let build_id = self.build_id()?;
+        let mut hex = String::with_capacity(build_id.len() * 2);
+        for byte in build_id {
+            let _ = write!(hex, "{byte:02x}");
+        }
+
+        Some(hex)
+    }
+
     /// Convert this record to a [`Mmap`] record.
     pub fn to_mmap(&self) -> Mmap<'a> {
         self.clone().into_mmap()
@@ -160,6 +279,12 @@ impl<'a> Mmap2<'a> {
             ..self
         }
     }
+
+    /// Whether the data in this `Mmap2` is borrowed from the buffer it was
+    /// parsed from.
+    pub fn is_borrowed(&self) -> bool {
+        self.filename.is_borrowed()
+    }
 }
 
 impl<'p> Parse<'p> for Mmap2<'p> {
@@ -194,10 +319,13 @@ impl<'p> Parse<'p> for MmapDetail {
             let _ = p.parse_u16()?;
             let build_id = p.parse_array()?;
 
-            if len as usize > build_id.len() {
+            if len as usize > Mmap2::BUILD_ID_MAX_LEN {
                 return Err(ParseError::custom(
                     ErrorKind::InvalidRecord,
-                    format_args!("build_id had invalid length ({len} > 20)"),
+                    format_args!(
+                        "build_id had invalid length ({len} > {})",
+                        Mmap2::BUILD_ID_MAX_LEN
+                    ),
                 ));
             }
 
@@ -248,3 +376,85 @@ impl fmt::Debug for Mmap2<'_> {
         dbg.finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    #[test]
+    fn parse_build_id() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x10, 0x00, 0x00, 0x00, 0x11, 0x00, 0x00, 0x00, // pid, tid
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // addr
+            0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // len
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // pgoff
+            0x04, 0x00, 0x00, 0x00,                         // build_id_size
+            0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x00, 0x00, 0x00, // build_id
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+            0x05, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // prot, flags
+            b'/', b'b', b'i', b'n', 0x00, 0x00, 0x00, 0x00, // filename
+        ];
+
+        let config = ParseConfig::<Little>::default()
+            .with_misc(bindings::PERF_RECORD_MISC_MMAP_BUILD_ID as u16);
+        let mut parser = Parser::new(bytes, config);
+        let mmap: Mmap2 = parser.parse().unwrap();
+
+        assert_eq!(mmap.build_id(), Some(&[0xDE, 0xAD, 0xBE, 0xEF][..]));
+        assert_eq!(mmap.build_id_hex().as_deref(), Some("deadbeef"));
+        assert_eq!(mmap.ino(), None);
+        assert_eq!(&*mmap.filename, b"/bin");
+    }
+
+    #[test]
+    fn construct() {
+        let mut mmap = Mmap2 {
+            pid: 1,
+            tid: 2,
+            addr: 0x1000,
+            len: 0x2000,
+            ..Default::default()
+        }
+        .with_build_id(&[0xDE, 0xAD, 0xBE, 0xEF]);
+        mmap.filename = b"/bin"[..].into();
+
+        assert_eq!(mmap.range(), 0x1000..0x3000);
+        assert_eq!(mmap.build_id_hex().as_deref(), Some("deadbeef"));
+        assert_eq!(mmap.ino(), None);
+
+        let mmap = mmap.with_inode(8, 1, 1234, 5);
+        assert_eq!(mmap.maj(), Some(8));
+        assert_eq!(mmap.min(), Some(1));
+        assert_eq!(mmap.ino(), Some(1234));
+        assert_eq!(mmap.ino_generation(), Some(5));
+        assert_eq!(mmap.build_id(), None);
+        assert_eq!(mmap.pid, 1);
+    }
+
+    #[test]
+    fn parse_oversized_build_id() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x10, 0x00, 0x00, 0x00, 0x11, 0x00, 0x00, 0x00, // pid, tid
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // addr
+            0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // len
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // pgoff
+            0x20, 0x00, 0x00, 0x00,                         // build_id_size
+            0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, // build_id
+            0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA,
+            0xAA, 0xAA, 0xAA, 0xAA,
+            0x05, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // prot, flags
+            b'/', b'b', b'i', b'n', 0x00, 0x00, 0x00, 0x00, // filename
+        ];
+
+        let config = ParseConfig::<Little>::default()
+            .with_misc(bindings::PERF_RECORD_MISC_MMAP_BUILD_ID as u16);
+        let mut parser = Parser::new(bytes, config);
+        let error = parser.parse::<Mmap2>().unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+    }
+}
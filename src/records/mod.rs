@@ -6,10 +6,14 @@
 #[path = "aux_record.rs"]
 mod aux;
 mod aux_output_hw_id;
+mod aux_trace;
 mod bpf_event;
 mod cgroup;
 mod comm;
+mod compressed;
 mod exit;
+mod finished_round;
+mod header_attr;
 mod itrace_start;
 mod ksymbol;
 mod lost;
@@ -19,18 +23,24 @@ mod mmap2;
 mod namespaces;
 mod read;
 mod sample;
+mod switch;
 mod switch_cpu_wide;
 mod text_poke;
 mod throttle;
+mod time_conv;
 
 use perf_event_open_sys::bindings::perf_event_header;
 
 pub use self::aux::*;
 pub use self::aux_output_hw_id::*;
+pub use self::aux_trace::*;
 pub use self::bpf_event::*;
 pub use self::cgroup::*;
 pub use self::comm::*;
+pub use self::compressed::*;
 pub use self::exit::*;
+pub use self::finished_round::*;
+pub use self::header_attr::*;
 pub use self::itrace_start::*;
 pub use self::ksymbol::*;
 pub use self::lost::*;
@@ -40,9 +50,11 @@ pub use self::mmap2::*;
 pub use self::namespaces::*;
 pub use self::read::*;
 pub use self::sample::*;
+pub use self::switch::*;
 pub use self::switch_cpu_wide::*;
 pub use self::text_poke::*;
 pub use self::throttle::*;
+pub use self::time_conv::*;
 
 /// FORK records indicate that a process called [`fork(2)`] successfully.
 ///
@@ -53,6 +65,13 @@ pub use self::throttle::*;
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
 pub type Fork = Exit;
 
+/// The first record type that is used by the `perf` tool instead of the
+/// kernel.
+///
+/// Records with types at or above this value only show up within `perf.data`
+/// files and pipe-mode streams.
+pub(crate) const USER_TYPE_START: u32 = 64;
+
 mod sample_id {
     option_struct! {
         ##[copy]
@@ -71,11 +90,37 @@ use std::borrow::Cow;
 use std::fmt;
 
 use crate::prelude::*;
+use crate::util::cow::CowSliceExt;
+use crate::{RecordMetadata, Visitor};
 
 /// A subset of the sample fields that can be recorded in non-SAMPLE records.
 ///
 /// This will be empty by default unless `sample_id_all` was set when
 /// configuring the perf event counter.
+///
+/// When `sample_id_all` is set, the following `sample_type` bits control
+/// which fields are present. They are laid out within the record in this
+/// order:
+///
+/// | `sample_type` bit | Fields                     |
+/// |-------------------|----------------------------|
+/// | `TID`             | [`pid`], [`tid`]           |
+/// | `TIME`            | [`time`]                   |
+/// | `ID`              | [`id`]                     |
+/// | `STREAM_ID`       | [`stream_id`]              |
+/// | `CPU`             | [`cpu`]                    |
+/// | `IDENTIFIER`      | [`id`]                     |
+///
+/// `CPU` is followed by a reserved `u32` which the kernel always sets to zero
+/// and is not exposed here. If both `ID` and `IDENTIFIER` are set then they
+/// contain the same value and [`id`] uses the one from `ID`.
+///
+/// [`pid`]: SampleId::pid
+/// [`tid`]: SampleId::tid
+/// [`time`]: SampleId::time
+/// [`id`]: SampleId::id
+/// [`stream_id`]: SampleId::stream_id
+/// [`cpu`]: SampleId::cpu
 #[derive(Copy, Clone, Default)]
 pub struct SampleId(sample_id::SampleId);
 
@@ -198,6 +243,151 @@ impl From<Sample<'_>> for SampleId {
     }
 }
 
+/// Access to the fields that are common to both [`Sample`]s and [`SampleId`]s.
+///
+/// SAMPLE records carry these fields within the record body while all other
+/// records carry them within the [`SampleId`] attached to their
+/// [`RecordMetadata`]. This trait allows generic code to access them without
+/// needing to care which is which.
+///
+/// Each method will return `None` if the corresponding field was not
+/// configured to be recorded.
+pub trait HasCommonFields {
+    /// The process ID that generated this event.
+    fn pid(&self) -> Option<u32>;
+
+    /// The thread ID that generated this event.
+    fn tid(&self) -> Option<u32>;
+
+    /// The time at which this event was recorded.
+    fn time(&self) -> Option<u64>;
+
+    /// The CPU on which this event was recorded.
+    fn cpu(&self) -> Option<u32>;
+}
+
+impl HasCommonFields for SampleId {
+    fn pid(&self) -> Option<u32> {
+        SampleId::pid(self)
+    }
+
+    fn tid(&self) -> Option<u32> {
+        SampleId::tid(self)
+    }
+
+    fn time(&self) -> Option<u64> {
+        SampleId::time(self)
+    }
+
+    fn cpu(&self) -> Option<u32> {
+        SampleId::cpu(self)
+    }
+}
+
+impl HasCommonFields for Sample<'_> {
+    fn pid(&self) -> Option<u32> {
+        Sample::pid(self)
+    }
+
+    fn tid(&self) -> Option<u32> {
+        Sample::tid(self)
+    }
+
+    fn time(&self) -> Option<u64> {
+        Sample::time(self)
+    }
+
+    fn cpu(&self) -> Option<u32> {
+        Sample::cpu(self)
+    }
+}
+
+/// Note that the metadata for SAMPLE records never contains a [`SampleId`] so
+/// this will always return `None` for those records. The same is true for
+/// MMAP records unless [`ParseConfig::with_mmap_sample_id`] was used.
+impl HasCommonFields for RecordMetadata {
+    fn pid(&self) -> Option<u32> {
+        self.sample_id().pid()
+    }
+
+    fn tid(&self) -> Option<u32> {
+        self.sample_id().tid()
+    }
+
+    fn time(&self) -> Option<u64> {
+        self.sample_id().time()
+    }
+
+    fn cpu(&self) -> Option<u32> {
+        self.sample_id().cpu()
+    }
+}
+
+/// Get the time at which a record was emitted.
+///
+/// SAMPLE records carry their time within the [`Sample`] itself, while all
+/// other records carry it in the [`SampleId`] stored in their
+/// [`RecordMetadata`]. This returns whichever one applies to `record`, which
+/// makes it suitable as a sort key for records of mixed types.
+///
+/// Returns `None` if the record does not have a time. This is the case if
+/// `PERF_SAMPLE_TIME` was not set in `sample_type`, or if `sample_id_all` was
+/// not set for records other than SAMPLE records.
+pub fn record_time(record: &Record<'_>, metadata: &RecordMetadata) -> Option<u64> {
+    common_fields_source(record, metadata).time()
+}
+
+/// Pick whichever of `record` and `metadata` the common fields of `record`
+/// should be read from.
+fn common_fields_source<'a>(
+    record: &'a Record<'_>,
+    metadata: &'a RecordMetadata,
+) -> &'a dyn HasCommonFields {
+    match record {
+        Record::Sample(sample) => &**sample,
+        _ => metadata,
+    }
+}
+
+/// SAMPLE records take their fields from the [`Sample`] itself while all
+/// other records take them from the [`RecordMetadata`].
+impl HasCommonFields for (Record<'_>, RecordMetadata) {
+    fn pid(&self) -> Option<u32> {
+        common_fields_source(&self.0, &self.1).pid()
+    }
+
+    fn tid(&self) -> Option<u32> {
+        common_fields_source(&self.0, &self.1).tid()
+    }
+
+    fn time(&self) -> Option<u64> {
+        common_fields_source(&self.0, &self.1).time()
+    }
+
+    fn cpu(&self) -> Option<u32> {
+        common_fields_source(&self.0, &self.1).cpu()
+    }
+}
+
+/// Errors have none of the common fields, so every method returns `None`.
+impl<T: HasCommonFields, E> HasCommonFields for Result<T, E> {
+    fn pid(&self) -> Option<u32> {
+        self.as_ref().ok()?.pid()
+    }
+
+    fn tid(&self) -> Option<u32> {
+        self.as_ref().ok()?.tid()
+    }
+
+    fn time(&self) -> Option<u64> {
+        self.as_ref().ok()?.time()
+    }
+
+    fn cpu(&self) -> Option<u32> {
+        self.as_ref().ok()?.cpu()
+    }
+}
+
 /// A record emitted by the linux kernel.
 ///
 /// This enum contains every supported record type emitted by the kernel.
@@ -214,13 +404,13 @@ pub enum Record<'a> {
     Throttle(Throttle),
     Unthrottle(Throttle),
     Fork(Fork),
-    Read(Read),
+    Read(Read<'a>),
     Sample(Box<Sample<'a>>),
     Mmap2(Mmap2<'a>),
     Aux(Aux),
     ITraceStart(ITraceStart),
     LostSamples(LostSamples),
-    Switch,
+    Switch(Switch),
     SwitchCpuWide(SwitchCpuWide),
     Namespaces(Namespaces<'a>),
     KSymbol(KSymbol<'a>),
@@ -268,11 +458,12 @@ record_from!(Comm<'a>);
 // These are both the same struct
 // record_from!(Exit);
 // record_from!(Fork);
-record_from!(Read);
+record_from!(Read<'a>);
 record_from!(Mmap2<'a>);
 record_from!(Aux);
 record_from!(ITraceStart);
 record_from!(LostSamples);
+record_from!(Switch);
 record_from!(SwitchCpuWide);
 record_from!(Namespaces<'a>);
 record_from!(KSymbol<'a>);
@@ -287,7 +478,153 @@ impl<'a> From<Sample<'a>> for Record<'a> {
     }
 }
 
-struct RecordVisitor;
+/// A compact, single-line, summary of the record.
+///
+/// Only the most important fields of each record are shown. Use the `Debug`
+/// impl if you need to see all of them.
+impl fmt::Display for Record<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use crate::util::fmt::ByteStr;
+
+        match self {
+            Self::Mmap(r) => write!(
+                f,
+                "MMAP pid={} tid={} addr={:#x} len={:#x} file={:?}",
+                r.pid,
+                r.tid,
+                r.addr,
+                r.len,
+                ByteStr(&r.filename)
+            ),
+            Self::Lost(r) => write!(f, "LOST id={} lost={}", r.id, r.lost),
+            Self::Comm(r) => write!(
+                f,
+                "COMM pid={} tid={} comm={:?}",
+                r.pid,
+                r.tid,
+                ByteStr(&r.comm)
+            ),
+            Self::Exit(r) => write!(
+                f,
+                "EXIT pid={} ppid={} tid={} ptid={} time={}",
+                r.pid, r.ppid, r.tid, r.ptid, r.time
+            ),
+            Self::Throttle(r) => write!(
+                f,
+                "THROTTLE time={} id={} stream_id={}",
+                r.time, r.id, r.stream_id
+            ),
+            Self::Unthrottle(r) => write!(
+                f,
+                "UNTHROTTLE time={} id={} stream_id={}",
+                r.time, r.id, r.stream_id
+            ),
+            Self::Fork(r) => write!(
+                f,
+                "FORK pid={} ppid={} tid={} ptid={} time={}",
+                r.pid, r.ppid, r.tid, r.ptid, r.time
+            ),
+            Self::Read(r) => match &r.values {
+                ReadData::Single(value) => write!(
+                    f,
+                    "READ pid={} tid={} value={}",
+                    r.pid,
+                    r.tid,
+                    value.value()
+                ),
+                ReadData::Group(group) => {
+                    write!(f, "READ pid={} tid={} nr={}", r.pid, r.tid, group.len())
+                }
+            },
+            Self::Sample(r) => {
+                f.write_str("SAMPLE")?;
+
+                if let Some(ip) = r.ip() {
+                    write!(f, " ip={ip:#x}")?;
+                }
+                if let Some(pid) = r.pid() {
+                    write!(f, " pid={pid}")?;
+                }
+                if let Some(tid) = r.tid() {
+                    write!(f, " tid={tid}")?;
+                }
+                if let Some(time) = r.time() {
+                    write!(f, " time={time}")?;
+                }
+                if let Some(cpu) = r.cpu() {
+                    write!(f, " cpu={cpu}")?;
+                }
+                if let Some(period) = r.period() {
+                    write!(f, " period={period}")?;
+                }
+
+                Ok(())
+            }
+            Self::Mmap2(r) => write!(
+                f,
+                "MMAP2 pid={} tid={} addr={:#x} len={:#x} file={:?}",
+                r.pid,
+                r.tid,
+                r.addr,
+                r.len,
+                ByteStr(&r.filename)
+            ),
+            Self::Aux(r) => write!(
+                f,
+                "AUX offset={:#x} size={:#x} flags={:#x}",
+                r.aux_offset,
+                r.aux_size,
+                r.flags.bits()
+            ),
+            Self::ITraceStart(r) => write!(f, "ITRACE_START pid={} tid={}", r.pid, r.tid),
+            Self::LostSamples(r) => write!(f, "LOST_SAMPLES lost={}", r.lost),
+            Self::Switch(r) => {
+                f.write_str(if r.out { "SWITCH out" } else { "SWITCH in" })?;
+                if r.preempt {
+                    f.write_str(" preempt")?;
+                }
+
+                Ok(())
+            }
+            Self::SwitchCpuWide(r) => {
+                let dir = match r {
+                    SwitchCpuWide::In { .. } => "in",
+                    SwitchCpuWide::Out { .. } => "out",
+                };
+
+                write!(f, "SWITCH_CPU_WIDE {dir} pid={} tid={}", r.pid(), r.tid())
+            }
+            Self::Namespaces(r) => write!(
+                f,
+                "NAMESPACES pid={} tid={} nr={}",
+                r.pid,
+                r.tid,
+                r.namespaces.len()
+            ),
+            Self::KSymbol(r) => write!(
+                f,
+                "KSYMBOL addr={:#x} len={} name={:?}",
+                r.addr,
+                r.len,
+                ByteStr(&r.name)
+            ),
+            Self::BpfEvent(r) => write!(f, "BPF_EVENT type={:?} id={}", r.ty, r.id),
+            Self::CGroup(r) => write!(f, "CGROUP id={} path={:?}", r.id, ByteStr(&r.path)),
+            Self::TextPoke(r) => write!(
+                f,
+                "TEXT_POKE addr={:#x} old_len={} new_len={}",
+                r.addr,
+                r.old_bytes.len(),
+                r.new_bytes.len()
+            ),
+            Self::AuxOutputHwId(r) => write!(f, "AUX_OUTPUT_HW_ID hw_id={:#x}", r.hw_id),
+            Self::Unknown { ty, data } => write!(f, "UNKNOWN type={ty} len={}", data.len()),
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+pub(crate) struct RecordVisitor;
 
 impl<'a> crate::Visitor<'a> for RecordVisitor {
     type Output = Record<'a>;
@@ -327,7 +664,7 @@ impl<'a> crate::Visitor<'a> for RecordVisitor {
         Record::Fork(record)
     }
 
-    fn visit_read(self, record: Read, _: crate::RecordMetadata) -> Self::Output {
+    fn visit_read(self, record: Read<'a>, _: crate::RecordMetadata) -> Self::Output {
         record.into()
     }
 
@@ -351,8 +688,8 @@ impl<'a> crate::Visitor<'a> for RecordVisitor {
         record.into()
     }
 
-    fn visit_switch(self, _: crate::RecordMetadata) -> Self::Output {
-        Record::Switch
+    fn visit_switch(self, record: Switch, _: crate::RecordMetadata) -> Self::Output {
+        record.into()
     }
 
     fn visit_switch_cpu_wide(
@@ -399,6 +736,128 @@ impl<'a> crate::Visitor<'a> for RecordVisitor {
     }
 }
 
+/// A visitor that returns the [`RecordMetadata`] alongside the [`Record`].
+pub(crate) struct RecordMetadataVisitor;
+
+macro_rules! forward_with_metadata {
+    ($( $method:ident($ty:ty) ),* $(,)?) => {$(
+        fn $method(self, record: $ty, metadata: RecordMetadata) -> Self::Output {
+            (RecordVisitor.$method(record, metadata.clone()), metadata)
+        }
+    )*};
+}
+
+impl<'a> crate::Visitor<'a> for RecordMetadataVisitor {
+    type Output = (Record<'a>, RecordMetadata);
+
+    fn visit_unimplemented(self, metadata: RecordMetadata) -> Self::Output {
+        (
+            RecordVisitor.visit_unimplemented(metadata.clone()),
+            metadata,
+        )
+    }
+
+    forward_with_metadata! {
+        visit_mmap(Mmap<'a>),
+        visit_lost(Lost),
+        visit_comm(Comm<'a>),
+        visit_exit(Exit),
+        visit_throttle(Throttle),
+        visit_unthrottle(Throttle),
+        visit_fork(Fork),
+        visit_read(Read<'a>),
+        visit_sample(Sample<'a>),
+        visit_mmap2(Mmap2<'a>),
+        visit_aux(Aux),
+        visit_itrace_start(ITraceStart),
+        visit_lost_samples(LostSamples),
+        visit_switch(Switch),
+        visit_switch_cpu_wide(SwitchCpuWide),
+        visit_namespaces(Namespaces<'a>),
+        visit_ksymbol(KSymbol<'a>),
+        visit_bpf_event(BpfEvent),
+        visit_cgroup(CGroup<'a>),
+        visit_text_poke(TextPoke<'a>),
+        visit_aux_output_hw_id(AuxOutputHwId),
+        visit_unknown(Cow<'a, [u8]>),
+    }
+}
+
+impl Record<'_> {
+    /// Convert all the borrowed data in this `Record` into owned data.
+    pub fn into_owned(self) -> Record<'static> {
+        match self {
+            Self::Mmap(record) => record.into_owned().into(),
+            Self::Lost(record) => record.into(),
+            Self::Comm(record) => record.into_owned().into(),
+            Self::Exit(record) => Record::Exit(record),
+            Self::Throttle(record) => Record::Throttle(record),
+            Self::Unthrottle(record) => Record::Unthrottle(record),
+            Self::Fork(record) => Record::Fork(record),
+            Self::Read(record) => record.into_owned().into(),
+            Self::Sample(record) => Record::Sample(Box::new((*record).into_owned())),
+            Self::Mmap2(record) => record.into_owned().into(),
+            Self::Aux(record) => record.into(),
+            Self::ITraceStart(record) => record.into(),
+            Self::LostSamples(record) => record.into(),
+            Self::Switch(record) => record.into(),
+            Self::SwitchCpuWide(record) => record.into(),
+            Self::Namespaces(record) => record.into_owned().into(),
+            Self::KSymbol(record) => record.into_owned().into(),
+            Self::BpfEvent(record) => record.into(),
+            Self::CGroup(record) => record.into_owned().into(),
+            Self::TextPoke(record) => record.into_owned().into(),
+            Self::AuxOutputHwId(record) => record.into(),
+            Self::Unknown { ty, data } => Record::Unknown {
+                ty,
+                data: data.into_owned().into(),
+            },
+        }
+    }
+
+    /// Get the data of an [`Unknown`](Record::Unknown) record.
+    ///
+    /// Returns `None` if this record is not unknown.
+    pub fn unknown_data(&self) -> Option<&[u8]> {
+        match self {
+            Self::Unknown { data, .. } => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Whether all the data within this record is borrowed from the buffer it
+    /// was parsed from.
+    ///
+    /// Records that do not contain any variable-length data are always
+    /// considered to be borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            Self::Mmap(record) => record.is_borrowed(),
+            Self::Comm(record) => record.is_borrowed(),
+            Self::Sample(record) => record.is_borrowed(),
+            Self::Mmap2(record) => record.is_borrowed(),
+            Self::Namespaces(record) => record.is_borrowed(),
+            Self::KSymbol(record) => record.is_borrowed(),
+            Self::CGroup(record) => record.is_borrowed(),
+            Self::TextPoke(record) => record.is_borrowed(),
+            Self::Read(record) => record.is_borrowed(),
+            Self::Unknown { data, .. } => data.is_borrowed(),
+            Self::Lost(_)
+            | Self::Exit(_)
+            | Self::Throttle(_)
+            | Self::Unthrottle(_)
+            | Self::Fork(_)
+            | Self::Aux(_)
+            | Self::ITraceStart(_)
+            | Self::LostSamples(_)
+            | Self::Switch(_)
+            | Self::SwitchCpuWide(_)
+            | Self::BpfEvent(_)
+            | Self::AuxOutputHwId(_) => true,
+        }
+    }
+}
+
 impl<'p> Record<'p> {
     /// Parse a `Record` using a [`perf_event_header`] that has already been
     /// parsed.
@@ -423,3 +882,260 @@ impl<'p> Parse<'p> for Record<'p> {
         p.parse_record(RecordVisitor)
     }
 }
+
+/// A [`Record`] along with its [`RecordMetadata`].
+///
+/// Parsing a [`Record`] on its own discards the metadata, which includes the
+/// `misc` flags from the record header and the [`SampleId`] trailer. This
+/// keeps both together in a single type that can be passed around. It is
+/// returned by [`Parser::parse_decoded`].
+///
+/// SAMPLE records take their common fields from the [`Sample`] itself while
+/// all other records take them from the [`metadata`](Self::metadata). The
+/// [`HasCommonFields`] impl takes care of picking the right one.
+#[derive(Clone, Debug)]
+pub struct DecodedRecord<'a> {
+    /// The metadata of the record.
+    pub metadata: RecordMetadata,
+
+    /// The record itself.
+    pub record: Record<'a>,
+}
+
+impl DecodedRecord<'_> {
+    /// Convert all the borrowed data in this `DecodedRecord` into owned data.
+    pub fn into_owned(self) -> DecodedRecord<'static> {
+        DecodedRecord {
+            metadata: self.metadata,
+            record: self.record.into_owned(),
+        }
+    }
+}
+
+impl<'a> From<(Record<'a>, RecordMetadata)> for DecodedRecord<'a> {
+    fn from((record, metadata): (Record<'a>, RecordMetadata)) -> Self {
+        Self { metadata, record }
+    }
+}
+
+impl<'a> From<DecodedRecord<'a>> for (Record<'a>, RecordMetadata) {
+    fn from(value: DecodedRecord<'a>) -> Self {
+        (value.record, value.metadata)
+    }
+}
+
+impl HasCommonFields for DecodedRecord<'_> {
+    fn pid(&self) -> Option<u32> {
+        common_fields_source(&self.record, &self.metadata).pid()
+    }
+
+    fn tid(&self) -> Option<u32> {
+        common_fields_source(&self.record, &self.metadata).tid()
+    }
+
+    fn time(&self) -> Option<u64> {
+        common_fields_source(&self.record, &self.metadata).time()
+    }
+
+    fn cpu(&self) -> Option<u32> {
+        common_fields_source(&self.record, &self.metadata).cpu()
+    }
+}
+
+impl<'p> Parse<'p> for DecodedRecord<'p> {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        p.parse_record_and_metadata().map(Self::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    #[test]
+    fn sample_id_fields() {
+        use perf_event_open_sys::bindings::perf_event_attr;
+
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // pid, tid
+            0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // time
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // cpu, res
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // identifier
+        ];
+
+        let mut attr = perf_event_attr::default();
+        attr.sample_type =
+            (SampleFlags::TID | SampleFlags::TIME | SampleFlags::CPU | SampleFlags::IDENTIFIER)
+                .bits();
+        attr.set_sample_id_all(1);
+
+        let config = ParseConfig::<Little>::from(&attr);
+        assert_eq!(SampleId::estimate_len(&config), data.len());
+
+        let sample_id: SampleId = Parser::new(data, config).parse().unwrap();
+        assert_eq!(sample_id.pid(), Some(1));
+        assert_eq!(sample_id.tid(), Some(2));
+        assert_eq!(sample_id.time(), Some(3));
+        assert_eq!(sample_id.cpu(), Some(4));
+        assert_eq!(sample_id.id(), Some(5));
+        assert_eq!(sample_id.stream_id(), None);
+    }
+
+    #[test]
+    fn parse_decoded() {
+        use perf_event_open_sys::bindings::perf_event_attr;
+
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x28, 0x00, // header (EXIT)
+            0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // pid, ppid
+            0x03, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, // tid, ptid
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // exit time
+            0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sample_id time
+        ];
+
+        let mut attr = perf_event_attr::default();
+        attr.sample_type = SampleFlags::TIME.bits();
+        attr.set_sample_id_all(1);
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::from(&attr));
+        let decoded = parser.parse_decoded().unwrap();
+
+        assert!(matches!(decoded.record, Record::Exit(_)));
+        assert_eq!(
+            decoded.metadata.ty(),
+            perf_event_open_sys::bindings::PERF_RECORD_EXIT
+        );
+        assert_eq!(decoded.time(), Some(6));
+        assert!(parser.parse_decoded().is_err());
+    }
+
+    #[test]
+    fn record_time_sample_and_trailer() {
+        use perf_event_open_sys::bindings::perf_event_attr;
+
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // header (SAMPLE)
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // time
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x28, 0x00, // header (EXIT)
+            0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // pid, ppid
+            0x03, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, // tid, ptid
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // exit time
+            0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sample_id time
+        ];
+
+        let mut attr = perf_event_attr::default();
+        attr.sample_type = SampleFlags::TIME.bits();
+        attr.set_sample_id_all(1);
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::from(&attr));
+
+        let (record, metadata) = parser.parse_record_and_metadata().unwrap();
+        assert!(matches!(record, Record::Sample(_)));
+        assert_eq!(record_time(&record, &metadata), Some(1));
+
+        let (record, metadata) = parser.parse_record_and_metadata().unwrap();
+        assert!(matches!(record, Record::Exit(_)));
+        assert_eq!(record_time(&record, &metadata), Some(6));
+
+        let mut parser = Parser::new(crate::doctest::MMAP, ParseConfig::<Little>::default());
+        let (record, metadata) = parser.parse_record_and_metadata().unwrap();
+        assert_eq!(record_time(&record, &metadata), None);
+    }
+
+    #[test]
+    fn has_common_fields() {
+        use perf_event_open_sys::bindings::perf_event_attr;
+
+        fn fields<T: HasCommonFields>(
+            v: &T,
+        ) -> (Option<u32>, Option<u32>, Option<u64>, Option<u32>) {
+            (v.pid(), v.tid(), v.time(), v.cpu())
+        }
+
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x00, // header (SAMPLE)
+            0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // pid, tid
+            0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // time
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // cpu, res
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0x00, // header (EXIT)
+            0x0A, 0x00, 0x00, 0x00, 0x0B, 0x00, 0x00, 0x00, // pid, ppid
+            0x0C, 0x00, 0x00, 0x00, 0x0D, 0x00, 0x00, 0x00, // tid, ptid
+            0x0E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // exit time
+            0x05, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, // sample_id pid, tid
+            0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sample_id time
+            0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sample_id cpu, res
+        ];
+
+        let mut attr = perf_event_attr::default();
+        attr.sample_type = (SampleFlags::TID | SampleFlags::TIME | SampleFlags::CPU).bits();
+        attr.set_sample_id_all(1);
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::from(&attr));
+
+        // The fields of a SAMPLE record live in the sample itself. Its metadata
+        // has no sample_id so it disagrees with the sample.
+        let (record, metadata) = parser.parse_record_and_metadata().unwrap();
+        let sample = match &record {
+            Record::Sample(sample) => sample,
+            record => panic!("expected a SAMPLE record, got {record:?}"),
+        };
+        let expected = (Some(1), Some(2), Some(3), Some(4));
+        assert_eq!(fields(&**sample), expected);
+        assert_eq!(fields(&SampleId::from(&**sample)), expected);
+        assert_eq!(fields(&metadata), (None, None, None, None));
+        assert_eq!(fields(&(record, metadata)), expected);
+
+        // Other records take them from the sample_id trailer.
+        let (record, metadata) = parser.parse_record_and_metadata().unwrap();
+        assert!(matches!(record, Record::Exit(_)));
+        let expected = (Some(5), Some(6), Some(7), Some(8));
+        assert_eq!(fields(metadata.sample_id()), expected);
+        assert_eq!(fields(&metadata), expected);
+        assert_eq!(fields(&(record, metadata)), expected);
+
+        let error: Result<RecordMetadata, ()> = Err(());
+        assert_eq!(fields(&error), (None, None, None, None));
+    }
+
+    #[test]
+    fn unknown_into_owned() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x00,
+            0x01, 0x02, 0x03, 0x04,
+        ];
+
+        let record: Record<'static> = {
+            let data = data.to_vec();
+            let mut parser = Parser::new(&data[..], ParseConfig::<Little>::default());
+            parser.parse::<Record>().unwrap().into_owned()
+        };
+
+        assert!(matches!(record, Record::Unknown { ty: 0xFFFF, .. }));
+        assert_eq!(record.unknown_data(), Some(&[1, 2, 3, 4][..]));
+    }
+
+    #[test]
+    fn record_is_borrowed() {
+        let mut parser = Parser::new(crate::doctest::MMAP, ParseConfig::<Little>::default());
+        let record: Record = parser.parse().unwrap();
+        assert!(record.is_borrowed());
+
+        let record = record.into_owned();
+        assert!(!record.is_borrowed());
+
+        match record {
+            Record::Mmap(mmap) => assert!(!mmap.is_borrowed()),
+            record => panic!("expected a MMAP record, got {record:?}"),
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::borrow::Cow;
 use perf_event_open_sys::bindings;
 
 use crate::prelude::*;
+use crate::util::cow::CowSliceExt;
 
 /// NAMESPACES records include namespace information of a process.
 ///
@@ -10,7 +11,7 @@ use crate::prelude::*;
 /// more documentation.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct Namespaces<'a> {
     /// Process ID.
     pub pid: u32,
@@ -47,7 +48,7 @@ impl<'a> Namespaces<'a> {
 
     /// UTS namespace.
     pub fn uts(&self) -> Option<&NamespaceEntry> {
-        self.namespaces.get(bindings::USER_NS_INDEX as usize)
+        self.namespaces.get(bindings::UTS_NS_INDEX as usize)
     }
 
     /// IPC namespace.
@@ -65,10 +66,73 @@ impl<'a> Namespaces<'a> {
         self.namespaces.get(bindings::USER_NS_INDEX as usize)
     }
 
+    /// Mount namespace.
+    pub fn mnt(&self) -> Option<&NamespaceEntry> {
+        self.namespaces.get(bindings::MNT_NS_INDEX as usize)
+    }
+
     /// Cgroup namespace.
     pub fn cgroup(&self) -> Option<&NamespaceEntry> {
         self.namespaces.get(bindings::CGROUP_NS_INDEX as usize)
     }
+
+    /// Map the namespaces with known indices into a [`TypedNamespaces`].
+    ///
+    /// Any entries beyond those known to this crate are not included. They
+    /// are still available via [`namespaces`](Self::namespaces).
+    pub fn typed(&self) -> TypedNamespaces {
+        TypedNamespaces {
+            network: self.network().copied(),
+            uts: self.uts().copied(),
+            ipc: self.ipc().copied(),
+            pid: self.pid().copied(),
+            user: self.user().copied(),
+            mnt: self.mnt().copied(),
+            cgroup: self.cgroup().copied(),
+        }
+    }
+
+    /// Convert all the borrowed data in this `Namespaces` into owned data.
+    pub fn into_owned(self) -> Namespaces<'static> {
+        Namespaces {
+            namespaces: self.namespaces.into_owned().into(),
+            ..self
+        }
+    }
+
+    /// Whether the data in this `Namespaces` is borrowed from the buffer it was
+    /// parsed from.
+    pub fn is_borrowed(&self) -> bool {
+        self.namespaces.is_borrowed()
+    }
+}
+
+/// The namespaces from a [`Namespaces`] record, by name.
+///
+/// Each field will be `None` if the record did not contain an entry at the
+/// corresponding index.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct TypedNamespaces {
+    /// Network namespace.
+    pub network: Option<NamespaceEntry>,
+
+    /// UTS namespace.
+    pub uts: Option<NamespaceEntry>,
+
+    /// IPC namespace.
+    pub ipc: Option<NamespaceEntry>,
+
+    /// PID namespace.
+    pub pid: Option<NamespaceEntry>,
+
+    /// User namespace.
+    pub user: Option<NamespaceEntry>,
+
+    /// Mount namespace.
+    pub mnt: Option<NamespaceEntry>,
+
+    /// Cgroup namespace.
+    pub cgroup: Option<NamespaceEntry>,
 }
 
 impl<'p> Parse<'p> for NamespaceEntry {
@@ -92,7 +156,7 @@ impl<'p> Parse<'p> for Namespaces<'p> {
     {
         let pid = p.parse()?;
         let tid = p.parse()?;
-        let len = p.parse_u64()? as usize;
+        let len = p.parse_slice_len::<NamespaceEntry>()?;
         let namespaces = unsafe { p.parse_slice(len)? };
 
         Ok(Self {
@@ -102,3 +166,70 @@ impl<'p> Parse<'p> for Namespaces<'p> {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    #[test]
+    fn parse_typed() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x10, 0x00, 0x00, 0x00, 0x11, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut parser: Parser<_, Little> = Parser::new(bytes, ParseConfig::default());
+        let namespaces: Namespaces = parser.parse().unwrap();
+        let typed = namespaces.typed();
+
+        assert_eq!(namespaces.pid, 0x10);
+        assert_eq!(namespaces.tid, 0x11);
+        assert_eq!(typed.network, Some(NamespaceEntry { dev: 1, inode: 2 }));
+        assert_eq!(typed.uts, Some(NamespaceEntry { dev: 3, inode: 4 }));
+        assert_eq!(typed.ipc, None);
+        assert_eq!(typed.cgroup, None);
+    }
+
+    #[test]
+    fn parse_overflowing_len() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x10, 0x00, 0x00, 0x00, 0x11, 0x00, 0x00, 0x00,
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut parser: Parser<_, Little> = Parser::new(bytes, ParseConfig::default());
+        let error = parser.parse::<Namespaces>().unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+    }
+
+    #[test]
+    fn parse_len_overflowing_in_bytes() {
+        // The length fits within a usize but the size of the entries, in bytes,
+        // does not.
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x10, 0x00, 0x00, 0x00, 0x11, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut parser: Parser<_, Little> = Parser::new(bytes, ParseConfig::default());
+        let error = parser.parse::<Namespaces>().unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+        assert!(error
+            .to_string()
+            .contains("array length in bytes larger than usize::MAX"));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::ParseError;
 use crate::prelude::*;
+use crate::util::cow::CowSliceExt;
 use std::borrow::Cow;
 use std::fmt;
 use std::iter::FusedIterator;
@@ -12,20 +13,104 @@ use std::iter::FusedIterator;
 /// documentation.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone, Debug)]
-pub struct Read {
+#[derive(Clone, Debug, Default)]
+pub struct Read<'a> {
     /// The process ID.
     pub pid: u32,
 
     /// The thread ID.
     pub tid: u32,
 
-    /// The value read from the counter during task switch.
-    pub values: ReadValue,
+    /// The values read from the counter during task switch.
+    pub values: ReadData<'a>,
+}
+
+/// The counter values contained within a [`Read`] record or a
+/// [`Sample`](crate::Sample).
+///
+/// The kernel emits a different layout depending on whether `read_format`
+/// contains [`ReadFormat::GROUP`]. Without it, only the value of the counter
+/// that generated the record is emitted. With it, the values of every counter
+/// in the group are emitted. [`into_group`](ReadData::into_group) can be used
+/// to handle both cases uniformly.
+#[derive(Clone, Debug)]
+pub enum ReadData<'a> {
+    /// The value of a single counter.
+    Single(ReadValue),
+
+    /// The values of all the counters within a group.
+    Group(ReadGroup<'a>),
+}
+
+impl<'a> ReadData<'a> {
+    /// Get the group values, if this is a [`ReadData::Group`].
+    pub fn as_group(&self) -> Option<&ReadGroup<'a>> {
+        match self {
+            Self::Group(group) => Some(group),
+            Self::Single(_) => None,
+        }
+    }
+
+    /// Get the single counter value, if this is a [`ReadData::Single`].
+    pub fn as_single(&self) -> Option<&ReadValue> {
+        match self {
+            Self::Single(value) => Some(value),
+            Self::Group(_) => None,
+        }
+    }
+
+    /// Convert this into a [`ReadGroup`].
+    ///
+    /// A single value is converted into a group containing just that one
+    /// counter.
+    pub fn into_group(self) -> ReadGroup<'a> {
+        match self {
+            Self::Single(value) => value.into(),
+            Self::Group(group) => group,
+        }
+    }
+
+    /// Whether the data in this `ReadData` is borrowed.
+    ///
+    /// A single value contains no borrowed data so this always returns `true`
+    /// for [`ReadData::Single`].
+    pub fn is_borrowed(&self) -> bool {
+        match self {
+            Self::Single(_) => true,
+            Self::Group(group) => group.is_borrowed(),
+        }
+    }
+
+    /// Convert all the borrowed data in this `ReadData` into owned data.
+    pub fn into_owned(self) -> ReadData<'static> {
+        match self {
+            Self::Single(value) => ReadData::Single(value),
+            Self::Group(group) => ReadData::Group(group.into_owned()),
+        }
+    }
+}
+
+impl Default for ReadData<'_> {
+    /// A single counter with a value of zero.
+    fn default() -> Self {
+        Self::Single(ReadValue::default())
+    }
+}
+
+impl From<ReadValue> for ReadData<'_> {
+    fn from(value: ReadValue) -> Self {
+        Self::Single(value)
+    }
+}
+
+impl<'a> From<ReadGroup<'a>> for ReadData<'a> {
+    fn from(group: ReadGroup<'a>) -> Self {
+        Self::Group(group)
+    }
 }
 
 /// Data read from a counter.
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct ReadValue {
     read_format: ReadFormat,
     value: u64,
@@ -135,12 +220,17 @@ pub struct ReadGroup<'a> {
 impl<'a> ReadGroup<'a> {
     /// The number of counters contained within this group.
     pub fn len(&self) -> usize {
-        self.entries().count()
+        self.data.len() / self.read_format.element_len()
     }
 
     /// Whether this group has any counters at all.
     pub fn is_empty(&self) -> bool {
-        self.len() == 0
+        self.data.is_empty()
+    }
+
+    /// Whether the data in this `ReadGroup` is borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        self.data.is_borrowed()
     }
 
     /// Convert all the borrowed data in this `ReadGroup` into owned data.
@@ -186,6 +276,15 @@ impl<'a> ReadGroup<'a> {
     pub fn entries(&self) -> GroupIter {
         GroupIter::new(self)
     }
+
+    /// Iterate over the entries contained within this `GroupRead` along with
+    /// their index within the group.
+    ///
+    /// The index of an entry is the order in which its counter was added to
+    /// the group, with the group leader at index 0.
+    pub fn enumerate_entries(&self) -> std::iter::Enumerate<GroupIter> {
+        self.entries().enumerate()
+    }
 }
 
 impl<'a> From<ReadValue> for ReadGroup<'a> {
@@ -435,7 +534,7 @@ impl<'p> Parse<'p> for ReadGroup<'p> {
                     "number of elements in group read was too large for data type",
                 )
             })?;
-        let data = unsafe { p.parse_slice(data_len)? };
+        let data = p.parse_u64_slice(data_len)?;
 
         Ok(Self {
             read_format,
@@ -446,7 +545,38 @@ impl<'p> Parse<'p> for ReadGroup<'p> {
     }
 }
 
-impl<'p> Parse<'p> for Read {
+impl<'a> Read<'a> {
+    /// Convert all the borrowed data in this `Read` into owned data.
+    pub fn into_owned(self) -> Read<'static> {
+        Read {
+            pid: self.pid,
+            tid: self.tid,
+            values: self.values.into_owned(),
+        }
+    }
+
+    /// Whether the data in this `Read` is borrowed from the buffer it was
+    /// parsed from.
+    pub fn is_borrowed(&self) -> bool {
+        self.values.is_borrowed()
+    }
+}
+
+impl<'p> Parse<'p> for ReadData<'p> {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        if p.config().read_format().contains(ReadFormat::GROUP) {
+            Ok(Self::Group(p.parse()?))
+        } else {
+            Ok(Self::Single(p.parse()?))
+        }
+    }
+}
+
+impl<'p> Parse<'p> for Read<'p> {
     fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
     where
         E: Endian,
@@ -471,3 +601,110 @@ impl fmt::Display for TryFromGroupError {
 }
 
 impl std::error::Error for TryFromGroupError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    #[test]
+    fn parse_group_with_id_and_lost() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // nr
+            0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[0].value
+            0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[0].id
+            0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[0].lost
+            0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[1].value
+            0x21, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[1].id
+            0x22, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[1].lost
+        ];
+
+        let config: ParseConfig<Little> = ParseConfig::default()
+            .with_read_format(ReadFormat::GROUP | ReadFormat::ID | ReadFormat::LOST);
+        let mut parser = Parser::new(bytes, config);
+        let group: ReadGroup = parser.parse().unwrap();
+
+        assert_eq!(group.len(), 2);
+        assert!(!group.is_empty());
+        assert_eq!(group.time_enabled(), None);
+        assert_eq!(group.time_running(), None);
+
+        let entries: Vec<_> = group.entries().collect();
+        assert_eq!(entries[0].value(), 0x10);
+        assert_eq!(entries[0].id(), Some(0x11));
+        assert_eq!(entries[0].lost(), Some(0x12));
+        assert_eq!(entries[1].value(), 0x20);
+        assert_eq!(entries[1].id(), Some(0x21));
+        assert_eq!(entries[1].lost(), Some(0x22));
+
+        let entry = group.get_by_id(0x21).unwrap();
+        assert_eq!(entry.value(), 0x20);
+
+        let indices: Vec<_> = group
+            .enumerate_entries()
+            .map(|(index, entry)| (index, entry.id()))
+            .collect();
+        assert_eq!(indices, [(0, Some(0x11)), (1, Some(0x21))]);
+    }
+
+    #[test]
+    fn parse_group_read_record() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // pid, tid
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // nr
+            0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[0].value
+            0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[1].value
+        ];
+
+        let config: ParseConfig<Little> =
+            ParseConfig::default().with_read_format(ReadFormat::GROUP);
+        let read: Read = Parser::new(bytes, config).parse().unwrap();
+
+        assert_eq!(read.pid, 1);
+        assert_eq!(read.tid, 2);
+
+        let group = match read.values {
+            ReadData::Group(group) => group,
+            values => panic!("expected a group read, got {values:?}"),
+        };
+        let values: Vec<_> = group.entries().map(|entry| entry.value()).collect();
+        assert_eq!(values, [0x10, 0x20]);
+    }
+
+    #[test]
+    fn read_data_into_group() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // value
+            0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // id
+        ];
+
+        let config: ParseConfig<Little> = ParseConfig::default().with_read_format(ReadFormat::ID);
+        let data: ReadData = Parser::new(bytes, config).parse().unwrap();
+        assert!(data.as_group().is_none());
+        assert_eq!(data.as_single().unwrap().value(), 0x10);
+
+        let group = data.into_group();
+        assert_eq!(group.len(), 1);
+        assert_eq!(group.get(0).unwrap().id(), Some(0x11));
+    }
+
+    #[test]
+    fn parse_single_read_record() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // pid, tid
+            0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // value
+        ];
+
+        let config: ParseConfig<Little> = ParseConfig::default();
+        let read: Read = Parser::new(bytes, config).parse().unwrap();
+
+        match read.values {
+            ReadData::Single(value) => assert_eq!(value.value(), 0x10),
+            values => panic!("expected a single read, got {values:?}"),
+        }
+    }
+}
@@ -11,8 +11,8 @@ use perf_event_open_sys::bindings::perf_mem_data_src;
 
 use crate::parse::ParseError;
 use crate::prelude::*;
-use crate::ReadGroup;
-use crate::ReadValue;
+use crate::util::cow::CowSliceExt;
+use crate::{CallchainContext, CpuMode, MiscFlags, PhysAddr, RawData, ReadData, VirtAddr, X86Reg};
 
 mod sample_impl {
     use super::*;
@@ -33,14 +33,16 @@ mod sample_impl {
             pub stream_id: u64,
             pub cpu: u32,
             pub period: u64,
-            pub values: ReadGroup<'a>,
+            pub values: ReadData<'a>,
             pub callchain: Cow<'a, [u64]>,
             pub raw: Cow<'a, [u8]>,
             pub lbr_hw_index: u64,
             pub lbr: Cow<'a, [BranchEntry]>,
+            pub branch_counters: Cow<'a, [u64]>,
             pub regs_user: Registers<'a>,
             pub stack_user: Cow<'a, [u8]>,
             pub weight: u64,
+            pub weight_struct: WeightStruct,
             pub data_src: DataSource,
             pub transaction: Txn,
             pub regs_intr: Registers<'a>,
@@ -48,8 +50,8 @@ mod sample_impl {
             pub phys_addr: u64,
             pub aux: Cow<'a, [u8]>,
             pub cgroup: u64,
-            pub data_page_size: u64,
-            pub code_page_size: u64
+            pub data_page_size: PageSize,
+            pub code_page_size: PageSize
         }
     }
 }
@@ -61,7 +63,246 @@ mod sample_impl {
 ///
 /// [manpage]: https://man7.org/linux/man-pages/man2/perf_event_open.2.html
 #[derive(Clone)]
-pub struct Sample<'a>(sample_impl::Sample<'a>);
+pub struct Sample<'a>(sample_impl::Sample<'a>, CpuMode);
+
+impl<'a> Sample<'a> {
+    /// Convert all the borrowed data in this `Sample` into owned data.
+    pub fn into_owned(self) -> Sample<'static> {
+        fn owned<T: Clone>(cow: Option<&Cow<'_, [T]>>) -> Option<Cow<'static, [T]>> {
+            cow.map(|cow| Cow::Owned(cow.to_vec()))
+        }
+
+        let s = &self.0;
+        Sample(
+            sample_impl::Sample::new(
+                s.ip().copied(),
+                s.pid().copied(),
+                s.tid().copied(),
+                s.time().copied(),
+                s.addr().copied(),
+                s.id().copied(),
+                s.stream_id().copied(),
+                s.cpu().copied(),
+                s.period().copied(),
+                s.values().cloned().map(ReadData::into_owned),
+                owned(s.callchain()),
+                owned(s.raw()),
+                s.lbr_hw_index().copied(),
+                owned(s.lbr()),
+                owned(s.branch_counters()),
+                s.regs_user().cloned().map(Registers::into_owned),
+                owned(s.stack_user()),
+                s.weight().copied(),
+                s.weight_struct().copied(),
+                s.data_src().copied(),
+                s.transaction().copied(),
+                s.regs_intr().cloned().map(Registers::into_owned),
+                s.phys_addr().copied(),
+                owned(s.aux()),
+                s.cgroup().copied(),
+                s.data_page_size().copied(),
+                s.code_page_size().copied(),
+            ),
+            self.1,
+        )
+    }
+
+    /// The fields describing the memory location accessed by this sample.
+    ///
+    /// This bundles together [`addr`](Self::addr),
+    /// [`phys_addr`](Self::phys_addr), and
+    /// [`data_page_size`](Self::data_page_size). Returns `None` if none of
+    /// them were recorded.
+    pub fn address(&self) -> Option<SampleAddress> {
+        let address = SampleAddress {
+            virt: self.addr().map(VirtAddr::new),
+            phys: self.phys_addr().map(PhysAddr::new),
+            data_page_size: self.data_page_size(),
+        };
+
+        if address == SampleAddress::default() {
+            None
+        } else {
+            Some(address)
+        }
+    }
+
+    /// The sampled instruction pointer as a [`VirtAddr`].
+    ///
+    /// This is the same as [`ip`](Self::ip).
+    pub fn ip_addr(&self) -> Option<VirtAddr> {
+        self.ip().map(VirtAddr::new)
+    }
+
+    /// Split the [`callchain`](Self::callchain) into the runs of frames
+    /// that belong to each [`CallchainContext`].
+    ///
+    /// The kernel stores context markers inline within the callchain, with
+    /// each marker applying to the frames that follow it. This yields each run
+    /// of frames along with the context it belongs to, without the markers
+    /// themselves. Markers that are not followed by any frames, such as a
+    /// trailing marker, are skipped.
+    ///
+    /// If the callchain does not start with a marker then the leading frames
+    /// are assigned a context based on the [`cpu_mode`](Self::cpu_mode) of
+    /// the sample. If that is not known either, they are assumed to be
+    /// [`KERNEL`](CallchainContext::KERNEL) frames if the first frame is
+    /// within [kernel space](VirtAddr::is_kernel_space) and
+    /// [`USER`](CallchainContext::USER) frames otherwise.
+    ///
+    /// Returns an empty iterator if the sample has no callchain.
+    pub fn callchain_segments(&self) -> CallchainSegments<'_> {
+        let callchain = self.callchain().unwrap_or_default();
+        let context = CallchainContext::from_cpu_mode(self.cpu_mode()).unwrap_or_else(|| {
+            match callchain.first() {
+                Some(&ip) if VirtAddr::new(ip).is_kernel_space() => CallchainContext::KERNEL,
+                _ => CallchainContext::USER,
+            }
+        });
+
+        CallchainSegments::new(callchain, context)
+    }
+
+    /// The branch stack of this sample, along with its hardware index and
+    /// counters.
+    ///
+    /// Returns `None` if the sample does not have a branch stack.
+    pub fn branch_stack(&self) -> Option<BranchStack<'_>> {
+        self.lbr().map(|entries| BranchStack {
+            entries,
+            hw_index: self.lbr_hw_index(),
+            counters: self.branch_counters(),
+        })
+    }
+
+    /// The CPU mode that the sample was taken in.
+    ///
+    /// This comes from the `misc` field of the record header. It will be
+    /// [`CpuMode::UNKNOWN`] if the sample was parsed without a header.
+    pub fn cpu_mode(&self) -> CpuMode {
+        self.1
+    }
+
+    /// Whether the sampled [`ip`](Self::ip) is within the kernel.
+    ///
+    /// This is true if the [`cpu_mode`](Self::cpu_mode) is either
+    /// [`CpuMode::KERNEL`] or [`CpuMode::GUEST_KERNEL`].
+    pub fn ip_is_kernel(&self) -> bool {
+        self.1.is_kernel()
+    }
+
+    /// Whether this sample contains the fields for all of the given `flags`.
+    ///
+    /// This is equivalent to checking that the accessor for each field
+    /// returns `Some`. Flags that do not correspond to a field known to this
+    /// crate are never present.
+    ///
+    /// Note that both [`ID`] and [`IDENTIFIER`] are stored in the same
+    /// [`id`](Self::id) field, so this will report both as being present if
+    /// either of them was set in `sample_type`.
+    ///
+    /// [`ID`]: SampleFlags::ID
+    /// [`IDENTIFIER`]: SampleFlags::IDENTIFIER
+    pub fn has(&self, flags: SampleFlags) -> bool {
+        self.present_fields().contains(flags)
+    }
+
+    /// Iterate over all the fields that are present in this sample.
+    ///
+    /// Fields are yielded in the same order as they appear within the record.
+    /// This allows generically processing every field of a sample, e.g. to
+    /// write it out to a columnar format, without having to call every
+    /// accessor individually.
+    pub fn fields(&self) -> impl Iterator<Item = SampleField<'_>> {
+        let fields = [
+            self.ip().map(SampleField::Ip),
+            self.pid().map(SampleField::Pid),
+            self.tid().map(SampleField::Tid),
+            self.time().map(SampleField::Time),
+            self.addr().map(SampleField::Addr),
+            self.id().map(SampleField::Id),
+            self.stream_id().map(SampleField::StreamId),
+            self.cpu().map(SampleField::Cpu),
+            self.period().map(SampleField::Period),
+            self.values().map(SampleField::Values),
+            self.callchain().map(SampleField::Callchain),
+            self.raw().map(SampleField::Raw),
+            self.lbr_hw_index().map(SampleField::LbrHwIndex),
+            self.lbr().map(SampleField::Lbr),
+            self.branch_counters().map(SampleField::BranchCounters),
+            self.regs_user().map(SampleField::RegsUser),
+            self.stack_user().map(SampleField::StackUser),
+            self.weight().map(SampleField::Weight),
+            self.weight_struct().map(SampleField::WeightStruct),
+            self.data_src().map(SampleField::DataSrc),
+            self.transaction().map(SampleField::Transaction),
+            self.regs_intr().map(SampleField::RegsIntr),
+            self.phys_addr().map(SampleField::PhysAddr),
+            self.cgroup().map(SampleField::Cgroup),
+            self.data_page_size().map(SampleField::DataPageSize),
+            self.code_page_size().map(SampleField::CodePageSize),
+            self.aux().map(SampleField::Aux),
+        ];
+
+        fields.into_iter().flatten()
+    }
+
+    fn present_fields(&self) -> SampleFlags {
+        let fields = [
+            (SampleFlags::IP, self.ip().is_some()),
+            (SampleFlags::TID, self.pid().is_some()),
+            (SampleFlags::TIME, self.time().is_some()),
+            (SampleFlags::ADDR, self.addr().is_some()),
+            (SampleFlags::READ, self.values().is_some()),
+            (SampleFlags::CALLCHAIN, self.callchain().is_some()),
+            (SampleFlags::ID, self.id().is_some()),
+            (SampleFlags::CPU, self.cpu().is_some()),
+            (SampleFlags::PERIOD, self.period().is_some()),
+            (SampleFlags::STREAM_ID, self.stream_id().is_some()),
+            (SampleFlags::RAW, self.raw().is_some()),
+            (SampleFlags::BRANCH_STACK, self.lbr().is_some()),
+            (SampleFlags::REGS_USER, self.regs_user().is_some()),
+            (SampleFlags::STACK_USER, self.stack_user().is_some()),
+            (SampleFlags::WEIGHT, self.weight().is_some()),
+            (SampleFlags::DATA_SRC, self.data_src().is_some()),
+            (SampleFlags::IDENTIFIER, self.id().is_some()),
+            (SampleFlags::TRANSACTION, self.transaction().is_some()),
+            (SampleFlags::REGS_INTR, self.regs_intr().is_some()),
+            (SampleFlags::PHYS_ADDR, self.phys_addr().is_some()),
+            (SampleFlags::AUX, self.aux().is_some()),
+            (SampleFlags::CGROUP, self.cgroup().is_some()),
+            (SampleFlags::DATA_PAGE_SIZE, self.data_page_size().is_some()),
+            (SampleFlags::CODE_PAGE_SIZE, self.code_page_size().is_some()),
+            (SampleFlags::WEIGHT_STRUCT, self.weight_struct().is_some()),
+        ];
+
+        let mut present = SampleFlags::empty();
+        for (flag, is_present) in fields {
+            present.set(flag, is_present);
+        }
+        present
+    }
+
+    /// Whether all the data within this sample is borrowed from the buffer it
+    /// was parsed from.
+    ///
+    /// Fields that are not present are treated as borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        fn borrowed<T: Clone>(cow: Option<&Cow<'_, [T]>>) -> bool {
+            cow.is_none_or(|cow| cow.is_borrowed())
+        }
+
+        borrowed(self.0.callchain())
+            && borrowed(self.0.raw())
+            && borrowed(self.0.lbr())
+            && borrowed(self.0.branch_counters())
+            && borrowed(self.0.stack_user())
+            && borrowed(self.0.aux())
+            && self.0.values().is_none_or(ReadData::is_borrowed)
+            && self.0.regs_user().is_none_or(Registers::is_borrowed)
+            && self.0.regs_intr().is_none_or(Registers::is_borrowed)
+    }
+}
 
 #[allow(missing_docs)]
 impl<'a> Sample<'a> {
@@ -101,7 +342,11 @@ impl<'a> Sample<'a> {
         self.0.period().copied()
     }
 
-    pub fn values(&self) -> Option<&ReadGroup<'a>> {
+    /// The counter values read when this sample was taken.
+    ///
+    /// This will be a [`ReadData::Group`] if `read_format` contained
+    /// [`ReadFormat::GROUP`] and a [`ReadData::Single`] otherwise.
+    pub fn values(&self) -> Option<&ReadData<'a>> {
         self.0.values()
     }
 
@@ -113,6 +358,15 @@ impl<'a> Sample<'a> {
         self.0.raw().map(|cow| &**cow)
     }
 
+    /// The [`raw`](Self::raw) data of this sample, wrapped in a [`RawData`]
+    /// for reading the fields of a tracepoint.
+    ///
+    /// `endian` should be the endianness of the data that the sample was
+    /// parsed from.
+    pub fn raw_data<E: Endian>(&self, endian: E) -> Option<RawData<'_, E>> {
+        self.raw().map(|raw| RawData::new(raw, endian))
+    }
+
     pub fn lbr_hw_index(&self) -> Option<u64> {
         self.0.lbr_hw_index().copied()
     }
@@ -121,6 +375,15 @@ impl<'a> Sample<'a> {
         self.0.lbr().map(|cow| &**cow)
     }
 
+    /// The counter values recorded alongside each entry in [`lbr`](Self::lbr).
+    ///
+    /// These are only present if `PERF_SAMPLE_BRANCH_COUNTERS` was set in
+    /// `branch_sample_type`. Each value packs the occurrences of several
+    /// events, see the `perf_event_open` documentation for the layout.
+    pub fn branch_counters(&self) -> Option<&[u64]> {
+        self.0.branch_counters().map(|cow| &**cow)
+    }
+
     pub fn regs_user(&self) -> Option<&Registers<'a>> {
         self.0.regs_user()
     }
@@ -133,6 +396,14 @@ impl<'a> Sample<'a> {
         self.0.weight().copied()
     }
 
+    /// The weight of the sample, split into its component fields.
+    ///
+    /// This is only present if `PERF_SAMPLE_WEIGHT_STRUCT` was set in
+    /// `sample_type`, in which case [`weight`](Self::weight) will not be.
+    pub fn weight_struct(&self) -> Option<WeightStruct> {
+        self.0.weight_struct().copied()
+    }
+
     pub fn data_src(&self) -> Option<DataSource> {
         self.0.data_src().copied()
     }
@@ -157,11 +428,11 @@ impl<'a> Sample<'a> {
         self.0.cgroup().copied()
     }
 
-    pub fn data_page_size(&self) -> Option<u64> {
+    pub fn data_page_size(&self) -> Option<PageSize> {
         self.0.data_page_size().copied()
     }
 
-    pub fn code_page_size(&self) -> Option<u64> {
+    pub fn code_page_size(&self) -> Option<PageSize> {
         self.0.code_page_size().copied()
     }
 }
@@ -174,7 +445,45 @@ impl<'p> Parse<'p> for Sample<'p> {
     {
         let config = p.config();
         let sty = config.sample_type();
+        let cpu_mode = MiscFlags::from_bits_retain(config.misc()).cpu_mode();
         let branch_hw_index = config.branch_hw_index();
+        let branch_counters = config.branch_counters();
+
+        // The kernel rejects attrs that set both of these since they occupy the
+        // same slot within the sample. If we see both then the config is bogus.
+        if sty.contains(SampleFlags::WEIGHT | SampleFlags::WEIGHT_STRUCT) {
+            return Err(ParseError::custom(
+                ErrorKind::UnsupportedConfig,
+                "sample_type contains both WEIGHT and WEIGHT_STRUCT",
+            ));
+        }
+
+        // Validate this up front so that a bad read_format is reported as such
+        // instead of whatever error parsing the fields before it would produce.
+        let unsupported_read_format = !(config.read_format() - ReadFormat::all()).is_empty();
+        if sty.contains(SampleFlags::READ) && unsupported_read_format {
+            return Err(ParseError::custom(
+                ErrorKind::UnsupportedConfig,
+                "sample_type contains READ but read_format contains unsupported flags",
+            ));
+        }
+
+        // The kernel rejects an empty register mask so an empty one here means
+        // the config doesn't match the one used to record the sample. Parsing
+        // anyway would misparse every field after the registers.
+        if sty.contains(SampleFlags::REGS_USER) && config.regs_user() == 0 {
+            return Err(ParseError::custom(
+                ErrorKind::UnsupportedConfig,
+                "sample_type contains REGS_USER but the regs_user mask is empty",
+            ));
+        }
+
+        if sty.contains(SampleFlags::REGS_INTR) && config.regs_intr() == 0 {
+            return Err(ParseError::custom(
+                ErrorKind::UnsupportedConfig,
+                "sample_type contains REGS_INTR but the regs_intr mask is empty",
+            ));
+        }
 
         let id = p.parse_if(sty.contains(SampleFlags::IDENTIFIER))?;
         let ip = p.parse_if(sty.contains(SampleFlags::IP))?;
@@ -188,17 +497,13 @@ impl<'p> Parse<'p> for Sample<'p> {
             Ok((p.parse_u32()?, p.parse_u32()?).0)
         })?;
         let period = p.parse_if(sty.contains(SampleFlags::PERIOD))?;
-        let values = p.parse_if_with(sty.contains(SampleFlags::READ), |p| {
-            if p.config().read_format().contains(ReadFormat::GROUP) {
-                p.parse()
-            } else {
-                ReadValue::parse(p).map(From::from)
-            }
-        })?;
+        let values = p.parse_if(sty.contains(SampleFlags::READ))?;
         let callchain = p.parse_if_with(sty.contains(SampleFlags::CALLCHAIN), |p| {
-            let nr = p.parse_u64()? as _;
-            unsafe { p.parse_slice(nr) }
+            let nr = p.parse_slice_len::<u64>()?;
+            p.parse_u64_slice(nr)
         })?;
+        // The padding for RAW covers the u32 size as well as the data so this
+        // can't use parse_aligned_bytes.
         let raw = p.parse_if_with(sty.contains(SampleFlags::RAW), |p| {
             p.parse_padded(std::mem::size_of::<u64>(), |p| {
                 let size = p.parse_u32()? as _;
@@ -206,15 +511,16 @@ impl<'p> Parse<'p> for Sample<'p> {
             })
         })?;
         let lbr = p.parse_if_with(sty.contains(SampleFlags::BRANCH_STACK), |p| {
-            let nr = p.parse_u64()? as usize;
+            let nr = p.parse_slice_len::<BranchEntry>()?;
             let hw_index = p.parse_if(branch_hw_index)?;
             let lbr = unsafe { p.parse_slice(nr)? };
+            let counters = p.parse_if_with(branch_counters, |p| p.parse_u64_slice(nr))?;
 
-            Ok((lbr, hw_index))
+            Ok((lbr, hw_index, counters))
         })?;
-        let (lbr, lbr_hw_index) = match lbr {
-            Some((lbr, hw_index)) => (Some(lbr), hw_index),
-            _ => (None, None),
+        let (lbr, lbr_hw_index, branch_counters) = match lbr {
+            Some((lbr, hw_index, counters)) => (Some(lbr), hw_index, counters),
+            _ => (None, None, None),
         };
         let regs_user = p.parse_if_with(sty.contains(SampleFlags::REGS_USER), |p| {
             Registers::parse_user(p)
@@ -245,6 +551,7 @@ impl<'p> Parse<'p> for Sample<'p> {
             Ok(data)
         })?;
         let weight = p.parse_if(sty.contains(SampleFlags::WEIGHT))?;
+        let weight_struct = p.parse_if(sty.contains(SampleFlags::WEIGHT_STRUCT))?;
         let data_src = p.parse_if(sty.contains(SampleFlags::DATA_SRC))?;
         let transaction = p.parse_if(sty.contains(SampleFlags::TRANSACTION))?;
         let regs_intr = p.parse_if_with(sty.contains(SampleFlags::REGS_INTR), |p| {
@@ -259,33 +566,38 @@ impl<'p> Parse<'p> for Sample<'p> {
             p.parse_bytes(size)
         })?;
 
-        Ok(Self(sample_impl::Sample::new(
-            ip,
-            pid,
-            tid,
-            time,
-            addr,
-            id,
-            stream_id,
-            cpu,
-            period,
-            values,
-            callchain,
-            raw,
-            lbr_hw_index,
-            lbr,
-            regs_user,
-            stack_user,
-            weight,
-            data_src,
-            transaction,
-            regs_intr,
-            phys_addr,
-            aux,
-            cgroup,
-            data_page_size,
-            code_page_size,
-        )))
+        Ok(Self(
+            sample_impl::Sample::new(
+                ip,
+                pid,
+                tid,
+                time,
+                addr,
+                id,
+                stream_id,
+                cpu,
+                period,
+                values,
+                callchain,
+                raw,
+                lbr_hw_index,
+                lbr,
+                branch_counters,
+                regs_user,
+                stack_user,
+                weight,
+                weight_struct,
+                data_src,
+                transaction,
+                regs_intr,
+                phys_addr,
+                aux,
+                cgroup,
+                data_page_size,
+                code_page_size,
+            ),
+            cpu_mode,
+        ))
     }
 }
 
@@ -295,6 +607,188 @@ impl fmt::Debug for Sample<'_> {
     }
 }
 
+/// The memory location accessed by a [`Sample`].
+///
+/// This is returned by [`Sample::address`]. Each field is only present if
+/// the corresponding flag was set in `sample_type`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SampleAddress {
+    /// The virtual address. Requires [`SampleFlags::ADDR`].
+    pub virt: Option<VirtAddr>,
+
+    /// The physical address. Requires [`SampleFlags::PHYS_ADDR`].
+    pub phys: Option<PhysAddr>,
+
+    /// The size of the page containing the data address. Requires
+    /// [`SampleFlags::DATA_PAGE_SIZE`].
+    pub data_page_size: Option<PageSize>,
+}
+
+/// A single field of a [`Sample`], along with its value.
+///
+/// This is the item type of [`Sample::fields`]. Each variant has the same
+/// type as the return value of the corresponding accessor on [`Sample`].
+#[derive(Copy, Clone, Debug)]
+#[non_exhaustive]
+pub enum SampleField<'a> {
+    Ip(u64),
+    Pid(u32),
+    Tid(u32),
+    Time(u64),
+    Addr(u64),
+    Id(u64),
+    StreamId(u64),
+    Cpu(u32),
+    Period(u64),
+    Values(&'a ReadData<'a>),
+    Callchain(&'a [u64]),
+    Raw(&'a [u8]),
+    LbrHwIndex(u64),
+    Lbr(&'a [BranchEntry]),
+    BranchCounters(&'a [u64]),
+    RegsUser(&'a Registers<'a>),
+    StackUser(&'a [u8]),
+    Weight(u64),
+    WeightStruct(WeightStruct),
+    DataSrc(DataSource),
+    Transaction(Txn),
+    RegsIntr(&'a Registers<'a>),
+    PhysAddr(u64),
+    Aux(&'a [u8]),
+    Cgroup(u64),
+    DataPageSize(PageSize),
+    CodePageSize(PageSize),
+}
+
+impl SampleField<'_> {
+    /// The name of this field, matching the name of its accessor on
+    /// [`Sample`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ip(_) => "ip",
+            Self::Pid(_) => "pid",
+            Self::Tid(_) => "tid",
+            Self::Time(_) => "time",
+            Self::Addr(_) => "addr",
+            Self::Id(_) => "id",
+            Self::StreamId(_) => "stream_id",
+            Self::Cpu(_) => "cpu",
+            Self::Period(_) => "period",
+            Self::Values(_) => "values",
+            Self::Callchain(_) => "callchain",
+            Self::Raw(_) => "raw",
+            Self::LbrHwIndex(_) => "lbr_hw_index",
+            Self::Lbr(_) => "lbr",
+            Self::BranchCounters(_) => "branch_counters",
+            Self::RegsUser(_) => "regs_user",
+            Self::StackUser(_) => "stack_user",
+            Self::Weight(_) => "weight",
+            Self::WeightStruct(_) => "weight_struct",
+            Self::DataSrc(_) => "data_src",
+            Self::Transaction(_) => "transaction",
+            Self::RegsIntr(_) => "regs_intr",
+            Self::PhysAddr(_) => "phys_addr",
+            Self::Aux(_) => "aux",
+            Self::Cgroup(_) => "cgroup",
+            Self::DataPageSize(_) => "data_page_size",
+            Self::CodePageSize(_) => "code_page_size",
+        }
+    }
+
+    /// The flag in `sample_type` that causes this field to be recorded.
+    ///
+    /// [`Pid`](Self::Pid) and [`Tid`](Self::Tid) are both recorded by
+    /// [`SampleFlags::TID`]. [`Id`](Self::Id) is recorded by either
+    /// [`SampleFlags::ID`] or [`SampleFlags::IDENTIFIER`], this returns the
+    /// former. [`LbrHwIndex`](Self::LbrHwIndex) and
+    /// [`BranchCounters`](Self::BranchCounters) are part of the branch stack
+    /// and so return [`SampleFlags::BRANCH_STACK`].
+    pub fn flag(&self) -> SampleFlags {
+        match self {
+            Self::Ip(_) => SampleFlags::IP,
+            Self::Pid(_) | Self::Tid(_) => SampleFlags::TID,
+            Self::Time(_) => SampleFlags::TIME,
+            Self::Addr(_) => SampleFlags::ADDR,
+            Self::Id(_) => SampleFlags::ID,
+            Self::StreamId(_) => SampleFlags::STREAM_ID,
+            Self::Cpu(_) => SampleFlags::CPU,
+            Self::Period(_) => SampleFlags::PERIOD,
+            Self::Values(_) => SampleFlags::READ,
+            Self::Callchain(_) => SampleFlags::CALLCHAIN,
+            Self::Raw(_) => SampleFlags::RAW,
+            Self::LbrHwIndex(_) | Self::Lbr(_) | Self::BranchCounters(_) => {
+                SampleFlags::BRANCH_STACK
+            }
+            Self::RegsUser(_) => SampleFlags::REGS_USER,
+            Self::StackUser(_) => SampleFlags::STACK_USER,
+            Self::Weight(_) => SampleFlags::WEIGHT,
+            Self::WeightStruct(_) => SampleFlags::WEIGHT_STRUCT,
+            Self::DataSrc(_) => SampleFlags::DATA_SRC,
+            Self::Transaction(_) => SampleFlags::TRANSACTION,
+            Self::RegsIntr(_) => SampleFlags::REGS_INTR,
+            Self::PhysAddr(_) => SampleFlags::PHYS_ADDR,
+            Self::Aux(_) => SampleFlags::AUX,
+            Self::Cgroup(_) => SampleFlags::CGROUP,
+            Self::DataPageSize(_) => SampleFlags::DATA_PAGE_SIZE,
+            Self::CodePageSize(_) => SampleFlags::CODE_PAGE_SIZE,
+        }
+    }
+}
+
+/// An iterator over the frames within a callchain, grouped by context.
+///
+/// This is returned by [`Sample::callchain_segments`].
+#[derive(Clone, Debug)]
+pub struct CallchainSegments<'a> {
+    rest: &'a [u64],
+    context: CallchainContext,
+}
+
+impl<'a> CallchainSegments<'a> {
+    /// Create a `CallchainSegments` for a raw callchain.
+    ///
+    /// Any frames before the first context marker in `callchain` are assigned
+    /// to `context`.
+    pub fn new(callchain: &'a [u64], context: CallchainContext) -> Self {
+        Self {
+            rest: callchain,
+            context,
+        }
+    }
+}
+
+impl<'a> Iterator for CallchainSegments<'a> {
+    type Item = (CallchainContext, &'a [u64]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((&first, rest)) = self.rest.split_first() {
+            match CallchainContext::from_entry(first) {
+                Some(context) => {
+                    self.context = context;
+                    self.rest = rest;
+                }
+                None => break,
+            }
+        }
+
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let len = self
+            .rest
+            .iter()
+            .position(|&ip| CallchainContext::from_entry(ip).is_some())
+            .unwrap_or(self.rest.len());
+        let (frames, rest) = self.rest.split_at(len);
+        self.rest = rest;
+
+        Some((self.context, frames))
+    }
+}
+
+impl std::iter::FusedIterator for CallchainSegments<'_> {}
+
 /// Describes the captured subset of registers when a sample was taken.
 ///
 /// See the [manpage] for all the details.
@@ -329,9 +823,64 @@ impl SampleRegsAbi {
     pub const fn new(value: u64) -> Self {
         Self(value)
     }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        matches!(*self, Self::NONE | Self::ABI_32 | Self::ABI_64)
+    }
 }
 
 impl<'p> Registers<'p> {
+    /// Convert all the borrowed data in this `Registers` into owned data.
+    pub fn into_owned(self) -> Registers<'static> {
+        Registers {
+            regs: self.regs.into_owned().into(),
+            ..self
+        }
+    }
+
+    /// Whether the register values are borrowed.
+    pub fn is_borrowed(&self) -> bool {
+        self.regs.is_borrowed()
+    }
+
+    /// Get the value of the register at bit `index` of the register mask.
+    ///
+    /// The meaning of each index depends on the architecture. Returns `None`
+    /// if the register was not recorded.
+    pub fn get(&self, index: u32) -> Option<u64> {
+        if index >= u64::BITS || self.mask & (1 << index) == 0 {
+            return None;
+        }
+
+        let position = (self.mask & ((1 << index) - 1)).count_ones();
+        self.regs.get(position as usize).copied()
+    }
+
+    /// Get the value of an x86 register.
+    ///
+    /// The register set depends on the [`abi`](Self::abi) of the program the
+    /// sample was taken from, which may differ between samples, e.g. for a
+    /// 32-bit program running on a 64-bit kernel. For [`ABI_32`] only the
+    /// low 32 bits of the register are returned and registers that are not
+    /// part of the 32-bit register set (`R8` through `R15`) return `None`.
+    /// Returns `None` if the register was not recorded or if the ABI is
+    /// [`NONE`].
+    ///
+    /// [`ABI_32`]: SampleRegsAbi::ABI_32
+    /// [`NONE`]: SampleRegsAbi::NONE
+    pub fn x86(&self, reg: X86Reg) -> Option<u64> {
+        if !reg.is_in_abi(self.abi) {
+            return None;
+        }
+
+        let value = self.get(reg.0.into())?;
+        Some(match self.abi {
+            SampleRegsAbi::ABI_32 => value & u64::from(u32::MAX),
+            _ => value,
+        })
+    }
+
     /// Parse registers using the user registers mask in the config.
     pub fn parse_user<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
     where
@@ -358,7 +907,7 @@ impl<'p> Registers<'p> {
         Ok(Self {
             abi: p.parse()?,
             mask,
-            regs: unsafe { p.parse_slice(mask.count_ones() as _)? },
+            regs: p.parse_u64_slice(mask.count_ones() as _)?,
         })
     }
 }
@@ -400,6 +949,23 @@ impl BranchType {
     pub const fn new(value: u8) -> Self {
         Self(value)
     }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        matches!(
+            *self,
+            Self::UNKNOWN
+                | Self::COND
+                | Self::UNCOND
+                | Self::IND
+                | Self::CALL
+                | Self::IND_CALL
+                | Self::RET
+                | Self::SYSCALL
+                | Self::COND_CALL
+                | Self::COND_RET
+        )
+    }
 }
 
 /// Record of a branch taken by the hardware.
@@ -407,6 +973,27 @@ impl BranchType {
 pub struct BranchEntry(perf_branch_entry);
 
 impl BranchEntry {
+    /// Create a `BranchEntry` from its raw fields.
+    ///
+    /// `flags` is the third `u64` of a `perf_branch_entry`, which packs
+    /// together the `mispred`, `predicted`, `in_tx`, `abort`, `cycles`, and
+    /// `type` bitfields in the native bit order. This is mostly useful for
+    /// building test data.
+    pub fn new(from: u64, to: u64, flags: u64) -> Self {
+        Self(perf_branch_entry {
+            from,
+            to,
+            _bitfield_align_1: [],
+            _bitfield_1: __BindgenBitfieldUnit::new(flags.to_ne_bytes()),
+        })
+    }
+
+    /// The addresses of the source instruction and of the branch target, as
+    /// a `(from, to)` pair.
+    pub fn from_to(&self) -> (u64, u64) {
+        (self.from(), self.to())
+    }
+
     /// Address of the source instruction.
     ///
     /// This may not always be a branch instruction.
@@ -459,12 +1046,196 @@ impl<'p> Parse<'p> for BranchEntry {
         E: Endian,
         B: ParseBuf<'p>,
     {
-        Ok(Self(perf_branch_entry {
-            from: p.parse()?,
-            to: p.parse()?,
-            _bitfield_align_1: [],
-            _bitfield_1: __BindgenBitfieldUnit::new(u64::to_ne_bytes(p.parse()?)),
-        }))
+        Ok(Self::new(p.parse()?, p.parse()?, p.parse()?))
+    }
+}
+
+/// The branch stack recorded as part of a [`Sample`].
+///
+/// This bundles together the [`lbr`](Sample::lbr),
+/// [`lbr_hw_index`](Sample::lbr_hw_index), and
+/// [`branch_counters`](Sample::branch_counters) fields of a sample, which
+/// are always recorded together. It is returned by [`Sample::branch_stack`].
+#[derive(Copy, Clone, Debug)]
+pub struct BranchStack<'a> {
+    /// The branch entries, starting with the most recent branch.
+    pub entries: &'a [BranchEntry],
+
+    /// The index of the most recent branch within the hardware branch
+    /// buffer. Only present if `PERF_SAMPLE_BRANCH_HW_INDEX` was set in
+    /// `branch_sample_type`.
+    pub hw_index: Option<u64>,
+
+    /// The counter values for each entry. Only present if
+    /// `PERF_SAMPLE_BRANCH_COUNTERS` was set in `branch_sample_type`.
+    pub counters: Option<&'a [u64]>,
+}
+
+impl<'a> BranchStack<'a> {
+    /// The number of entries in the branch stack.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the branch stack is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the entries in the branch stack along with their counter
+    /// values, if counters were recorded.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a BranchEntry, Option<u64>)> + 'a {
+        let counters = self.counters;
+
+        self.entries
+            .iter()
+            .enumerate()
+            .map(move |(index, entry)| (entry, counters.and_then(|c| c.get(index).copied())))
+    }
+}
+
+/// The weight of a sample, split into multiple sub-fields.
+///
+/// This is emitted by the kernel in place of the plain `weight` field when
+/// `PERF_SAMPLE_WEIGHT_STRUCT` is set in `sample_type`. It corresponds to
+/// `union perf_sample_weight` in the kernel headers.
+///
+/// The meaning of the individual fields is PMU-specific and may not be
+/// populated at all on some hardware. The semantic accessors on this type
+/// follow the interpretation used by Intel PMUs and by the `perf` tool.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct WeightStruct {
+    /// The first 32-bit weight field (`var1_dw`).
+    pub var1_dw: u32,
+
+    /// The second 16-bit weight field (`var2_w`).
+    pub var2_w: u16,
+
+    /// The third 16-bit weight field (`var3_w`).
+    pub var3_w: u16,
+}
+
+impl WeightStruct {
+    /// Create a `WeightStruct` from the full 64-bit weight value.
+    pub const fn new(full: u64) -> Self {
+        Self {
+            var1_dw: full as u32,
+            var2_w: (full >> 32) as u16,
+            var3_w: (full >> 48) as u16,
+        }
+    }
+
+    /// The full 64-bit weight value, as it would have been emitted for
+    /// `PERF_SAMPLE_WEIGHT`.
+    pub const fn full(&self) -> u64 {
+        (self.var1_dw as u64) | ((self.var2_w as u64) << 32) | ((self.var3_w as u64) << 48)
+    }
+
+    /// The total latency of the sampled memory access, in cycles.
+    ///
+    /// This is the same value that would be reported as the plain `weight`
+    /// on PMUs which support load latency sampling.
+    pub const fn total_latency(&self) -> u32 {
+        self.var1_dw
+    }
+
+    /// The latency of the sampled instruction itself, in cycles.
+    ///
+    /// On Intel PMUs this is the instruction latency as opposed to the total
+    /// latency of the memory access.
+    pub const fn issue_latency(&self) -> u16 {
+        self.var2_w
+    }
+
+    /// The number of cycles spent in a PMU-specific pipeline stage.
+    ///
+    /// This is used by POWER PMUs. It is unused on most other hardware.
+    pub const fn pipeline_stage_cycles(&self) -> u16 {
+        self.var3_w
+    }
+}
+
+impl From<u64> for WeightStruct {
+    fn from(full: u64) -> Self {
+        Self::new(full)
+    }
+}
+
+impl<'p> Parse<'p> for WeightStruct {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        Ok(Self::new(p.parse()?))
+    }
+}
+
+/// The size of a page, in bytes.
+///
+/// This is emitted by the kernel when `PERF_SAMPLE_DATA_PAGE_SIZE` or
+/// `PERF_SAMPLE_CODE_PAGE_SIZE` is set in `sample_type`. It will usually be
+/// the base page size (e.g. 4KiB), or the size of a huge page (e.g. 2MiB or
+/// 1GiB). The kernel reports a page size of 0 if it was unable to determine
+/// the page size for the sampled address.
+///
+/// The `Debug` impl prints the size using binary units (e.g. `2MiB`).
+#[derive(Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PageSize(u64);
+
+impl PageSize {
+    /// Create a `PageSize` from a size in bytes.
+    pub const fn new(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    /// The size of the page, in bytes.
+    pub const fn bytes(&self) -> u64 {
+        self.0
+    }
+
+    /// The base-2 logarithm of the page size, rounded down.
+    ///
+    /// Returns `None` if the page size is 0.
+    pub const fn log2(&self) -> Option<u32> {
+        self.0.checked_ilog2()
+    }
+}
+
+impl From<u64> for PageSize {
+    fn from(bytes: u64) -> Self {
+        Self::new(bytes)
+    }
+}
+
+impl From<PageSize> for u64 {
+    fn from(size: PageSize) -> Self {
+        size.bytes()
+    }
+}
+
+impl<'p> Parse<'p> for PageSize {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        Ok(Self::new(p.parse()?))
+    }
+}
+
+impl fmt::Debug for PageSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+        let mut value = self.0;
+        let mut unit = 0;
+        while value != 0 && value % 1024 == 0 && unit + 1 < UNITS.len() {
+            value /= 1024;
+            unit += 1;
+        }
+
+        write!(f, "{}{}", value, UNITS[unit])
     }
 }
 
@@ -472,6 +1243,11 @@ impl<'p> Parse<'p> for BranchEntry {
 ///
 /// See the [manpage] for a full description.
 ///
+/// The `Display` impl gives a short summary of the known parts, such as
+/// `load, L2 hit, no snoop, TLB L1 hit`. Only the level closest to the CPU is
+/// shown when several are set. Use `Debug` to see each of the individual
+/// flags.
+///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
 #[derive(Copy, Clone, Default)]
 pub struct DataSource(perf_mem_data_src);
@@ -486,6 +1262,29 @@ impl DataSource {
         MemOp::from_bits_retain(self.bitfield().mem_op())
     }
 
+    /// The primary operation performed by the sampled instruction.
+    ///
+    /// If multiple bits are set within [`mem_op`](Self::mem_op) then the
+    /// first one set in this order is returned: store, load, prefetch, exec.
+    /// This means that an instruction which both reads and writes memory is
+    /// classified as a store. Returns `None` if none of those bits are set,
+    /// such as when the operation is not available.
+    pub fn primary_op(&self) -> Option<MemOpKind> {
+        let op = self.mem_op();
+
+        if op.contains(MemOp::STORE) {
+            Some(MemOpKind::Store)
+        } else if op.contains(MemOp::LOAD) {
+            Some(MemOpKind::Load)
+        } else if op.contains(MemOp::PFETCH) {
+            Some(MemOpKind::Prefetch)
+        } else if op.contains(MemOp::EXEC) {
+            Some(MemOpKind::Exec)
+        } else {
+            None
+        }
+    }
+
     /// Memory hierarchy level hit or miss.
     pub fn mem_lvl(&self) -> MemLevel {
         MemLevel::from_bits_retain(self.bitfield().mem_lvl())
@@ -548,6 +1347,43 @@ impl DataSource {
     pub fn mem_hops(&self) -> u8 {
         self.bitfield().mem_hops() as _
     }
+
+    /// The name of the memory level closest to the CPU that is set.
+    ///
+    /// This checks the legacy `mem_lvl` flags first and falls back to
+    /// `mem_lvl_num` for newer hardware that only fills in the latter.
+    fn level_name(&self) -> Option<&'static str> {
+        const LEVELS: &[(MemLevel, &str)] = &[
+            (MemLevel::L1, "L1"),
+            (MemLevel::LFB, "LFB"),
+            (MemLevel::L2, "L2"),
+            (MemLevel::L3, "L3"),
+            (MemLevel::LOC_RAM, "local RAM"),
+            (MemLevel::REM_CCE1, "remote cache (1 hop)"),
+            (MemLevel::REM_CCE2, "remote cache (2 hops)"),
+            (MemLevel::REM_RAM1, "remote RAM (1 hop)"),
+            (MemLevel::REM_RAM2, "remote RAM (2 hops)"),
+            (MemLevel::IO, "I/O"),
+            (MemLevel::UNC, "uncached"),
+        ];
+
+        let lvl = self.mem_lvl();
+        if let Some(&(_, name)) = LEVELS.iter().find(|&&(flag, _)| lvl.contains(flag)) {
+            return Some(name);
+        }
+
+        Some(match self.mem_lvl_num() {
+            MemLevelNum::L1 => "L1",
+            MemLevelNum::L2 => "L2",
+            MemLevelNum::L3 => "L3",
+            MemLevelNum::L4 => "L4",
+            MemLevelNum::ANY_CACHE => "cache",
+            MemLevelNum::LFB => "LFB",
+            MemLevelNum::RAM => "RAM",
+            MemLevelNum::PMEM => "PMEM",
+            _ => return None,
+        })
+    }
 }
 
 impl fmt::Debug for DataSource {
@@ -566,6 +1402,104 @@ impl fmt::Debug for DataSource {
     }
 }
 
+impl fmt::Display for DataSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut empty = true;
+        let mut part = |f: &mut fmt::Formatter<'_>, args: fmt::Arguments<'_>| -> fmt::Result {
+            if !std::mem::replace(&mut empty, false) {
+                f.write_str(", ")?;
+            }
+
+            f.write_fmt(args)
+        };
+
+        if let Some(op) = self.primary_op() {
+            let op = match op {
+                MemOpKind::Load => "load",
+                MemOpKind::Store => "store",
+                MemOpKind::Prefetch => "prefetch",
+                MemOpKind::Exec => "exec",
+            };
+            part(f, format_args!("{op}"))?;
+        }
+
+        let lvl = self.mem_lvl();
+        let result = if lvl.contains(MemLevel::HIT) {
+            " hit"
+        } else if lvl.contains(MemLevel::MISS) {
+            " miss"
+        } else {
+            ""
+        };
+        if let Some(level) = self.level_name() {
+            // The legacy names for remote levels already say that they are
+            // remote.
+            let remote = if self.mem_remote() && !level.starts_with("remote ") {
+                "remote "
+            } else {
+                ""
+            };
+            part(f, format_args!("{remote}{level}{result}"))?;
+        }
+
+        let snoop = self.mem_snoop();
+        let snoop = [
+            (MemSnoop::HITM, "snoop hit modified"),
+            (MemSnoop::HIT, "snoop hit"),
+            (MemSnoop::FWD, "snoop forwarded"),
+            (MemSnoop::PEER, "peer snoop"),
+            (MemSnoop::MISS, "snoop miss"),
+            (MemSnoop::NONE, "no snoop"),
+        ]
+        .into_iter()
+        .find(|&(flag, _)| snoop.contains(flag));
+        if let Some((_, snoop)) = snoop {
+            part(f, format_args!("{snoop}"))?;
+        }
+
+        if self.mem_lock().contains(MemLock::LOCKED) {
+            part(f, format_args!("locked"))?;
+        }
+
+        let tlb = self.mem_dtlb();
+        let level = [
+            (MemDtlb::L1, " L1"),
+            (MemDtlb::L2, " L2"),
+            (MemDtlb::WK, " walker"),
+            (MemDtlb::OS, " OS fault handler"),
+        ]
+        .into_iter()
+        .find(|&(flag, _)| tlb.contains(flag))
+        .map(|(_, name)| name);
+        let result = if tlb.contains(MemDtlb::HIT) {
+            Some(" hit")
+        } else if tlb.contains(MemDtlb::MISS) {
+            Some(" miss")
+        } else {
+            None
+        };
+        if level.is_some() || result.is_some() {
+            let level = level.unwrap_or("");
+            let result = result.unwrap_or("");
+            part(f, format_args!("TLB{level}{result}"))?;
+        }
+
+        let blk = self.mem_blk();
+        if blk.contains(MemBlk::DATA) {
+            part(f, format_args!("blocked on data"))?;
+        }
+        if blk.contains(MemBlk::ADDR) {
+            part(f, format_args!("blocked on address"))?;
+        }
+
+        if empty {
+            f.write_str("N/A")?;
+        }
+
+        Ok(())
+    }
+}
+
 impl<'p> Parse<'p> for DataSource {
     fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
     where
@@ -576,6 +1510,24 @@ impl<'p> Parse<'p> for DataSource {
     }
 }
 
+/// The primary memory operation of a [`DataSource`].
+///
+/// This is returned by [`DataSource::primary_op`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MemOpKind {
+    /// The instruction loaded from memory.
+    Load,
+
+    /// The instruction stored to memory.
+    Store,
+
+    /// The instruction prefetched memory.
+    Prefetch,
+
+    /// The memory access was the instruction fetch itself.
+    Exec,
+}
+
 bitflags! {
     /// Memory operation.
     ///
@@ -744,6 +1696,22 @@ impl MemLevelNum {
     pub const fn new(value: u8) -> Self {
         Self(value)
     }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        matches!(
+            *self,
+            Self::L1
+                | Self::L2
+                | Self::L3
+                | Self::L4
+                | Self::ANY_CACHE
+                | Self::LFB
+                | Self::RAM
+                | Self::PMEM
+                | Self::NA
+        )
+    }
 }
 
 #[cfg(test)]
@@ -752,6 +1720,342 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn sample_address() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // addr
+            0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // phys_addr
+        ];
+
+        let config: ParseConfig<Little> =
+            ParseConfig::default().with_sample_type(SampleFlags::ADDR | SampleFlags::PHYS_ADDR);
+        let sample: Sample = Parser::new(data, config).parse().unwrap();
+
+        assert_eq!(
+            sample.address(),
+            Some(SampleAddress {
+                virt: Some(VirtAddr::new(0x1000)),
+                phys: Some(PhysAddr::new(0x2000)),
+                data_page_size: None,
+            })
+        );
+
+        let config: ParseConfig<Little> = ParseConfig::default();
+        let sample: Sample = Parser::new(data, config).parse().unwrap();
+        assert_eq!(sample.address(), None);
+    }
+
+    #[test]
+    fn sample_fields() {
+        let config: ParseConfig<Little> = ParseConfig::default().with_sample_type(
+            SampleFlags::IP | SampleFlags::TID | SampleFlags::CALLCHAIN | SampleFlags::CGROUP,
+        );
+        let mut parser = Parser::new(crate::doctest::CUSTOM_SAMPLE, config);
+        let sample: crate::Record = parser.parse().unwrap();
+        let sample = match sample {
+            crate::Record::Sample(sample) => sample,
+            record => panic!("expected a SAMPLE record, got {record:?}"),
+        };
+
+        let names: Vec<_> = sample.fields().map(|field| field.name()).collect();
+        assert_eq!(names, ["ip", "pid", "tid", "callchain", "cgroup"]);
+
+        let flags = sample
+            .fields()
+            .fold(SampleFlags::empty(), |flags, field| flags | field.flag());
+        assert_eq!(
+            flags,
+            SampleFlags::IP | SampleFlags::TID | SampleFlags::CALLCHAIN | SampleFlags::CGROUP
+        );
+
+        match sample.fields().nth(3) {
+            Some(SampleField::Callchain(callchain)) => {
+                assert_eq!(callchain, [0xF001, 0xF002, 0xF003, 0xF004])
+            }
+            field => panic!("expected a callchain, got {field:?}"),
+        }
+
+        let empty: &[u8] = &[];
+        let sample: Sample = Parser::new(empty, ParseConfig::<Little>::default())
+            .parse()
+            .unwrap();
+        assert_eq!(sample.fields().count(), 0);
+    }
+
+    #[test]
+    fn sample_fields_with_aux() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // ip
+            0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // cgroup
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // data_page_size
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // aux size
+            0xDE, 0xAD, 0xBE, 0xEF,                         // aux data
+        ];
+
+        let config: ParseConfig<Little> = ParseConfig::default().with_sample_type(
+            SampleFlags::IP | SampleFlags::CGROUP | SampleFlags::DATA_PAGE_SIZE | SampleFlags::AUX,
+        );
+        let sample: Sample = Parser::new(data, config).parse().unwrap();
+
+        let names: Vec<_> = sample.fields().map(|field| field.name()).collect();
+        assert_eq!(names, ["ip", "cgroup", "data_page_size", "aux"]);
+        assert_eq!(sample.cgroup(), Some(7));
+        assert_eq!(sample.aux(), Some(&[0xDE, 0xAD, 0xBE, 0xEF][..]));
+    }
+
+    #[test]
+    fn callchain_segments() {
+        const KERNEL: u64 = CallchainContext::KERNEL.0;
+        const USER: u64 = CallchainContext::USER.0;
+
+        let callchain = [
+            KERNEL,
+            0xFFFF_0001,
+            0xFFFF_0002,
+            USER,
+            0x1001,
+            KERNEL,
+            USER,
+            0x1002,
+            USER,
+        ];
+        let segments: Vec<_> = CallchainSegments::new(&callchain, CallchainContext::HV).collect();
+        assert_eq!(
+            segments,
+            [
+                (CallchainContext::KERNEL, &[0xFFFF_0001, 0xFFFF_0002][..]),
+                (CallchainContext::USER, &[0x1001][..]),
+                (CallchainContext::USER, &[0x1002][..]),
+            ]
+        );
+
+        let segments: Vec<_> =
+            CallchainSegments::new(&[0x1, USER, 0x2], CallchainContext::HV).collect();
+        assert_eq!(
+            segments,
+            [
+                (CallchainContext::HV, &[0x1][..]),
+                (CallchainContext::USER, &[0x2][..])
+            ]
+        );
+
+        assert_eq!(
+            CallchainSegments::new(&[USER], CallchainContext::HV).count(),
+            0
+        );
+
+        // The callchain in this sample has no context markers and the sample
+        // has no cpu mode, so the frames are classified by their address.
+        let config: ParseConfig<Little> = ParseConfig::default().with_sample_type(
+            SampleFlags::IP | SampleFlags::TID | SampleFlags::CALLCHAIN | SampleFlags::CGROUP,
+        );
+        let sample: Sample = Parser::new(&crate::doctest::CUSTOM_SAMPLE[8..], config)
+            .parse()
+            .unwrap();
+        let segments: Vec<_> = sample.callchain_segments().collect();
+        assert_eq!(
+            segments,
+            [(
+                CallchainContext::USER,
+                &[0xF001, 0xF002, 0xF003, 0xF004][..]
+            )]
+        );
+    }
+
+    #[test]
+    fn sample_page_sizes() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x00, 0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, // data_page_size
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // code_page_size
+        ];
+
+        let config: ParseConfig<Little> = ParseConfig::default()
+            .with_sample_type(SampleFlags::DATA_PAGE_SIZE | SampleFlags::CODE_PAGE_SIZE);
+        let sample: Sample = Parser::new(data, config).parse().unwrap();
+
+        let data_page_size = sample.data_page_size().unwrap();
+        assert_eq!(data_page_size.bytes(), 2 << 20);
+        assert_eq!(data_page_size.log2(), Some(21));
+        assert_eq!(format!("{data_page_size:?}"), "2MiB");
+
+        let code_page_size = sample.code_page_size().unwrap();
+        assert_eq!(code_page_size.bytes(), 4096);
+        assert_eq!(format!("{code_page_size:?}"), "4KiB");
+
+        assert_eq!(PageSize::new(0).log2(), None);
+        assert_eq!(format!("{:?}", PageSize::new(0)), "0B");
+        assert_eq!(format!("{:?}", PageSize::new(1 << 30)), "1GiB");
+        assert_eq!(format!("{:?}", PageSize::new(1536)), "1536B");
+    }
+
+    #[test]
+    fn sample_cpu_mode() {
+        let config: ParseConfig<Little> = ParseConfig::default().with_sample_type(
+            SampleFlags::IP | SampleFlags::TID | SampleFlags::CALLCHAIN | SampleFlags::CGROUP,
+        );
+
+        for (misc, mode, kernel) in [
+            (1u16, CpuMode::KERNEL, true),
+            (2, CpuMode::USER, false),
+            (4, CpuMode::GUEST_KERNEL, true),
+        ] {
+            let mut data = crate::doctest::CUSTOM_SAMPLE.to_vec();
+            data[4..6].copy_from_slice(&misc.to_le_bytes());
+
+            let sample = match Parser::new(&data[..], config.clone()).parse().unwrap() {
+                crate::Record::Sample(sample) => sample,
+                record => panic!("expected a SAMPLE record, got {record:?}"),
+            };
+
+            assert_eq!(sample.cpu_mode(), mode);
+            assert_eq!(sample.ip_is_kernel(), kernel);
+            assert_eq!((*sample).into_owned().cpu_mode(), mode);
+        }
+    }
+
+    #[test]
+    fn sample_has_fields() {
+        let config: ParseConfig<Little> = ParseConfig::default().with_sample_type(
+            SampleFlags::IP | SampleFlags::TID | SampleFlags::CALLCHAIN | SampleFlags::CGROUP,
+        );
+        let mut parser = Parser::new(crate::doctest::CUSTOM_SAMPLE, config);
+        let (mut p, _) = parser.parse_metadata().unwrap();
+        let sample: Sample = p.parse().unwrap();
+
+        assert!(sample.has(SampleFlags::CALLCHAIN));
+        assert!(sample.has(SampleFlags::IP | SampleFlags::TID | SampleFlags::CGROUP));
+        assert!(!sample.has(SampleFlags::TIME));
+        assert!(!sample.has(SampleFlags::IP | SampleFlags::RAW));
+        assert!(!sample.has(SampleFlags::from_bits_retain(1 << 63)));
+        assert!(sample.has(SampleFlags::empty()));
+    }
+
+    #[test]
+    fn parse_sample_with_empty_regs_mask() {
+        let data: &[u8] = &[0; 16];
+
+        for flag in [SampleFlags::REGS_USER, SampleFlags::REGS_INTR] {
+            let config: ParseConfig<Little> = ParseConfig::default().with_sample_type(flag);
+            let error = Parser::new(data, config).parse::<Sample>().unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::UnsupportedConfig);
+        }
+    }
+
+    #[test]
+    fn parse_sample_with_both_weights() {
+        let data: &[u8] = &[0; 16];
+        let config: ParseConfig<Little> = ParseConfig::default()
+            .with_sample_type(SampleFlags::WEIGHT | SampleFlags::WEIGHT_STRUCT);
+
+        let error = Parser::new(data, config).parse::<Sample>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnsupportedConfig);
+    }
+
+    #[test]
+    fn parse_sample_with_unsupported_read_format() {
+        let data: &[u8] = &[];
+        let config: ParseConfig<Little> = ParseConfig::default()
+            .with_sample_type(SampleFlags::IP | SampleFlags::READ)
+            .with_read_format(ReadFormat::from_bits_retain(1 << 40));
+
+        let error = Parser::new(data, config).parse::<Sample>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnsupportedConfig);
+    }
+
+    #[test]
+    fn x86_registers_by_abi() {
+        let mask = (1 << X86Reg::AX.0) | (1 << X86Reg::IP.0) | (1 << X86Reg::R8.0);
+        let regs = |abi| Registers {
+            abi,
+            mask,
+            regs: Cow::Borrowed(&[0x1_0000_0001, 0xFFFF_FFFF_8100_0000, 8]),
+        };
+
+        let regs64 = regs(SampleRegsAbi::ABI_64);
+        assert_eq!(regs64.get(X86Reg::IP.0.into()), Some(0xFFFF_FFFF_8100_0000));
+        assert_eq!(regs64.get(64), None);
+        assert_eq!(regs64.x86(X86Reg::AX), Some(0x1_0000_0001));
+        assert_eq!(regs64.x86(X86Reg::R8), Some(8));
+        assert_eq!(regs64.x86(X86Reg::BX), None);
+
+        let regs32 = regs(SampleRegsAbi::ABI_32);
+        assert_eq!(regs32.x86(X86Reg::AX), Some(1));
+        assert_eq!(regs32.x86(X86Reg::IP), Some(0x8100_0000));
+        assert_eq!(regs32.x86(X86Reg::R8), None);
+
+        assert_eq!(regs(SampleRegsAbi::NONE).x86(X86Reg::AX), None);
+    }
+
+    #[test]
+    fn data_source_primary_op() {
+        let source = |op: MemOp| {
+            let mut src = perf_mem_data_src { val: 0 };
+            unsafe { src.__bindgen_anon_1.set_mem_op(op.bits()) };
+            DataSource(src)
+        };
+
+        assert_eq!(source(MemOp::NA).primary_op(), None);
+        assert_eq!(source(MemOp::LOAD).primary_op(), Some(MemOpKind::Load));
+        assert_eq!(source(MemOp::EXEC).primary_op(), Some(MemOpKind::Exec));
+        assert_eq!(
+            source(MemOp::LOAD | MemOp::STORE).primary_op(),
+            Some(MemOpKind::Store)
+        );
+        assert_eq!(
+            source(MemOp::PFETCH | MemOp::EXEC).primary_op(),
+            Some(MemOpKind::Prefetch)
+        );
+    }
+
+    #[test]
+    fn data_source_display() {
+        let mut src = perf_mem_data_src { val: 0 };
+        let bits = unsafe { &mut src.__bindgen_anon_1 };
+        bits.set_mem_op(MemOp::LOAD.bits());
+        bits.set_mem_lvl((MemLevel::HIT | MemLevel::L2).bits());
+        bits.set_mem_snoop(bindings::PERF_MEM_SNOOP_NONE as _);
+        bits.set_mem_dtlb((MemDtlb::HIT | MemDtlb::L1).bits());
+        assert_eq!(
+            DataSource(src).to_string(),
+            "load, L2 hit, no snoop, TLB L1 hit"
+        );
+
+        let mut src = perf_mem_data_src { val: 0 };
+        let bits = unsafe { &mut src.__bindgen_anon_1 };
+        bits.set_mem_op(MemOp::STORE.bits());
+        bits.set_mem_lvl(MemLevel::MISS.bits());
+        bits.set_mem_lvl_num(MemLevelNum::RAM.0 as _);
+        bits.set_mem_remote(1);
+        bits.set_mem_lock(MemLock::LOCKED.bits());
+        bits.set_mem_dtlb(MemDtlb::MISS.bits());
+        assert_eq!(
+            DataSource(src).to_string(),
+            "store, remote RAM miss, locked, TLB miss"
+        );
+
+        let mut src = perf_mem_data_src { val: 0 };
+        let bits = unsafe { &mut src.__bindgen_anon_1 };
+        bits.set_mem_op(MemOp::LOAD.bits());
+        bits.set_mem_lvl((MemLevel::HIT | MemLevel::REM_CCE1).bits());
+        bits.set_mem_remote(1);
+        assert_eq!(
+            DataSource(src).to_string(),
+            "load, remote cache (1 hop) hit"
+        );
+
+        let mut src = perf_mem_data_src { val: 0 };
+        let bits = unsafe { &mut src.__bindgen_anon_1 };
+        bits.set_mem_lvl(MemLevel::HIT.bits());
+        bits.set_mem_lvl_num(MemLevelNum::L3.0 as _);
+        bits.set_mem_remote(1);
+        assert_eq!(DataSource(src).to_string(), "remote L3 hit");
+
+        assert_eq!(DataSource::default().to_string(), "N/A");
+    }
+
     #[test]
     fn simple_parse_sample() {
         #[rustfmt::skip]
@@ -802,9 +2106,155 @@ mod tests {
         assert_eq!(sample.time(), Some(0xA43AA18E77C9));
         assert_eq!(sample.cpu(), Some(0));
 
-        let group = sample.values().unwrap();
+        let group = sample.values().unwrap().as_group().unwrap();
         assert_eq!(group.len(), 2);
 
         assert_eq!(sample.cgroup(), Some(1));
     }
+
+    #[test]
+    fn parse_sample_with_overflowing_callchain() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0xEF, 0xBE, 0xAD, 0xDE, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let config: ParseConfig<Little> =
+            ParseConfig::default().with_sample_type(SampleFlags::CALLCHAIN);
+        let error = Parser::new(data, config).parse::<Sample>().unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+    }
+
+    #[test]
+    fn parse_sample_with_lengths_overflowing_in_bytes() {
+        // These lengths fit within a usize but the size of the elements, in
+        // bytes, does not.
+        #[rustfmt::skip]
+        let callchain: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20,
+            0xEF, 0xBE, 0xAD, 0xDE, 0x00, 0x00, 0x00, 0x00,
+        ];
+        #[rustfmt::skip]
+        let lbr: &[u8] = &[
+            0xAB, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0x0A,
+            0xEF, 0xBE, 0xAD, 0xDE, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        for (data, sample_type) in [
+            (callchain, SampleFlags::CALLCHAIN),
+            (lbr, SampleFlags::BRANCH_STACK),
+        ] {
+            let config: ParseConfig<Little> = ParseConfig::default().with_sample_type(sample_type);
+            let error = Parser::new(data, config).parse::<Sample>().unwrap_err();
+
+            assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+            assert!(error
+                .to_string()
+                .contains("array length in bytes larger than usize::MAX"));
+        }
+    }
+
+    #[test]
+    fn parse_sample_with_branch_counters() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // nr
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // lbr[0].from
+            0x00, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // lbr[0].to
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // lbr[0].flags
+            0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // counters[0]
+            0x64, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // weight
+        ];
+
+        let config: ParseConfig<Little> = ParseConfig::default()
+            .with_sample_type(SampleFlags::BRANCH_STACK | SampleFlags::WEIGHT)
+            .with_branch_counters(true);
+        let sample: Sample = Parser::new(data, config).parse().unwrap();
+
+        assert_eq!(sample.lbr().map(|lbr| lbr.len()), Some(1));
+        assert_eq!(sample.branch_counters(), Some(&[5][..]));
+        assert_eq!(sample.weight(), Some(100));
+
+        let stack = sample.branch_stack().unwrap();
+        assert_eq!(stack.len(), 1);
+        assert_eq!(stack.hw_index, None);
+
+        let entries: Vec<_> = stack
+            .iter()
+            .map(|(entry, counter)| (entry.from_to(), counter))
+            .collect();
+        assert_eq!(entries, [((0x1000, 0x2000), Some(5))]);
+    }
+
+    #[test]
+    fn branch_entry_new() {
+        let entry = BranchEntry::new(0x1000, 0x2000, 0b0101);
+        assert_eq!(entry.from_to(), (0x1000, 0x2000));
+        assert!(entry.mispred());
+        assert!(!entry.predicted());
+        assert!(entry.in_tx());
+        assert!(!entry.abort());
+    }
+
+    #[test]
+    fn parse_sample_with_group_read_and_id() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x05, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, // pid, tid
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // nr
+            0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[0].value
+            0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[0].id
+            0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[1].value
+            0x21, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // values[1].id
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // callchain nr
+            0xEF, 0xBE, 0xAD, 0xDE, 0x00, 0x00, 0x00, 0x00, // callchain[0]
+        ];
+
+        let config: ParseConfig<Little> = ParseConfig::default()
+            .with_sample_type(SampleFlags::TID | SampleFlags::READ | SampleFlags::CALLCHAIN)
+            .with_read_format(ReadFormat::GROUP | ReadFormat::ID);
+        let sample: Sample = Parser::new(data, config).parse().unwrap();
+
+        assert_eq!(sample.pid(), Some(5));
+        assert_eq!(sample.tid(), Some(6));
+
+        let group = sample.values().unwrap().as_group().unwrap();
+        assert_eq!(group.len(), 2);
+        assert_eq!(group.get(0).unwrap().value(), 0x10);
+        assert_eq!(group.get(0).unwrap().id(), Some(0x11));
+        assert_eq!(group.get(1).unwrap().value(), 0x20);
+        assert_eq!(group.get(1).unwrap().id(), Some(0x21));
+
+        assert_eq!(sample.callchain(), Some(&[0xDEADBEEF][..]));
+    }
+
+    #[test]
+    fn parse_sample_with_weight_struct() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x10, 0x00, 0x00, 0x00, 0x20, 0x00, 0x30, 0x00,
+        ];
+
+        let config: ParseConfig<Little> =
+            ParseConfig::default().with_sample_type(SampleFlags::WEIGHT_STRUCT);
+        let sample: Sample = Parser::new(data, config).parse().unwrap();
+
+        let weight = sample.weight_struct().unwrap();
+        assert_eq!(sample.weight(), None);
+        assert_eq!(weight.total_latency(), 0x10);
+        assert_eq!(weight.issue_latency(), 0x20);
+        assert_eq!(weight.pipeline_stage_cycles(), 0x30);
+        assert_eq!(weight.full(), 0x0030_0020_0000_0010);
+    }
+
+    #[test]
+    fn enum_is_known() {
+        assert!(BranchType::CALL.is_known());
+        assert!(!BranchType::new(0xFF).is_known());
+        assert!(MemLevelNum::L1.is_known());
+        assert!(SampleRegsAbi::ABI_64.is_known());
+        assert!(!SampleRegsAbi::new(u64::MAX).is_known());
+    }
 }
@@ -0,0 +1,69 @@
+use perf_event_open_sys::bindings::{
+    PERF_RECORD_MISC_SWITCH_OUT, PERF_RECORD_MISC_SWITCH_OUT_PREEMPT,
+};
+
+use crate::prelude::*;
+
+/// SWITCH records indicate that a context switch has occurred.
+///
+/// These records have no body. Everything they carry is stored in the misc
+/// flags of the record header. See [`SwitchCpuWide`](crate::SwitchCpuWide)
+/// for the version of this record that is emitted in cpu-wide mode.
+///
+/// This struct corresponds to `PERF_RECORD_SWITCH`. See the [manpage] for
+/// more documentation.
+///
+/// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Switch {
+    /// Whether this was a context switch away from the current process. If
+    /// not, then it was a context switch into the current process.
+    pub out: bool,
+
+    /// Whether the current process was preempted, as opposed to having
+    /// blocked or yielded.
+    ///
+    /// This is only ever set when `out` is set.
+    pub preempt: bool,
+}
+
+impl<'p> Parse<'p> for Switch {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        let misc = p.config().misc();
+
+        Ok(Self {
+            out: misc & PERF_RECORD_MISC_SWITCH_OUT as u16 != 0,
+            preempt: misc & PERF_RECORD_MISC_SWITCH_OUT_PREEMPT as u16 != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+    use crate::Record;
+
+    #[test]
+    fn parse_switch_out_preempt() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x0E, 0x00, 0x00, 0x00, 0x00, 0x60, 0x08, 0x00,
+        ];
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        let record: Record = parser.parse().unwrap();
+
+        match record {
+            Record::Switch(switch) => {
+                assert!(switch.out);
+                assert!(switch.preempt);
+            }
+            _ => panic!("expected a SWITCH record, got {record:?}"),
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use std::borrow::Cow;
 use std::fmt;
 
+use crate::error::ParseError;
 use crate::prelude::*;
+use crate::util::cow::CowSliceExt;
 
 /// TEXT_POKE records indicate a change in the kernel text.
 ///
@@ -9,7 +11,7 @@ use crate::prelude::*;
 /// more documentation.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct TextPoke<'a> {
     /// The address of the change.
     pub addr: u64,
@@ -23,13 +25,26 @@ pub struct TextPoke<'a> {
 
 impl<'a> TextPoke<'a> {
     /// Convert all the borrowed data in this `TextPoke` into owned data.
-    pub fn to_owned(self) -> TextPoke<'static> {
+    pub fn into_owned(self) -> TextPoke<'static> {
         TextPoke {
             old_bytes: self.old_bytes.into_owned().into(),
             new_bytes: self.new_bytes.into_owned().into(),
             ..self
         }
     }
+
+    /// Whether both the old and new bytes in this `TextPoke` are borrowed
+    /// from the buffer it was parsed from.
+    pub fn is_borrowed(&self) -> bool {
+        self.old_bytes.is_borrowed() && self.new_bytes.is_borrowed()
+    }
+
+    /// Convert all the borrowed data in this `TextPoke` into owned data.
+    ///
+    /// This is the same as [`into_owned`](TextPoke::into_owned).
+    pub fn to_owned(self) -> TextPoke<'static> {
+        self.into_owned()
+    }
 }
 
 impl<'p> Parse<'p> for TextPoke<'p> {
@@ -38,12 +53,25 @@ impl<'p> Parse<'p> for TextPoke<'p> {
         E: Endian,
         B: ParseBuf<'p>,
     {
-        use crate::util::cow::CowSliceExt;
-
         let addr = p.parse()?;
         let old_len = p.parse_u16()? as usize;
         let new_len = p.parse_u16()? as usize;
 
+        // When parsed as part of a record the parser is limited to the bytes of
+        // that record so we can never read into the next one. We still check the
+        // lengths here so that a malformed record gets a more useful error.
+        if let Some(remaining) = p.remaining_hint() {
+            if old_len + new_len > remaining {
+                return Err(ParseError::custom(
+                    ErrorKind::InvalidRecord,
+                    format_args!(
+                        "text_poke record has {old_len} old bytes and {new_len} new bytes \
+                         but only {remaining} bytes remain in the record"
+                    ),
+                ));
+            }
+        }
+
         // The records emitted by perf_event_open always have a length that is a
         // multiple of 8. Strictly speaking, we don't have to do this since this is the
         // end of the record and higher levels should avoid this being a problem, but
@@ -82,3 +110,31 @@ fn round_up_mod(v: usize, k: usize, m: usize) -> usize {
         _ => unreachable!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+    use crate::Record;
+
+    #[test]
+    fn lengths_exceed_record() {
+        #[rustfmt::skip]
+        let data: &[u8] = &[
+            0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x00, // header (TEXT_POKE)
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // addr
+            0x04, 0x00, 0x08, 0x00, 0x01, 0x02, 0x03, 0x04, // old_len, new_len, bytes
+            // The following record should not be consumed.
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18, 0x00, // header (LOST)
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // id
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // lost
+        ];
+
+        let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+        let error = parser.parse::<Record>().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+
+        let record: Record = parser.parse().unwrap();
+        assert!(matches!(record, Record::Lost(_)));
+    }
+}
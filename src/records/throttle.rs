@@ -11,7 +11,7 @@ use crate::prelude::*;
 /// `PERF_RECORD_UNTHROTTLE`. See the [manpage] for more documentation.
 ///
 /// [manpage]: http://man7.org/linux/man-pages/man2/perf_event_open.2.html
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 #[allow(missing_docs)]
 pub struct Throttle {
     pub time: u64,
@@ -0,0 +1,194 @@
+use crate::prelude::*;
+
+/// TIME_CONV records contain the parameters needed to convert hardware
+/// timestamps (e.g. TSC values) into perf timestamps.
+///
+/// Like [`AuxTrace`](crate::AuxTrace), these are not emitted by the kernel.
+/// They are written by the `perf` tool using the values from the
+/// `perf_event_mmap_page` of the traced events. Older versions of `perf` only
+/// emit the first three fields. In that case the remaining fields are
+/// populated with values that result in the same conversion that those
+/// versions of `perf` would have performed.
+///
+/// This struct corresponds to `PERF_RECORD_TIME_CONV`. See the [perf source]
+/// for more documentation.
+///
+/// [perf source]: https://sourcegraph.com/github.com/torvalds/linux@eb7081409f94a9a8608593d0fb63a1aa3d6f95d8/-/blob/tools/lib/perf/include/perf/event.h?L396
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TimeConv {
+    /// The shift applied when converting cycles to time.
+    pub time_shift: u64,
+
+    /// The multiplier applied when converting cycles to time.
+    pub time_mult: u64,
+
+    /// The offset added to the converted time.
+    pub time_zero: u64,
+
+    /// The cycle count which truncated cycle values are relative to.
+    ///
+    /// Only used if `cap_user_time_short` is set.
+    pub time_cycles: u64,
+
+    /// The mask indicating how many bits of the cycle counter are valid.
+    ///
+    /// Only used if `cap_user_time_short` is set.
+    pub time_mask: u64,
+
+    /// Whether `time_zero` is valid.
+    pub cap_user_time_zero: bool,
+
+    /// Whether the hardware counter is narrower than 64 bits and so needs to
+    /// be extended using `time_cycles` and `time_mask` before conversion.
+    pub cap_user_time_short: bool,
+}
+
+impl TimeConv {
+    /// The record type used for TIME_CONV records within `perf.data` files.
+    pub const RECORD_TYPE: u32 = 79;
+
+    /// Convert a raw hardware cycle count into a perf timestamp.
+    ///
+    /// This performs the same computation as `tsc_to_perf_time` within the
+    /// `perf` tool. If `cap_user_time_short` is set then `cyc` is first
+    /// extended relative to `time_cycles` before being converted.
+    pub fn tsc_to_perf_time(&self, cyc: u64) -> u64 {
+        let mut cyc = cyc;
+        if self.cap_user_time_short {
+            cyc = self
+                .time_cycles
+                .wrapping_add(cyc.wrapping_sub(self.time_cycles) & self.time_mask);
+        }
+
+        let shift = self.time_shift as u32;
+        let quot = cyc.checked_shr(shift).unwrap_or(0);
+        let rem = cyc & 1u64.checked_shl(shift).unwrap_or(0).wrapping_sub(1);
+
+        self.time_zero
+            .wrapping_add(quot.wrapping_mul(self.time_mult))
+            .wrapping_add(
+                rem.wrapping_mul(self.time_mult)
+                    .checked_shr(shift)
+                    .unwrap_or(0),
+            )
+    }
+}
+
+impl<'p> Parse<'p> for TimeConv {
+    fn parse<B, E>(p: &mut Parser<B, E>) -> ParseResult<Self>
+    where
+        E: Endian,
+        B: ParseBuf<'p>,
+    {
+        let time_shift = p.parse()?;
+        let time_mult = p.parse()?;
+        let time_zero = p.parse()?;
+
+        // Older versions of perf only emit the first three fields.
+        if p.is_at_end()? {
+            return Ok(Self {
+                time_shift,
+                time_mult,
+                time_zero,
+                time_cycles: 0,
+                time_mask: 0,
+                cap_user_time_zero: true,
+                cap_user_time_short: false,
+            });
+        }
+
+        let time_cycles = p.parse()?;
+        let time_mask = p.parse()?;
+        let cap_user_time_zero = p.parse_u8()? != 0;
+        let cap_user_time_short = p.parse_u8()? != 0;
+
+        // reserved bytes for alignment
+        let _ = p.parse_array::<6>()?;
+
+        Ok(Self {
+            time_shift,
+            time_mult,
+            time_zero,
+            time_cycles,
+            time_mask,
+            cap_user_time_zero,
+            cap_user_time_short,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    fn conv() -> TimeConv {
+        TimeConv {
+            time_shift: 10,
+            time_mult: 1000,
+            time_zero: 5000,
+            time_cycles: 0,
+            time_mask: 0,
+            cap_user_time_zero: true,
+            cap_user_time_short: false,
+        }
+    }
+
+    #[test]
+    fn parse_old_layout() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x0A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0xE8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x88, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut parser: Parser<_, Little> = Parser::new(bytes, ParseConfig::default());
+        let record: TimeConv = parser.parse().unwrap();
+
+        assert_eq!(record, conv());
+    }
+
+    #[test]
+    fn parse_extended_layout() {
+        #[rustfmt::skip]
+        let bytes: &[u8] = &[
+            0x0A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0xE8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x88, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00,
+            0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let mut parser: Parser<_, Little> = Parser::new(bytes, ParseConfig::default());
+        let record: TimeConv = parser.parse().unwrap();
+
+        assert_eq!(record.time_cycles, 0x1000);
+        assert_eq!(record.time_mask, 0xFFFFFFFF);
+        assert!(record.cap_user_time_zero);
+        assert!(record.cap_user_time_short);
+    }
+
+    #[test]
+    fn convert_full() {
+        assert_eq!(conv().tsc_to_perf_time(123456789), 120568270);
+        assert_eq!(conv().tsc_to_perf_time(0x1_0000_2000), 4194317000);
+    }
+
+    #[test]
+    fn convert_short() {
+        let short = TimeConv {
+            time_cycles: 0x1000,
+            time_mask: 0xFFFFFFFF,
+            cap_user_time_short: true,
+            ..conv()
+        };
+
+        assert_eq!(short.tsc_to_perf_time(0x1_0000_2000), 13000);
+        assert_eq!(
+            short.tsc_to_perf_time(0x2000),
+            conv().tsc_to_perf_time(0x2000)
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::prelude::*;
+use crate::SampleRegsAbi;
+
+c_enum! {
+    /// A register within the x86 register set recorded in [`Registers`].
+    ///
+    /// These correspond to the `PERF_REG_X86_x` values within the kernel's
+    /// [`perf_regs.h`]. The same register indices are used for both 32-bit
+    /// and 64-bit programs, but `R8` through `R15` do not exist in the 32-bit
+    /// register set and only the low 32 bits of the other registers are
+    /// meaningful there. [`Registers::x86`] takes care of both of these using
+    /// the [`abi`] of the sample.
+    ///
+    /// [`Registers`]: crate::Registers
+    /// [`Registers::x86`]: crate::Registers::x86
+    /// [`abi`]: crate::Registers::abi
+    /// [`perf_regs.h`]: https://sourcegraph.com/github.com/torvalds/linux@eb7081409f94a9a8608593d0fb63a1aa3d6f95d8/-/blob/arch/x86/include/uapi/asm/perf_regs.h
+    #[derive(Copy, Clone, Eq, PartialEq, Hash)]
+    pub enum X86Reg : u8 {
+        AX = 0,
+        BX = 1,
+        CX = 2,
+        DX = 3,
+        SI = 4,
+        DI = 5,
+        BP = 6,
+        SP = 7,
+        IP = 8,
+        FLAGS = 9,
+        CS = 10,
+        SS = 11,
+        DS = 12,
+        ES = 13,
+        FS = 14,
+        GS = 15,
+        R8 = 16,
+        R9 = 17,
+        R10 = 18,
+        R11 = 19,
+        R12 = 20,
+        R13 = 21,
+        R14 = 22,
+        R15 = 23,
+    }
+}
+
+impl X86Reg {
+    /// Create a new `X86Reg`.
+    pub const fn new(value: u8) -> Self {
+        Self(value)
+    }
+
+    /// Whether this value is one of the variants known to this crate.
+    pub const fn is_known(&self) -> bool {
+        self.0 <= Self::R15.0
+    }
+
+    /// Whether this register is part of the register set for `abi`.
+    ///
+    /// `R8` through `R15` are only present for [`ABI_64`]. No registers are
+    /// present for [`NONE`] or for ABIs that are not known to this crate.
+    ///
+    /// [`ABI_64`]: SampleRegsAbi::ABI_64
+    /// [`NONE`]: SampleRegsAbi::NONE
+    pub const fn is_in_abi(&self, abi: SampleRegsAbi) -> bool {
+        match abi {
+            SampleRegsAbi::ABI_32 => self.0 <= Self::GS.0,
+            SampleRegsAbi::ABI_64 => self.is_known(),
+            _ => false,
+        }
+    }
+
+    /// The name of this register within the register set for `abi`.
+    ///
+    /// This uses the width-specific name of the register, e.g. `eax` for
+    /// [`ABI_32`] and `rax` for [`ABI_64`]. Returns `None` if the register is
+    /// not part of the register set for `abi`.
+    ///
+    /// [`ABI_32`]: SampleRegsAbi::ABI_32
+    /// [`ABI_64`]: SampleRegsAbi::ABI_64
+    pub const fn name(&self, abi: SampleRegsAbi) -> Option<&'static str> {
+        const NAMES_32: [&str; 16] = [
+            "eax", "ebx", "ecx", "edx", "esi", "edi", "ebp", "esp", "eip", "eflags", "cs", "ss",
+            "ds", "es", "fs", "gs",
+        ];
+        const NAMES_64: [&str; 24] = [
+            "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "rip", "rflags", "cs", "ss",
+            "ds", "es", "fs", "gs", "r8", "r9", "r10", "r11", "r12", "r13", "r14", "r15",
+        ];
+
+        if !self.is_in_abi(abi) {
+            return None;
+        }
+
+        Some(match abi {
+            SampleRegsAbi::ABI_32 => NAMES_32[self.0 as usize],
+            _ => NAMES_64[self.0 as usize],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_names() {
+        assert_eq!(X86Reg::AX.name(SampleRegsAbi::ABI_32), Some("eax"));
+        assert_eq!(X86Reg::AX.name(SampleRegsAbi::ABI_64), Some("rax"));
+        assert_eq!(X86Reg::R8.name(SampleRegsAbi::ABI_32), None);
+        assert_eq!(X86Reg::R15.name(SampleRegsAbi::ABI_64), Some("r15"));
+        assert_eq!(X86Reg::IP.name(SampleRegsAbi::NONE), None);
+        assert_eq!(X86Reg::new(24).name(SampleRegsAbi::ABI_64), None);
+    }
+}
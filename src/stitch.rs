@@ -0,0 +1,487 @@
+use std::fmt;
+use std::mem;
+
+use perf_event_open_sys::bindings::perf_event_header;
+
+use crate::endian::Endian;
+use crate::parse::{ErrorKind, ParseConfig, ParseError, ParseResult, Parser};
+use crate::{Record, Visitor};
+
+/// Reassembles whole records out of a sequence of byte chunks.
+///
+/// Some sources of records do not guarantee that a record will be contained
+/// entirely within a single chunk of data. The main example of this is the
+/// output of decompressing [`Compressed`] records: the `perf` tool compresses
+/// records as one continuous zstd stream so a single record may straddle two
+/// [`Compressed`] records.
+///
+/// `RecordStitcher` buffers the data passed to [`push`] and hands out each
+/// record (header included) once all of its bytes are available. The
+/// returned slices can then be parsed with a [`Parser`].
+///
+/// Decompression itself is handled by `CompressedDecoder`, which uses a
+/// `RecordStitcher` internally, when the `zstd` feature is enabled. Without
+/// it, decompression is the caller's job and its output can be stitched back
+/// together as below.
+///
+/// # Example
+/// Decompressing the records within a `perf.data` file. Note that the
+/// decompressor must be kept around across [`Compressed`] records.
+/// ```ignore
+/// let mut decoder = zstd::stream::raw::Decoder::new()?;
+/// let mut stitcher = RecordStitcher::new(Little);
+///
+/// for compressed in compressed_records {
+///     let output = decompress_all(&mut decoder, &compressed.data)?;
+///     stitcher.push(&output);
+///
+///     while let Some(bytes) = stitcher.next_record()? {
+///         let record: Record = Parser::new(bytes, config.clone()).parse()?;
+///         // ...
+///     }
+/// }
+/// ```
+///
+/// # Pipe mode
+/// When `perf record -o - -z` writes to a pipe there is no file header and the
+/// records arrive in arbitrarily sized reads. [`PipeReader`] handles this,
+/// including decompressing [`Compressed`] records when the `zstd` feature is
+/// enabled.
+///
+/// [`Compressed`]: crate::Compressed
+/// [`PipeReader`]: crate::parse::PipeReader
+/// [`Parser`]: crate::parse::Parser
+/// [`push`]: RecordStitcher::push
+#[derive(Clone, Debug, Default)]
+pub struct RecordStitcher<E> {
+    endian: E,
+    buffer: Vec<u8>,
+    offset: usize,
+}
+
+impl<E: Endian> RecordStitcher<E> {
+    /// Create a new, empty, `RecordStitcher`.
+    pub fn new(endian: E) -> Self {
+        Self {
+            endian,
+            buffer: Vec::new(),
+            offset: 0,
+        }
+    }
+
+    /// Append a chunk of data to the end of the stream.
+    pub fn push(&mut self, data: &[u8]) {
+        // Drop the records that have already been handed out so the buffer
+        // does not grow without bound.
+        if self.offset != 0 {
+            self.buffer.drain(..self.offset);
+            self.offset = 0;
+        }
+
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Get the bytes of the next complete record, if there is one.
+    ///
+    /// Returns `Ok(None)` if more data is needed before the next record is
+    /// complete.
+    pub fn next_record(&mut self) -> ParseResult<Option<&[u8]>> {
+        let size = match self.peek_header()? {
+            Some((_, size)) => size,
+            None => return Ok(None),
+        };
+
+        let start = self.offset;
+        self.offset += size;
+        Ok(Some(&self.buffer[start..self.offset]))
+    }
+
+    /// Get the type and size of the next record without consuming it.
+    ///
+    /// Returns `Ok(None)` if more data is needed before the next record is
+    /// complete.
+    pub(crate) fn peek_header(&self) -> ParseResult<Option<(u32, usize)>> {
+        let rest = self.peek();
+        let header_len = mem::size_of::<perf_event_header>();
+
+        if rest.len() < header_len {
+            return Ok(None);
+        }
+
+        let ty = self
+            .endian
+            .convert_u32([rest[0], rest[1], rest[2], rest[3]]);
+        let size = self.endian.convert_u16([rest[6], rest[7]]) as usize;
+        if size < header_len {
+            return Err(ParseError::custom(
+                ErrorKind::InvalidRecord,
+                "header size was too small to be valid",
+            ));
+        }
+
+        if rest.len() < size {
+            return Ok(None);
+        }
+
+        Ok(Some((ty, size)))
+    }
+
+    /// The bytes that have been pushed but not yet consumed.
+    pub(crate) fn peek(&self) -> &[u8] {
+        &self.buffer[self.offset..]
+    }
+
+    /// Drop up to `len` pending bytes without treating them as a record.
+    ///
+    /// Returns the number of bytes that were dropped.
+    pub(crate) fn consume(&mut self, len: usize) -> usize {
+        let len = len.min(self.pending());
+        self.offset += len;
+        len
+    }
+
+    /// The number of bytes that have been pushed but not yet returned as part
+    /// of a record.
+    pub fn pending(&self) -> usize {
+        self.buffer.len() - self.offset
+    }
+
+    /// Whether there is no pending data left in this `RecordStitcher`.
+    pub fn is_empty(&self) -> bool {
+        self.pending() == 0
+    }
+}
+
+/// Decompresses the payloads of [`Compressed`] records back into records.
+///
+/// The `perf` tool compresses records as one continuous zstd stream, so the
+/// decompressor state has to be kept across [`Compressed`] records and a
+/// single record may be split across two of them. `CompressedDecoder` keeps a
+/// persistent zstd decompression stream and feeds its output through a
+/// [`RecordStitcher`] so that [`next_record`] only ever returns whole
+/// records.
+///
+/// This requires the `zstd` feature.
+///
+/// # Example
+/// ```ignore
+/// let mut decoder = CompressedDecoder::new(Little)?;
+///
+/// for compressed in compressed_records {
+///     decoder.push(&compressed)?;
+///
+///     while let Some(bytes) = decoder.next_record()? {
+///         let record: Record = Parser::new(bytes, config.clone()).parse()?;
+///         // ...
+///     }
+/// }
+/// ```
+///
+/// [`Compressed`]: crate::Compressed
+/// [`next_record`]: CompressedDecoder::next_record
+#[cfg(feature = "zstd")]
+pub struct CompressedDecoder<E> {
+    decoder: zstd::stream::raw::Decoder<'static>,
+    stitcher: RecordStitcher<E>,
+    buffer: Vec<u8>,
+}
+
+#[cfg(feature = "zstd")]
+impl<E: Endian> CompressedDecoder<E> {
+    /// The size of the buffer that decompressed data is written into before
+    /// being handed to the stitcher.
+    const BUFFER_LEN: usize = 64 * 1024;
+
+    /// Create a new `CompressedDecoder`.
+    pub fn new(endian: E) -> ParseResult<Self> {
+        Ok(Self {
+            decoder: zstd::stream::raw::Decoder::new()?,
+            stitcher: RecordStitcher::new(endian),
+            buffer: vec![0; Self::BUFFER_LEN],
+        })
+    }
+
+    /// Decompress the payload of a [`Compressed`](crate::Compressed) record.
+    ///
+    /// The records within it can then be retrieved with
+    /// [`next_record`](Self::next_record).
+    pub fn push(&mut self, record: &crate::Compressed<'_>) -> ParseResult<()> {
+        self.push_bytes(&record.data)
+    }
+
+    /// Decompress a chunk of raw zstd data.
+    pub fn push_bytes(&mut self, data: &[u8]) -> ParseResult<()> {
+        use zstd::stream::raw::{InBuffer, Operation, OutBuffer};
+
+        let mut input = InBuffer::around(data);
+        loop {
+            let mut output = OutBuffer::around(&mut self.buffer[..]);
+            self.decoder.run(&mut input, &mut output)?;
+            let written = output.pos();
+            self.stitcher.push(&self.buffer[..written]);
+
+            // A full output buffer means the decoder may still have more
+            // output pending even if all the input has been consumed.
+            if input.pos() == data.len() && written < self.buffer.len() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Get the bytes of the next complete decompressed record, if there is
+    /// one.
+    ///
+    /// Returns `Ok(None)` if more data is needed before the next record is
+    /// complete.
+    pub fn next_record(&mut self) -> ParseResult<Option<&[u8]>> {
+        self.stitcher.next_record()
+    }
+
+    /// Get the type and size of the next decompressed record without
+    /// consuming it.
+    pub(crate) fn peek_header(&self) -> ParseResult<Option<(u32, usize)>> {
+        self.stitcher.peek_header()
+    }
+
+    /// The number of decompressed bytes that have not yet been returned as
+    /// part of a record.
+    pub fn pending(&self) -> usize {
+        self.stitcher.pending()
+    }
+
+    /// Whether there is no pending decompressed data left.
+    pub fn is_empty(&self) -> bool {
+        self.stitcher.is_empty()
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<E: fmt::Debug> fmt::Debug for CompressedDecoder<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompressedDecoder")
+            .field("stitcher", &self.stitcher)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A stateful parser for records that arrive in arbitrarily sized chunks.
+///
+/// This is the streaming counterpart to [`Parser`]. When reading from a live
+/// ring buffer the data available at any one time may end partway through a
+/// record. Instead of failing with an [`Eof`](ErrorKind::Eof) error,
+/// `RecordStream` keeps the partial record around and returns `Ok(None)`
+/// until enough data has been [`feed`](RecordStream::feed)ed to complete it.
+///
+/// # Example
+/// ```
+/// # use perf_event_data::endian::Little;
+/// # use perf_event_data::parse::{ParseConfig, RecordStream};
+/// # let data = perf_event_data::doctest::MMAP;
+/// let mut stream = RecordStream::new(ParseConfig::<Little>::default());
+///
+/// let (first, second) = data.split_at(20);
+/// stream.feed(first);
+/// assert!(stream.next_record()?.is_none());
+///
+/// stream.feed(second);
+/// let record = stream.next_record()?.expect("record should be complete");
+/// # Ok::<_, perf_event_data::parse::ParseError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct RecordStream<E> {
+    config: ParseConfig<E>,
+    stitcher: RecordStitcher<E>,
+}
+
+impl<E: Endian> RecordStream<E> {
+    /// Create a new, empty, `RecordStream`.
+    pub fn new(config: ParseConfig<E>) -> Self {
+        Self {
+            stitcher: RecordStitcher::new(config.endian().clone()),
+            config,
+        }
+    }
+
+    /// Get the [`ParseConfig`] used by this `RecordStream`.
+    pub fn config(&self) -> &ParseConfig<E> {
+        &self.config
+    }
+
+    /// Add more data to the end of the stream.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.stitcher.push(data);
+    }
+
+    /// Parse the next record in the stream.
+    ///
+    /// Returns `Ok(None)` if the next record has not been fully fed in yet.
+    pub fn next_record(&mut self) -> ParseResult<Option<Record<'_>>> {
+        self.next_record_with(crate::records::RecordVisitor)
+    }
+
+    /// Parse the next record in the stream using a custom [`Visitor`].
+    ///
+    /// Returns `Ok(None)` if the next record has not been fully fed in yet.
+    pub fn next_record_with<'a, V>(&'a mut self, visitor: V) -> ParseResult<Option<V::Output>>
+    where
+        V: Visitor<'a>,
+    {
+        let config = self.config.clone();
+        let bytes = match self.stitcher.next_record()? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+
+        Parser::new(bytes, config).parse_record(visitor).map(Some)
+    }
+
+    /// The number of bytes that have been fed in but not yet parsed.
+    pub fn pending(&self) -> usize {
+        self.stitcher.pending()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endian::Little;
+
+    #[test]
+    fn record_split_across_frames() {
+        let data = crate::doctest::MMAP;
+        let (first, second) = data.split_at(20);
+
+        let mut stitcher = RecordStitcher::new(Little);
+        stitcher.push(first);
+        assert!(stitcher.next_record().unwrap().is_none());
+
+        stitcher.push(second);
+        let bytes = stitcher.next_record().unwrap().unwrap();
+        assert_eq!(bytes, data);
+
+        let record: Record = Parser::new(bytes, ParseConfig::<Little>::default())
+            .parse()
+            .unwrap();
+        assert!(matches!(record, Record::Mmap(_)));
+
+        assert!(stitcher.next_record().unwrap().is_none());
+        assert!(stitcher.is_empty());
+    }
+
+    #[test]
+    fn stream_byte_at_a_time() {
+        let data = crate::doctest::MMAP;
+        let mut stream = RecordStream::new(ParseConfig::<Little>::default());
+
+        let (last, rest) = data.split_last().unwrap();
+        for byte in rest {
+            stream.feed(std::slice::from_ref(byte));
+            assert!(stream.next_record().unwrap().is_none());
+        }
+
+        stream.feed(std::slice::from_ref(last));
+        let record = stream.next_record().unwrap().unwrap();
+        assert!(matches!(record, Record::Mmap(_)));
+        assert_eq!(stream.pending(), 0);
+    }
+
+    /// A single zstd frame containing four copies of [`crate::doctest::MMAP`].
+    #[cfg(feature = "zstd")]
+    #[rustfmt::skip]
+    const MMAP_X4_ZSTD: &[u8] = &[
+        0x28, 0xb5, 0x2f, 0xfd, 0x20, 0xc0, 0x75, 0x01, 0x00, 0x72, 0x02, 0x08,
+        0x10, 0xc0, 0x6b, 0x0d, 0xaf, 0xaf, 0xff, 0x54, 0x77, 0x59, 0x7f, 0xf3,
+        0x2e, 0x70, 0x60, 0x64, 0x0a, 0x27, 0x95, 0xd0, 0x18, 0x1f, 0x7c, 0x1d,
+        0x56, 0xda, 0x67, 0xc4, 0x33, 0x21, 0xf9, 0x17, 0x03, 0x00, 0x9d, 0xea,
+        0x04, 0x35, 0xd5, 0xe4, 0x4f, 0x31, 0x9d,
+    ];
+
+    /// Two zstd frames containing the first 20 bytes and the rest of
+    /// [`crate::doctest::MMAP`] respectively.
+    #[cfg(feature = "zstd")]
+    #[rustfmt::skip]
+    const MMAP_SPLIT_ZSTD: [&[u8]; 2] = [
+        &[
+            0x28, 0xb5, 0x2f, 0xfd, 0x20, 0x14, 0xa1, 0x00, 0x00, 0x01, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x30, 0x00, 0x16, 0x4c, 0x01, 0x00, 0x17, 0x4c, 0x01,
+            0x00, 0x00, 0xa0, 0x48, 0x96,
+        ],
+        &[
+            0x28, 0xb5, 0x2f, 0xfd, 0x20, 0x1c, 0xe1, 0x00, 0x00, 0x4f, 0x7f, 0x00,
+            0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xa0, 0x48,
+            0x96, 0x4f, 0x7f, 0x00, 0x00, 0x2f, 0x2f, 0x61, 0x6e, 0x6f, 0x6e, 0x00,
+            0x00,
+        ],
+    ];
+
+    #[cfg(feature = "zstd")]
+    fn decode_all<E: Endian>(decoder: &mut CompressedDecoder<E>) -> Vec<Vec<u8>> {
+        let mut records = Vec::new();
+        while let Some(bytes) = decoder.next_record().unwrap() {
+            records.push(bytes.to_vec());
+        }
+        records
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn record_split_across_compressed_frames() {
+        use crate::Compressed;
+
+        let mut decoder = CompressedDecoder::new(Little).unwrap();
+
+        let first = Compressed {
+            data: MMAP_SPLIT_ZSTD[0].into(),
+        };
+        decoder.push(&first).unwrap();
+        assert!(decode_all(&mut decoder).is_empty());
+        assert_eq!(decoder.pending(), 20);
+
+        let second = Compressed {
+            data: MMAP_SPLIT_ZSTD[1].into(),
+        };
+        decoder.push(&second).unwrap();
+        assert_eq!(decode_all(&mut decoder), [crate::doctest::MMAP]);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn zstd_frame_split_across_records() {
+        let mut decoder = CompressedDecoder::new(Little).unwrap();
+        let mut records = Vec::new();
+
+        // Split the frame at an arbitrary point so that both the zstd stream
+        // and the records within it straddle the boundary.
+        for chunk in MMAP_X4_ZSTD.chunks(21) {
+            decoder.push_bytes(chunk).unwrap();
+            records.extend(decode_all(&mut decoder));
+        }
+
+        assert_eq!(records.len(), 4);
+        for bytes in records {
+            let record: Record = Parser::new(&bytes[..], ParseConfig::<Little>::default())
+                .parse()
+                .unwrap();
+            assert!(matches!(record, Record::Mmap(_)));
+        }
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn invalid_zstd_data() {
+        let mut decoder = CompressedDecoder::new(Little).unwrap();
+        let error = decoder.push_bytes(&[0xFF; 16]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::External);
+    }
+
+    #[test]
+    fn zero_size_header() {
+        let mut stitcher = RecordStitcher::new(Little);
+        stitcher.push(&[0; 8]);
+
+        let error = stitcher.next_record().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidRecord);
+    }
+}
@@ -11,6 +11,9 @@ pub(crate) trait CowSliceExt: Sized {
 
     /// Same as `Vec::truncate`.
     fn truncate(&mut self, len: usize);
+
+    /// Whether this cow borrows its data instead of owning it.
+    fn is_borrowed(&self) -> bool;
 }
 
 impl<'a, T> CowSliceExt for Cow<'a, [T]>
@@ -39,6 +42,10 @@ where
             Cow::Borrowed(slice) => *slice = slice.split_at(len).0,
         }
     }
+
+    fn is_borrowed(&self) -> bool {
+        matches!(self, Cow::Borrowed(_))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,56 @@
+//! Helpers for interpreting the filenames within MMAP and MMAP2 records.
+
+/// The suffix the kernel appends to the path of a file that has been
+/// unlinked.
+const DELETED_SUFFIX: &[u8] = b" (deleted)";
+
+/// Strip a trailing ` (deleted)` from `filename`, if present.
+pub(crate) fn clean(filename: &[u8]) -> &[u8] {
+    filename.strip_suffix(DELETED_SUFFIX).unwrap_or(filename)
+}
+
+/// Whether `filename` refers to anonymous memory.
+///
+/// This matches the names that the `perf` tool treats as anonymous memory.
+pub(crate) fn is_anonymous(filename: &[u8]) -> bool {
+    let filename = clean(filename);
+
+    filename == b"//anon"
+        || filename.starts_with(b"/dev/zero")
+        || filename.starts_with(b"/anon_hugepage")
+}
+
+/// Whether `filename` is a special kernel-provided name such as `[vdso]` or
+/// `[stack]`.
+pub(crate) fn is_special(filename: &[u8]) -> bool {
+    filename.len() >= 2 && filename.starts_with(b"[") && filename.ends_with(b"]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_strips_deleted() {
+        assert_eq!(clean(b"/tmp/a.so (deleted)"), b"/tmp/a.so");
+        assert_eq!(clean(b"/tmp/a.so"), b"/tmp/a.so");
+        assert_eq!(clean(b" (deleted)"), b"");
+    }
+
+    #[test]
+    fn anonymous_names() {
+        assert!(is_anonymous(b"//anon"));
+        assert!(is_anonymous(b"/dev/zero (deleted)"));
+        assert!(is_anonymous(b"/anon_hugepage (deleted)"));
+        assert!(!is_anonymous(b"/usr/lib/libc.so.6"));
+        assert!(!is_anonymous(b"[heap]"));
+    }
+
+    #[test]
+    fn special_names() {
+        assert!(is_special(b"[vdso]"));
+        assert!(is_special(b"[stack]"));
+        assert!(!is_special(b"["));
+        assert!(!is_special(b"/bin/[weird]name"));
+    }
+}
@@ -1,5 +1,39 @@
 use std::fmt::{self, UpperHex, Write};
 
+/// Write out the names of the flags set within `flags`, separated by ` | `.
+///
+/// Any bits that do not correspond to a known flag are written out as a
+/// trailing `UNKNOWN(0x..)` segment.
+pub(crate) fn write_flag_names<F>(flags: &F, f: &mut fmt::Formatter) -> fmt::Result
+where
+    F: bitflags::Flags<Bits = u64>,
+{
+    let unknown = flags.bits() & !F::all().bits();
+    let mut first = true;
+    let mut separator = |f: &mut fmt::Formatter| -> fmt::Result {
+        if !std::mem::take(&mut first) {
+            f.write_str(" | ")?;
+        }
+        Ok(())
+    };
+
+    for (name, _) in flags.iter_names() {
+        separator(f)?;
+        f.write_str(name)?;
+    }
+
+    if unknown != 0 {
+        separator(f)?;
+        write!(f, "UNKNOWN({unknown:#x})")?;
+    }
+
+    if first {
+        f.write_str("(empty)")?;
+    }
+
+    Ok(())
+}
+
 /// Write an array of bytes containing possibly invalid UTF-8 as if it was a
 /// debug string.
 ///
@@ -47,12 +81,12 @@ pub(crate) struct HexStr<'a>(pub &'a [u8]);
 impl fmt::Debug for HexStr<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for &b in self.0 {
-            let nibbles = [b & 0xF, b >> 4];
+            let nibbles = [b >> 4, b & 0xF];
 
             for n in nibbles {
                 let c = match n {
                     0x0..=0x9 => b'0' + n,
-                    0xA..=0xF => b'A' + n,
+                    0xA..=0xF => b'A' + (n - 0xA),
                     _ => unreachable!(),
                 };
 
@@ -1,4 +1,7 @@
 //! Various random non-public utilities used within the crate.
 
 pub(crate) mod cow;
+pub(crate) mod filename;
 pub(crate) mod fmt;
+#[cfg(test)]
+pub(crate) mod testdata;
@@ -0,0 +1,36 @@
+//! Record fixtures and builders shared between the tests of several modules.
+
+/// A `perf_event_attr` with `sample_type` set to `IP`.
+#[rustfmt::skip]
+pub(crate) const ATTR: &[u8] = &[
+    0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, // type, size
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // config
+    0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sample_period
+    0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sample_type (IP)
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // read_format
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // flags
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // wakeup_events, bp_type
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // config1
+];
+
+/// A SAMPLE record that can be parsed using [`ATTR`].
+#[rustfmt::skip]
+pub(crate) const SAMPLE: &[u8] = &[
+    0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // header (SAMPLE)
+    0x00, 0x10, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, // ip
+];
+
+/// Build a little-endian record of type `ty` with `body` as its body.
+pub(crate) fn record(ty: u32, body: &[u8]) -> Vec<u8> {
+    let mut record = Vec::new();
+    record.extend_from_slice(&ty.to_le_bytes());
+    record.extend_from_slice(&0u16.to_le_bytes());
+    record.extend_from_slice(&(8 + body.len() as u16).to_le_bytes());
+    record.extend_from_slice(body);
+    record
+}
+
+/// Encode `values` as consecutive little-endian `u64`s.
+pub(crate) fn u64s(values: &[u64]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
@@ -2,7 +2,10 @@ use std::borrow::Cow;
 
 use perf_event_open_sys::bindings;
 
-use crate::parse::Parser;
+use crate::endian::Endian;
+use crate::parse::{ParseResult, Parser};
+use crate::parsebuf::ParseBufCursor;
+use crate::records::RecordVisitor;
 use crate::*;
 
 used_in_docs!(Parser);
@@ -40,16 +43,70 @@ impl RecordMetadata {
     /// If `sample_id_all` was set when configuring the record then this will
     /// contain a subset of the fields configured to be sampled.
     ///
-    /// Note that, even if `sample_id_all` is set, MMAP and SAMPLE records will
-    /// always have an empty `SampleId`. If you want the `SampleId` fields
-    /// to be set then configure the kernel to generate MMAP2 records
-    /// instead.
+    /// Note that, even if `sample_id_all` is set, SAMPLE records will always
+    /// have an empty `SampleId`. MMAP records will too, unless
+    /// [`ParseConfig::with_mmap_sample_id`] was used. If you want the
+    /// `SampleId` fields to be set then configure the kernel to generate MMAP2
+    /// records instead.
+    ///
+    /// [`ParseConfig::with_mmap_sample_id`]: crate::parse::ParseConfig::with_mmap_sample_id
     #[inline]
     pub fn sample_id(&self) -> &SampleId {
         &self.sample_id
     }
 }
 
+/// Record metadata whose [`SampleId`] has not been parsed yet.
+///
+/// This is returned by [`Parser::parse_metadata_lazy`]. The record type and
+/// misc flags are available immediately but the `sample_id` trailer is only
+/// parsed if it is asked for. This avoids doing that work for records that
+/// end up being discarded.
+pub struct LazyRecordMetadata<'p, E> {
+    header: bindings::perf_event_header,
+    sample_id: Option<Parser<ParseBufCursor<'p>, E>>,
+}
+
+impl<'p, E: Endian> LazyRecordMetadata<'p, E> {
+    #[inline]
+    pub(crate) fn new(
+        header: bindings::perf_event_header,
+        sample_id: Option<Parser<ParseBufCursor<'p>, E>>,
+    ) -> Self {
+        Self { header, sample_id }
+    }
+
+    /// The type of this record, as emitted by the kernel.
+    #[inline]
+    pub fn ty(&self) -> u32 {
+        self.header.type_
+    }
+
+    /// Miscellaneous flags set by the kernel.
+    #[inline]
+    pub fn misc(&self) -> u16 {
+        self.header.misc
+    }
+
+    /// Parse the [`SampleId`] for this record.
+    ///
+    /// This will be empty for records that do not have a `sample_id` trailer.
+    /// See [`RecordMetadata::sample_id`] for details on which records those
+    /// are.
+    pub fn sample_id(self) -> ParseResult<SampleId> {
+        match self.sample_id {
+            Some(mut p) => p.parse(),
+            None => Ok(SampleId::default()),
+        }
+    }
+
+    /// Parse the [`SampleId`] and convert this into a full [`RecordMetadata`].
+    pub fn into_metadata(self) -> ParseResult<RecordMetadata> {
+        let header = self.header;
+        Ok(RecordMetadata::new(header, self.sample_id()?))
+    }
+}
+
 /// A visitor for visiting parsed records.
 ///
 /// This is used in combination with [`Parser::parse_record`] to parse the
@@ -122,7 +179,7 @@ pub trait Visitor<'a>: Sized {
     }
 
     /// Visit a [`Read`] record.
-    fn visit_read(self, record: Read, metadata: RecordMetadata) -> Self::Output {
+    fn visit_read(self, record: Read<'a>, metadata: RecordMetadata) -> Self::Output {
         self.visit_unimplemented(metadata)
     }
 
@@ -154,8 +211,8 @@ pub trait Visitor<'a>: Sized {
         self.visit_unimplemented(metadata)
     }
 
-    /// Visit a SWITCH record.
-    fn visit_switch(self, metadata: RecordMetadata) -> Self::Output {
+    /// Visit a [`Switch`] record.
+    fn visit_switch(self, record: Switch, metadata: RecordMetadata) -> Self::Output {
         self.visit_unimplemented(metadata)
     }
 
@@ -216,3 +273,122 @@ pub trait Visitor<'a>: Sized {
         self.visit_unimplemented(metadata)
     }
 }
+
+macro_rules! map_record {
+    ($( $method:ident($record:ty) ),* $(,)?) => {$(
+        fn $method(self, record: $record, metadata: RecordMetadata) -> Self::Output {
+            (self.func)(RecordVisitor.$method(record, metadata))
+        }
+    )*};
+}
+
+macro_rules! record_adapter {
+    ($visitor:ident, $output:ty, $($bound:tt)+) => {
+        impl<'a, F, T> Visitor<'a> for $visitor<F>
+        where
+            F: $($bound)+,
+        {
+            type Output = $output;
+
+            fn visit_unimplemented(self, metadata: RecordMetadata) -> Self::Output {
+                (self.func)(RecordVisitor.visit_unimplemented(metadata))
+            }
+
+            map_record! {
+                visit_mmap(Mmap<'a>),
+                visit_lost(Lost),
+                visit_comm(Comm<'a>),
+                visit_exit(Exit),
+                visit_throttle(Throttle),
+                visit_unthrottle(Throttle),
+                visit_fork(Fork),
+                visit_read(Read<'a>),
+                visit_sample(Sample<'a>),
+                visit_mmap2(Mmap2<'a>),
+                visit_aux(Aux),
+                visit_itrace_start(ITraceStart),
+                visit_lost_samples(LostSamples),
+                visit_switch(Switch),
+                visit_switch_cpu_wide(SwitchCpuWide),
+                visit_namespaces(Namespaces<'a>),
+                visit_ksymbol(KSymbol<'a>),
+                visit_bpf_event(BpfEvent),
+                visit_cgroup(CGroup<'a>),
+                visit_text_poke(TextPoke<'a>),
+                visit_aux_output_hw_id(AuxOutputHwId),
+                visit_unknown(Cow<'a, [u8]>),
+            }
+        }
+    };
+}
+
+/// A [`Visitor`] that parses a [`Record`] and then passes it to a closure.
+///
+/// This is useful when you want to transform records as they are parsed but
+/// don't want to write a full visitor implementation.
+///
+/// ```
+/// # fn main() -> perf_event_data::parse::ParseResult<()> {
+/// # use perf_event_data::endian::Little;
+/// # use perf_event_data::parse::{ParseConfig, Parser};
+/// use perf_event_data::{MapVisitor, Record};
+///
+/// # let data = perf_event_data::doctest::MMAP;
+/// let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+/// let is_mmap = parser.parse_record(MapVisitor::new(|record| matches!(record, Record::Mmap(_))))?;
+///
+/// assert!(is_mmap);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct MapVisitor<F> {
+    func: F,
+}
+
+impl<F> MapVisitor<F> {
+    /// Create a new `MapVisitor` which calls `func` on every parsed record.
+    pub fn new(func: F) -> Self {
+        Self { func }
+    }
+}
+
+record_adapter!(MapVisitor, T, FnOnce(Record<'a>) -> T);
+
+/// A [`Visitor`] that parses a [`Record`] and then passes it to a closure
+/// which can choose to discard it.
+///
+/// Note that the record is always fully parsed, even if it is then discarded.
+/// If you want to avoid parsing records that you are not interested in then
+/// you will need to implement [`Visitor`] directly.
+///
+/// ```
+/// # fn main() -> perf_event_data::parse::ParseResult<()> {
+/// # use perf_event_data::endian::Little;
+/// # use perf_event_data::parse::{ParseConfig, Parser};
+/// use perf_event_data::{FilterVisitor, Record};
+///
+/// # let data = perf_event_data::doctest::MMAP;
+/// let mut parser = Parser::new(data, ParseConfig::<Little>::default());
+/// let sample = parser.parse_record(FilterVisitor::new(|record| match record {
+///     Record::Sample(sample) => Some(sample),
+///     _ => None,
+/// }))?;
+///
+/// assert!(sample.is_none());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct FilterVisitor<F> {
+    func: F,
+}
+
+impl<F> FilterVisitor<F> {
+    /// Create a new `FilterVisitor` which calls `func` on every parsed record.
+    pub fn new(func: F) -> Self {
+        Self { func }
+    }
+}
+
+record_adapter!(FilterVisitor, Option<T>, FnOnce(Record<'a>) -> Option<T>);
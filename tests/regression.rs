@@ -57,6 +57,45 @@ fn fuzz_with_config(_data: &[u8]) {
     unimplemented!()
 }
 
+/// Run every reproducer in `tests/fixtures/crashes` through the fuzz harnesses.
+///
+/// Crash files emitted by `cargo fuzz` can be copied into that directory
+/// as-is without needing to write a dedicated test for them. All files are
+/// run even if one of them fails, and the names of the failing files are
+/// included in the panic message.
+#[test]
+fn crash_fixtures() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/crashes");
+    let mut entries = std::fs::read_dir(&dir)
+        .expect("failed to read the crash fixtures directory")
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("failed to read the crash fixtures directory");
+    entries.sort();
+
+    let mut failed = Vec::new();
+    for path in entries {
+        if !path.is_file() {
+            continue;
+        }
+
+        let data = std::fs::read(&path).expect("failed to read crash fixture");
+        let result = std::panic::catch_unwind(|| {
+            fuzz_test(&data);
+
+            if cfg!(feature = "arbitrary") {
+                fuzz_with_config(&data);
+            }
+        });
+
+        if result.is_err() {
+            failed.push(path.display().to_string());
+        }
+    }
+
+    assert!(failed.is_empty(), "crash fixtures failed: {failed:#?}");
+}
+
 #[test]
 fn zero_header_size() {
     fuzz_test(&[0, 0, 0, 0, 0, 0, 0, 0]);